@@ -54,6 +54,28 @@ pub fn detach_block_cell(
     txn: &StoreTransaction,
     block: &BlockView,
     cell_set: &mut HamtMap<Byte32, TransactionMeta>,
+) -> Result<(), Error> {
+    let undo = match txn.get_block_undo(&block.hash()) {
+        Some(undo) => undo,
+        // pruned, or attached before the undo data was introduced
+        None => return detach_block_cell_without_undo(txn, block, cell_set),
+    };
+    for tx in block.transactions() {
+        txn.delete_cell_set(&tx.hash())?;
+        cell_set.remove(&tx.hash());
+    }
+    for (tx_hash, meta) in undo {
+        txn.update_cell_set(&tx_hash, &meta.pack())?;
+        cell_set.insert(tx_hash, meta);
+    }
+    txn.delete_block_undo(&block.hash())
+}
+
+// Rebuilds the spent metas from the transactions, which reads all the inputs' source transactions
+fn detach_block_cell_without_undo(
+    txn: &StoreTransaction,
+    block: &BlockView,
+    cell_set: &mut HamtMap<Byte32, TransactionMeta>,
 ) -> Result<(), Error> {
     for tx in block.transactions().iter().rev() {
         txn.delete_cell_set(&tx.hash())?;
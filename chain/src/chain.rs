@@ -1,9 +1,10 @@
 use crate::cell::{attach_block_cell, detach_block_cell};
 use crate::switch::Switch;
+use crate::{ReorgConfig, LOG_TARGET_REORG};
 use ckb_error::{Error, InternalErrorKind};
-use ckb_logger::{self, debug, error, info, log_enabled, trace, warn};
-use ckb_proposal_table::ProposalTable;
-use ckb_shared::shared::Shared;
+use ckb_logger::{self, debug, error, error_target, info, log_enabled, trace, warn, warn_target};
+use ckb_proposal_table::{ProposalTable, ProposalView};
+use ckb_shared::{reorg::ReorgRecord, shared::Shared};
 use ckb_stop_handler::{SignalSender, StopHandler};
 use ckb_store::{ChainStore, StoreTransaction};
 use ckb_types::{
@@ -13,7 +14,7 @@ use ckb_types::{
             ResolvedTransaction,
         },
         service::{Request, DEFAULT_CHANNEL_SIZE, SIGNAL_CHANNEL_SIZE},
-        BlockExt, BlockNumber, BlockView, TransactionMeta,
+        BlockExt, BlockNumber, BlockView, EpochExt, HeaderView, TransactionMeta,
     },
     packed::{Byte32, OutPoint, ProposalShortId},
     prelude::*,
@@ -24,15 +25,22 @@ use ckb_verification::{BlockVerifier, ContextualBlockVerifier, Verifier, VerifyC
 use crossbeam_channel::{self, select, Receiver, Sender};
 use faketime::unix_time_as_millis;
 use im::hashmap::HashMap as HamtMap;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::process::Command;
 use std::sync::Arc;
 use std::{cmp, thread};
 
+// The notify script is run at most once in this interval for the same refused fork
+const REFUSED_FORK_NOTIFY_INTERVAL: u64 = 10 * 60 * 1000; // 10 minutes
+
 type ProcessBlockRequest = Request<(Arc<BlockView>, Switch), Result<bool, Error>>;
+type BlockHashRequest = Request<Byte32, Result<(), Error>>;
 
 #[derive(Clone)]
 pub struct ChainController {
     process_block_sender: Sender<ProcessBlockRequest>,
+    invalidate_block_sender: Sender<BlockHashRequest>,
+    reconsider_block_sender: Sender<BlockHashRequest>,
     stop: StopHandler<()>,
 }
 
@@ -58,10 +66,31 @@ impl ChainController {
                 .into())
         })
     }
+
+    /// Marks the block and its descendants invalid, the main chain switches to the best
+    /// remaining fork if the block is on it.
+    pub fn invalidate_block(&self, hash: Byte32) -> Result<(), Error> {
+        Request::call(&self.invalidate_block_sender, hash).unwrap_or_else(|| {
+            Err(InternalErrorKind::System
+                .reason("Chain service has gone")
+                .into())
+        })
+    }
+
+    /// Undoes `invalidate_block`, the main chain switches to the best fork if it changes.
+    pub fn reconsider_block(&self, hash: Byte32) -> Result<(), Error> {
+        Request::call(&self.reconsider_block_sender, hash).unwrap_or_else(|| {
+            Err(InternalErrorKind::System
+                .reason("Chain service has gone")
+                .into())
+        })
+    }
 }
 
 struct ChainReceivers {
     process_block_receiver: Receiver<ProcessBlockRequest>,
+    invalidate_block_receiver: Receiver<BlockHashRequest>,
+    reconsider_block_receiver: Receiver<BlockHashRequest>,
 }
 
 #[derive(Debug, Default)]
@@ -154,19 +183,53 @@ impl GlobalIndex {
     }
 }
 
+/// The headers known to the node, including the ones whose blocks are not received yet.
+///
+/// It tells whether a block is an ancestor of the assume valid target before the target block
+/// itself arrives, only the stored headers are looked up without it.
+pub trait HeaderIndex: Send + Sync {
+    fn get_ancestor(&self, base: &Byte32, number: BlockNumber) -> Option<HeaderView>;
+}
+
 pub struct ChainService {
     shared: Shared,
     proposal_table: ProposalTable,
+    // Scripts are not executed for this block and its ancestors until it is attached to the
+    // main chain
+    assume_valid_target: Option<Byte32>,
+    header_index: Option<Arc<dyn HeaderIndex>>,
+    reorg_config: ReorgConfig,
+    // When the notify script was run for the refused forks, keyed by their first attached block
+    refused_forks_notified_at: HashMap<Byte32, u64>,
 }
 
 impl ChainService {
     pub fn new(shared: Shared, proposal_table: ProposalTable) -> ChainService {
+        let assume_valid_target = shared
+            .consensus()
+            .assume_valid_target()
+            .map(|target| target.pack())
+            .filter(|target| !shared.store().is_main_chain(target));
         ChainService {
             shared,
             proposal_table,
+            assume_valid_target,
+            header_index: None,
+            reorg_config: ReorgConfig::default(),
+            refused_forks_notified_at: HashMap::new(),
         }
     }
 
+    pub fn with_header_index(mut self, header_index: Arc<dyn HeaderIndex>) -> Self {
+        self.header_index = Some(header_index);
+        self
+    }
+
+    pub fn with_reorg_config(mut self, reorg_config: ReorgConfig) -> Self {
+        self.reorg_config = reorg_config;
+        self
+    }
+
     // remove `allow` tag when https://github.com/crossbeam-rs/crossbeam/issues/404 is solved
     #[allow(clippy::zero_ptr, clippy::drop_copy)]
     pub fn start<S: ToString>(mut self, thread_name: Option<S>) -> ChainController {
@@ -174,6 +237,10 @@ impl ChainService {
             crossbeam_channel::bounded::<()>(SIGNAL_CHANNEL_SIZE);
         let (process_block_sender, process_block_receiver) =
             crossbeam_channel::bounded(DEFAULT_CHANNEL_SIZE);
+        let (invalidate_block_sender, invalidate_block_receiver) =
+            crossbeam_channel::bounded(DEFAULT_CHANNEL_SIZE);
+        let (reconsider_block_sender, reconsider_block_receiver) =
+            crossbeam_channel::bounded(DEFAULT_CHANNEL_SIZE);
 
         // Mainly for test: give a empty thread_name
         let mut thread_builder = thread::Builder::new();
@@ -183,6 +250,8 @@ impl ChainService {
 
         let receivers = ChainReceivers {
             process_block_receiver,
+            invalidate_block_receiver,
+            reconsider_block_receiver,
         };
         let thread = thread_builder
             .spawn(move || loop {
@@ -198,6 +267,24 @@ impl ChainService {
                             error!("process_block_receiver closed");
                             break;
                         },
+                    },
+                    recv(receivers.invalidate_block_receiver) -> msg => match msg {
+                        Ok(Request { responder, arguments: hash }) => {
+                            let _ = responder.send(self.invalidate_block(&hash));
+                        },
+                        _ => {
+                            error!("invalidate_block_receiver closed");
+                            break;
+                        },
+                    },
+                    recv(receivers.reconsider_block_receiver) -> msg => match msg {
+                        Ok(Request { responder, arguments: hash }) => {
+                            let _ = responder.send(self.reconsider_block(&hash));
+                        },
+                        _ => {
+                            error!("reconsider_block_receiver closed");
+                            break;
+                        },
                     }
                 }
             })
@@ -206,6 +293,8 @@ impl ChainService {
 
         ChainController {
             process_block_sender,
+            invalidate_block_sender,
+            reconsider_block_sender,
             stop,
         }
     }
@@ -315,7 +404,7 @@ impl ChainService {
         );
 
         // is_better_than
-        let new_best_block = cannon_total_difficulty > current_total_difficulty;
+        let mut new_best_block = cannon_total_difficulty > current_total_difficulty;
 
         if new_best_block {
             debug!(
@@ -324,8 +413,15 @@ impl ChainService {
                 block.header().hash(),
                 &cannon_total_difficulty - &current_total_difficulty
             );
-            self.find_fork(&mut fork, current_tip_header.number(), &block, ext);
+            self.find_fork(&mut fork, current_tip_header.number(), &block, ext.clone());
+            // a fork deeper than the limit is kept as a fork block
+            if self.exceeds_max_reorg_depth(&fork) {
+                self.refuse_fork(&fork);
+                new_best_block = false;
+            }
+        }
 
+        if new_best_block {
             self.rollback(&fork, &db_txn, &mut cell_set)?;
             // update and verify chain root
             // MUST update index before reconcile_main_chain
@@ -342,6 +438,11 @@ impl ChainService {
         db_txn.commit()?;
 
         if new_best_block {
+            self.check_assume_valid_target(&fork);
+            if fork.has_detached() {
+                self.record_reorg(&fork);
+            }
+
             let tip_header = block.header().to_owned();
             info!(
                 "block: {}, hash: {:#x}, epoch: {:#}, total_diff: {:#x}, txs: {}",
@@ -352,42 +453,14 @@ impl ChainService {
                 block.transactions().len()
             );
 
-            self.update_proposal_table(&fork);
-            let (detached_proposal_id, new_proposals) = self
-                .proposal_table
-                .finalize(origin_proposals, tip_header.number());
-            fork.detached_proposal_id = detached_proposal_id;
-
-            let new_snapshot = self.shared.new_snapshot(
+            self.publish_main_chain(
+                &mut fork,
+                origin_proposals,
                 tip_header,
                 total_difficulty,
                 epoch,
                 cell_set,
-                new_proposals,
             );
-
-            self.shared.store_snapshot(Arc::clone(&new_snapshot));
-
-            if let Err(e) = self.shared.tx_pool_controller().update_tx_pool_for_reorg(
-                fork.detached_blocks().clone(),
-                fork.attached_blocks().clone(),
-                fork.detached_proposal_id().clone(),
-                new_snapshot,
-            ) {
-                error!("notify update_tx_pool_for_reorg error {}", e);
-            }
-            for detached_block in fork.detached_blocks() {
-                if let Err(e) = self
-                    .shared
-                    .tx_pool_controller()
-                    .notify_new_uncle(detached_block.as_uncle())
-                {
-                    error!("notify new_uncle error {}", e);
-                }
-            }
-            if log_enabled!(ckb_logger::Level::Debug) {
-                self.print_chain(10);
-            }
         } else {
             info!(
                 "uncle: {}, hash: {:#x}, epoch: {:#}, total_diff: {:#x}, txs: {}",
@@ -410,6 +483,442 @@ impl ChainService {
         Ok(true)
     }
 
+    // Only the assume valid target and its ancestors skip the scripts, the blocks of the
+    // other forks are fully verified
+    fn is_assumed_valid(&self, txn: &StoreTransaction, block: &BlockView) -> bool {
+        let target = match self.assume_valid_target.as_ref() {
+            Some(target) => target,
+            None => return false,
+        };
+        let ancestor = match self.header_index.as_ref() {
+            Some(header_index) => header_index.get_ancestor(target, block.number()),
+            None => txn.get_block_header(target).and_then(|mut header| {
+                while header.number() > block.number() {
+                    header = txn.get_block_header(&header.parent_hash())?;
+                }
+                Some(header)
+            }),
+        };
+        ancestor.map_or(false, |ancestor| ancestor.hash() == block.hash())
+    }
+
+    // Resume the full verification once the assume valid target is on the main chain
+    fn check_assume_valid_target(&mut self, fork: &ForkChanges) {
+        let reached = self.assume_valid_target.as_ref().map_or(false, |target| {
+            fork.attached_blocks()
+                .iter()
+                .any(|block| &block.hash() == target)
+        });
+        if reached {
+            info!(
+                "assume valid target {:#x} reached, resume script verification",
+                self.assume_valid_target.take().expect("checked above")
+            );
+        }
+    }
+
+    // Updates the proposal table, the snapshot and the tx-pool once the main chain changes
+    // have been committed
+    fn publish_main_chain(
+        &mut self,
+        fork: &mut ForkChanges,
+        origin_proposals: &ProposalView,
+        tip_header: HeaderView,
+        total_difficulty: U256,
+        epoch: EpochExt,
+        cell_set: HamtMap<Byte32, TransactionMeta>,
+    ) {
+        self.update_proposal_table(fork);
+        let (detached_proposal_id, new_proposals) = self
+            .proposal_table
+            .finalize(origin_proposals, tip_header.number());
+        fork.detached_proposal_id = detached_proposal_id;
+
+        let new_snapshot =
+            self.shared
+                .new_snapshot(tip_header, total_difficulty, epoch, cell_set, new_proposals);
+
+        self.shared.store_snapshot(Arc::clone(&new_snapshot));
+
+        if let Err(e) = self.shared.tx_pool_controller().update_tx_pool_for_reorg(
+            fork.detached_blocks().clone(),
+            fork.attached_blocks().clone(),
+            fork.detached_proposal_id().clone(),
+            new_snapshot,
+        ) {
+            error!("notify update_tx_pool_for_reorg error {}", e);
+        }
+        for detached_block in fork.detached_blocks() {
+            if let Err(e) = self
+                .shared
+                .tx_pool_controller()
+                .notify_new_uncle(detached_block.as_uncle())
+            {
+                error!("notify new_uncle error {}", e);
+            }
+        }
+        if log_enabled!(ckb_logger::Level::Debug) {
+            self.print_chain(10);
+        }
+    }
+
+    pub(crate) fn invalidate_block(&mut self, hash: &Byte32) -> Result<(), Error> {
+        let header = self.stored_block_header(hash)?;
+        if header.is_genesis() {
+            return Err(InternalErrorKind::System
+                .reason("the genesis block can not be invalidated")
+                .into());
+        }
+
+        let invalid_blocks = self.descendants(hash);
+        let db_txn = self.shared.store().begin_transaction();
+        for invalid_hash in &invalid_blocks {
+            if let Some(mut ext) = db_txn.get_block_ext(invalid_hash) {
+                ext.verified = Some(false);
+                db_txn.insert_block_ext(invalid_hash, &ext)?;
+            }
+        }
+        // the marks are kept even if the switch below fails
+        db_txn.commit()?;
+        info!(
+            "invalidate block: {}, hash: {:#x}, {} blocks marked invalid",
+            header.number(),
+            hash,
+            invalid_blocks.len()
+        );
+
+        if self.shared.store().is_main_chain(hash) {
+            self.switch_to_best_valid_block(false)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn reconsider_block(&mut self, hash: &Byte32) -> Result<(), Error> {
+        let header = self.stored_block_header(hash)?;
+        let db_txn = self.shared.store().begin_transaction();
+        let is_invalid =
+            |hash: &Byte32| db_txn.get_block_ext(hash).and_then(|ext| ext.verified) == Some(false);
+
+        // the invalid ancestors are reconsidered as well
+        let mut reconsidered = self.descendants(hash);
+        let mut parent_hash = header.parent_hash();
+        while is_invalid(&parent_hash) {
+            let parent = self.stored_block_header(&parent_hash)?;
+            reconsidered.insert(parent_hash);
+            parent_hash = parent.parent_hash();
+        }
+
+        for reconsidered_hash in &reconsidered {
+            if let Some(mut ext) = db_txn.get_block_ext(reconsidered_hash) {
+                if ext.verified == Some(false) {
+                    ext.verified = None;
+                    db_txn.insert_block_ext(reconsidered_hash, &ext)?;
+                }
+            }
+        }
+        db_txn.commit()?;
+        info!("reconsider block: {}, hash: {:#x}", header.number(), hash);
+
+        self.switch_to_best_valid_block(true)
+    }
+
+    // Switches the main chain to the best valid block. A block which fails the verification is
+    // marked invalid by the failed switch, so the next best block is tried until one is valid.
+    // With `only_better`, the main chain is kept unless the best block has more total
+    // difficulty than the tip.
+    fn switch_to_best_valid_block(&mut self, only_better: bool) -> Result<(), Error> {
+        let mut failed: Option<(Byte32, Error)> = None;
+        loop {
+            let db_txn = self.shared.store().begin_transaction();
+            let best_block = self
+                .best_valid_block(&db_txn)
+                .expect("the genesis block is valid");
+            if only_better {
+                let current_total_difficulty = self.shared.snapshot().total_difficulty().to_owned();
+                let better = db_txn
+                    .get_block_ext(&best_block)
+                    .map_or(false, |ext| ext.total_difficulty > current_total_difficulty);
+                if !better {
+                    return Ok(());
+                }
+            }
+            // no block was marked invalid by the last failure, the same switch would fail again
+            if let Some((failed_block, err)) = failed.take() {
+                if failed_block == best_block {
+                    return Err(err);
+                }
+            }
+            match self.switch_main_chain(db_txn, &best_block) {
+                Ok(()) => return Ok(()),
+                Err(err) => {
+                    warn!(
+                        "switch main chain to block {:#x} failed: {:?}",
+                        best_block, err
+                    );
+                    failed = Some((best_block, err));
+                }
+            }
+        }
+    }
+
+    fn stored_block_header(&self, hash: &Byte32) -> Result<HeaderView, Error> {
+        self.shared.store().get_block_header(hash).ok_or_else(|| {
+            InternalErrorKind::System
+                .reason(format!("block {:#x} not found", hash))
+                .into()
+        })
+    }
+
+    // The block itself and all its stored descendants, main chain and forks alike
+    fn descendants(&self, root: &Byte32) -> HashSet<Byte32> {
+        let store = self.shared.store();
+        let mut descendants = HashSet::new();
+        let mut pending = vec![root.to_owned()];
+        while let Some(hash) = pending.pop() {
+            pending.extend(store.get_block_children(&hash));
+            descendants.insert(hash);
+        }
+        descendants
+    }
+
+    // The block with the most total difficulty whose fork from the main chain contains no
+    // invalid block, the first received one wins a tie. The candidates are the fork blocks
+    // and the highest valid main chain block.
+    fn best_valid_block(&self, txn: &StoreTransaction) -> Option<Byte32> {
+        let is_invalid =
+            |hash: &Byte32| txn.get_block_ext(hash).and_then(|ext| ext.verified) == Some(false);
+        let tip_number = self.shared.snapshot().tip_number();
+        let main_chain_best = (0..=tip_number)
+            .rev()
+            .filter_map(|number| txn.get_block_hash(number))
+            .find(|hash| !is_invalid(hash))?;
+
+        let mut candidates: Vec<(Byte32, BlockExt)> = txn
+            .get_fork_block_headers()
+            .into_iter()
+            .map(|header| header.hash())
+            .chain(std::iter::once(main_chain_best))
+            .filter_map(|hash| txn.get_block_ext(&hash).map(|ext| (hash, ext)))
+            .filter(|(_, ext)| ext.verified != Some(false))
+            .collect();
+        candidates.sort_by(|(_, a), (_, b)| {
+            b.total_difficulty
+                .cmp(&a.total_difficulty)
+                .then_with(|| a.received_at.cmp(&b.received_at))
+        });
+
+        candidates.into_iter().map(|(hash, _)| hash).find(|hash| {
+            let mut hash = hash.to_owned();
+            while !txn.is_main_chain(&hash) {
+                if is_invalid(&hash) {
+                    return false;
+                }
+                match txn.get_block_header(&hash) {
+                    Some(header) => hash = header.parent_hash(),
+                    None => return false,
+                }
+            }
+            // the main chain part is valid only up to the invalidated blocks
+            !is_invalid(&hash)
+        })
+    }
+
+    // Switches the main chain to the stored fork ending at `new_tip`, `db_txn` is committed
+    // only if all the attached blocks are valid, otherwise only the invalid marks are. Only the explicit invalidate_block and
+    // reconsider_block calls switch here, so `max_reorg_depth` is deliberately not applied:
+    // the operator asked for the reorg.
+    fn switch_main_chain(
+        &mut self,
+        db_txn: StoreTransaction,
+        new_tip: &Byte32,
+    ) -> Result<(), Error> {
+        let shared_snapshot = Arc::clone(&self.shared.snapshot());
+        let mut cell_set = shared_snapshot.cell_set().clone();
+        let origin_proposals = shared_snapshot.proposals();
+        let current_tip_header = shared_snapshot.tip_header();
+
+        let mut fork = ForkChanges::default();
+        let mut hash = new_tip.to_owned();
+        while !db_txn.is_main_chain(&hash) {
+            let block = db_txn
+                .get_block(&hash)
+                .expect("fork block stored before switch_main_chain");
+            hash = block.parent_hash();
+            fork.attached_blocks.push_front(block);
+        }
+        let latest_common = db_txn
+            .get_block_number(&hash)
+            .expect("main chain block indexed");
+        for number in (latest_common + 1)..=current_tip_header.number() {
+            let detached_block = db_txn
+                .get_block_hash(number)
+                .and_then(|hash| db_txn.get_block(&hash))
+                .expect("main chain block stored before switch_main_chain");
+            fork.detached_blocks.push_back(detached_block);
+        }
+        // blocks which have never been attached are verified when reconciling
+        let mut verified = true;
+        for block in fork.attached_blocks.iter() {
+            let ext = db_txn
+                .get_block_ext(&block.hash())
+                .expect("block ext stored before switch_main_chain");
+            verified = verified && ext.verified == Some(true);
+            if !verified {
+                fork.dirty_exts.push_back(ext);
+            }
+        }
+
+        self.rollback(&fork, &db_txn, &mut cell_set)?;
+        if let Err(err) = self.reconcile_main_chain(&db_txn, &mut fork, Switch::NONE, &mut cell_set)
+        {
+            // `db_txn` is discarded, only the marks of the blocks which failed are kept
+            let invalid_exts: Vec<(Byte32, BlockExt)> = fork
+                .attached_blocks()
+                .iter()
+                .filter_map(|block| {
+                    db_txn
+                        .get_block_ext(&block.hash())
+                        .filter(|ext| ext.verified == Some(false))
+                        .map(|ext| (block.hash(), ext))
+                })
+                .collect();
+            drop(db_txn);
+            let marks_txn = self.shared.store().begin_transaction();
+            for (hash, ext) in &invalid_exts {
+                marks_txn.insert_block_ext(hash, ext)?;
+            }
+            marks_txn.commit()?;
+            return Err(err);
+        }
+
+        let tip_header = self.stored_block_header(new_tip)?;
+        let total_difficulty = db_txn
+            .get_block_ext(new_tip)
+            .expect("block ext stored before switch_main_chain")
+            .total_difficulty;
+        let epoch = db_txn
+            .get_block_epoch(new_tip)
+            .expect("block epoch stored before switch_main_chain");
+        db_txn.insert_tip_header(&tip_header)?;
+        db_txn.insert_current_epoch_ext(&epoch)?;
+        db_txn.commit()?;
+
+        self.check_assume_valid_target(&fork);
+        if fork.has_detached() {
+            self.record_reorg(&fork);
+        }
+        info!(
+            "switch main chain to block: {}, hash: {:#x}, epoch: {:#}, total_diff: {:#x}",
+            tip_header.number(),
+            tip_header.hash(),
+            tip_header.epoch(),
+            total_difficulty,
+        );
+
+        self.publish_main_chain(
+            &mut fork,
+            origin_proposals,
+            tip_header,
+            total_difficulty,
+            epoch,
+            cell_set,
+        );
+        Ok(())
+    }
+
+    fn exceeds_max_reorg_depth(&self, fork: &ForkChanges) -> bool {
+        self.reorg_config
+            .max_reorg_depth
+            .map_or(false, |max_depth| {
+                fork.detached_blocks().len() as BlockNumber > max_depth
+            })
+    }
+
+    fn new_reorg_record(fork: &ForkChanges, refused: bool) -> ReorgRecord {
+        ReorgRecord {
+            timestamp: unix_time_as_millis(),
+            depth: fork.detached_blocks().len() as BlockNumber,
+            detached_blocks: fork.detached_blocks().iter().map(BlockView::hash).collect(),
+            attached_blocks: fork.attached_blocks().iter().map(BlockView::hash).collect(),
+            refused,
+        }
+    }
+
+    fn record_reorg(&self, fork: &ForkChanges) {
+        let record = Self::new_reorg_record(fork, false);
+        warn_target!(
+            LOG_TARGET_REORG,
+            "reorg depth: {}, detached: {:?}, attached: {:?}",
+            record.depth,
+            record.detached_blocks,
+            record.attached_blocks,
+        );
+        self.shared.record_reorg(record);
+    }
+
+    fn refuse_fork(&mut self, fork: &ForkChanges) {
+        let record = Self::new_reorg_record(fork, true);
+        let message = format!(
+            "refused reorg depth {} exceeding max_reorg_depth {:?}, fork tip: {:#x}",
+            record.depth,
+            self.reorg_config.max_reorg_depth,
+            record
+                .attached_blocks
+                .last()
+                .expect("fork attaches the new block"),
+        );
+        error_target!(
+            LOG_TARGET_REORG,
+            "{}, detached: {:?}, attached: {:?}",
+            message,
+            record.detached_blocks,
+            record.attached_blocks,
+        );
+        let fork_root = record
+            .attached_blocks
+            .first()
+            .expect("fork attaches the new block")
+            .to_owned();
+        self.shared.record_reorg(record);
+
+        if let Some(notify_script) = self.reorg_config.notify_script.clone() {
+            if !self.should_notify_refused_fork(fork_root, unix_time_as_millis()) {
+                return;
+            }
+            // The chain service doesn't wait for the script
+            let spawned = thread::Builder::new()
+                .name("ReorgNotify".to_string())
+                .spawn(
+                    move || match Command::new(notify_script).args(&[message]).status() {
+                        Ok(exit_status) => {
+                            info!("send refused reorg to notify script. {}", exit_status);
+                        }
+                        Err(err) => {
+                            error!("failed to run reorg notify script: {}", err);
+                        }
+                    },
+                );
+            if let Err(err) = spawned {
+                error!("failed to spawn reorg notify script: {}", err);
+            }
+        }
+    }
+
+    // A growing refused fork is refused again on every new block, the script is only run
+    // once per `REFUSED_FORK_NOTIFY_INTERVAL` for it
+    pub(crate) fn should_notify_refused_fork(&mut self, fork_root: Byte32, now: u64) -> bool {
+        self.refused_forks_notified_at.retain(|_, notified_at| {
+            now.saturating_sub(*notified_at) < REFUSED_FORK_NOTIFY_INTERVAL
+        });
+        if self.refused_forks_notified_at.contains_key(&fork_root) {
+            false
+        } else {
+            self.refused_forks_notified_at.insert(fork_root, now);
+            true
+        }
+    }
+
     pub(crate) fn update_proposal_table(&mut self, fork: &ForkChanges) {
         for blk in fork.detached_blocks() {
             self.proposal_table.remove(blk.header().number());
@@ -561,6 +1070,7 @@ impl ChainService {
         cell_set: &mut HamtMap<Byte32, TransactionMeta>,
     ) -> Result<(), Error> {
         let txs_verify_cache = self.shared.txs_verify_cache();
+        let script_verify_cache = self.shared.script_verify_cache();
 
         let verified_len = fork.verified_len();
         for b in fork.attached_blocks().iter().take(verified_len) {
@@ -577,6 +1087,11 @@ impl ChainService {
             .iter()
             .zip(fork.attached_blocks.iter().skip(verified_len))
         {
+            let switch = if self.is_assumed_valid(txn, b) {
+                switch | Switch::DISABLE_SCRIPT
+            } else {
+                switch
+            };
             if !switch.disable_all() {
                 if found_error.is_none() {
                     let contextual_block_verifier = ContextualBlockVerifier::new(&verify_context);
@@ -613,6 +1128,7 @@ impl ChainService {
                                 &resolved,
                                 b,
                                 txs_verify_cache.clone(),
+                                Arc::clone(&script_verify_cache),
                                 &future_executor,
                                 switch,
                             ) {
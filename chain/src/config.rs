@@ -0,0 +1,13 @@
+use ckb_types::core::BlockNumber;
+use serde_derive::{Deserialize, Serialize};
+
+/// Chain reorganization policy
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReorgConfig {
+    // Refuse the forks which would detach more blocks than this, unlimited if absent
+    #[serde(default)]
+    pub max_reorg_depth: Option<BlockNumber>,
+    // Script executed in the background when a fork is refused, the first arg is the message
+    #[serde(default)]
+    pub notify_script: Option<String>,
+}
@@ -6,6 +6,11 @@
 
 mod cell;
 pub mod chain;
+mod config;
 pub mod switch;
 #[cfg(test)]
 mod tests;
+
+pub use crate::config::ReorgConfig;
+
+pub(crate) const LOG_TARGET_REORG: &str = "ckb-reorg";
@@ -0,0 +1,142 @@
+use crate::chain::{ChainService, HeaderIndex};
+use crate::switch::Switch;
+use crate::tests::util::{create_transaction, start_chain, MockChain, MockStore};
+use ckb_shared::shared::SharedBuilder;
+use ckb_store::ChainStore;
+use ckb_types::prelude::*;
+use ckb_types::{
+    core::{BlockNumber, HeaderView, ScriptHashType, TransactionView},
+    h256,
+    packed::{Byte32, Script},
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// the headers which are synced before their blocks
+struct MockHeaderIndex(HashMap<Byte32, HeaderView>);
+
+impl HeaderIndex for MockHeaderIndex {
+    fn get_ancestor(&self, base: &Byte32, number: BlockNumber) -> Option<HeaderView> {
+        let mut header = self.0.get(base)?.clone();
+        while header.number() > number {
+            header = self.0.get(&header.parent_hash())?.clone();
+        }
+        Some(header)
+    }
+}
+
+// spends the genesis cell at `index` into an output whose type script is never found
+fn create_invalid_script_transaction(
+    genesis_txs: &[TransactionView],
+    index: usize,
+) -> TransactionView {
+    let tx = create_transaction(&genesis_txs[index].hash(), index as u8);
+    let missing_script = Script::new_builder()
+        .code_hash(h256!("0x1").pack())
+        .hash_type(ScriptHashType::Data.into())
+        .build();
+    let output = tx
+        .output(0)
+        .expect("output 0")
+        .as_builder()
+        .type_(Some(missing_script).pack())
+        .build();
+    tx.as_advanced_builder().set_outputs(vec![output]).build()
+}
+
+#[test]
+fn test_assume_valid_target() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent.clone(), shared.consensus());
+
+    let genesis_txs = shared.consensus().genesis_block().transactions();
+    let tx1 = create_invalid_script_transaction(&genesis_txs, 1);
+    let tx2 = create_invalid_script_transaction(&genesis_txs, 2);
+
+    chain.gen_empty_block(&mock_store);
+    chain.gen_block_with_proposal_txs(vec![tx1.clone(), tx2.clone()], &mock_store);
+    chain.gen_empty_block(&mock_store);
+    chain.gen_block_with_commit_txs(vec![tx1], &mock_store, false);
+    let target = chain.blocks().last().expect("target block").clone();
+    chain.gen_empty_block(&mock_store);
+    chain.gen_block_with_commit_txs(vec![tx2], &mock_store, false);
+
+    // the scripts are executed without assume valid target
+    for block in &chain.blocks()[..3] {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_EPOCH)
+            .expect("process block ok");
+    }
+    assert!(chain_controller
+        .internal_process_block(Arc::new(target.clone()), Switch::DISABLE_EPOCH)
+        .is_err());
+
+    let mut consensus = shared.consensus().clone();
+    consensus.assume_valid_target = Some(target.hash().unpack());
+    let (chain_controller, shared, _) = start_chain(Some(consensus));
+
+    // the target and its ancestors skip the scripts
+    for block in &chain.blocks()[..5] {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_EPOCH)
+            .expect("process block ok");
+    }
+    assert!(shared.store().is_main_chain(&target.hash()));
+
+    // full verification resumes once the target is on the main chain
+    let last = chain.blocks().last().expect("last block").clone();
+    assert!(chain_controller
+        .internal_process_block(Arc::new(last), Switch::DISABLE_EPOCH)
+        .is_err());
+}
+
+#[test]
+fn test_assume_valid_target_on_another_fork() {
+    let (_, shared, parent) = start_chain(None);
+    let genesis_txs = shared.consensus().genesis_block().transactions();
+    let tx = create_invalid_script_transaction(&genesis_txs, 1);
+
+    // only the headers of the chain leading to the target are known
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain = MockChain::new(parent.clone(), shared.consensus());
+    for nonce in 0..4 {
+        chain.gen_empty_block_with_nonce(nonce + 1, &mock_store);
+    }
+    let target = chain.blocks().last().expect("target block").clone();
+    let header_index = MockHeaderIndex(
+        chain
+            .blocks()
+            .iter()
+            .map(|block| (block.hash(), block.header()))
+            .collect(),
+    );
+
+    let fork_store = MockStore::new(&parent, shared.store());
+    let mut fork = MockChain::new(parent.clone(), shared.consensus());
+    fork.gen_empty_block(&fork_store);
+    fork.gen_block_with_proposal_txs(vec![tx.clone()], &fork_store);
+    fork.gen_empty_block(&fork_store);
+    fork.gen_block_with_commit_txs(vec![tx], &fork_store, false);
+
+    let mut consensus = shared.consensus().clone();
+    consensus.assume_valid_target = Some(target.hash().unpack());
+    let (shared, table) = SharedBuilder::default()
+        .consensus(consensus)
+        .build()
+        .unwrap();
+    let chain_controller = ChainService::new(shared, table)
+        .with_header_index(Arc::new(header_index))
+        .start::<&str>(None);
+
+    // the blocks which are not ancestors of the target are fully verified
+    for block in &fork.blocks()[..3] {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_EPOCH)
+            .expect("process block ok");
+    }
+    let last = fork.blocks().last().expect("last block").clone();
+    assert!(chain_controller
+        .internal_process_block(Arc::new(last), Switch::DISABLE_EPOCH)
+        .is_err());
+}
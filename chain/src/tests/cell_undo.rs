@@ -0,0 +1,55 @@
+use crate::switch::Switch;
+use crate::tests::util::{create_transaction, start_chain, MockChain, MockStore};
+use std::sync::Arc;
+
+#[test]
+fn test_cell_set_undo() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain1 = MockChain::new(parent.clone(), shared.consensus());
+
+    let genesis_tx_hash = shared.consensus().genesis_block().transactions()[1].hash();
+    let tx1 = create_transaction(&genesis_tx_hash, 1);
+    let tx2 = create_transaction(&tx1.hash(), 2);
+    chain1.gen_empty_block(&mock_store);
+    chain1.gen_block_with_proposal_txs(vec![tx1.clone(), tx2.clone()], &mock_store);
+    chain1.gen_empty_block(&mock_store);
+    let mut chain2 = chain1.clone();
+    chain1.gen_block_with_commit_txs(vec![tx1.clone(), tx2.clone()], &mock_store, false);
+    chain1.gen_empty_block(&mock_store);
+
+    let mut cell_sets = Vec::new();
+    for block in chain1.blocks() {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block ok");
+        cell_sets.push((block.hash(), shared.snapshot().cell_set().clone()));
+    }
+    assert!(!shared.snapshot().cell_set().contains_key(&genesis_tx_hash));
+
+    // the historical cell sets are rebuilt from the undo data
+    {
+        let snapshot = shared.snapshot();
+        for (hash, cell_set) in &cell_sets {
+            assert_eq!(snapshot.cell_set_at(hash).as_ref(), Some(cell_set));
+        }
+    }
+
+    // the spent metas are restored when the block is detached
+    for _ in 0..3 {
+        chain2.gen_empty_block_with_nonce(100u128, &mock_store);
+    }
+    for block in &chain2.blocks()[3..] {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block ok");
+    }
+    let snapshot = shared.snapshot();
+    assert_eq!(snapshot.tip_header(), &chain2.tip_header());
+    assert_eq!(
+        snapshot.cell_set().get(&genesis_tx_hash),
+        cell_sets[2].1.get(&genesis_tx_hash)
+    );
+    assert!(!snapshot.cell_set().contains_key(&tx1.hash()));
+    assert!(!snapshot.cell_set().contains_key(&tx2.hash()));
+}
@@ -0,0 +1,106 @@
+use crate::tests::util::{create_transaction, start_chain, MockChain, MockStore};
+use ckb_store::ChainStore;
+use ckb_types::{h256, prelude::*};
+use std::sync::Arc;
+
+#[test]
+fn test_invalidate_and_reconsider_block() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain1 = MockChain::new(parent.clone(), shared.consensus());
+    for _ in 0..2 {
+        chain1.gen_empty_block(&mock_store);
+    }
+    let mut chain2 = chain1.clone();
+    for _ in 0..3 {
+        chain1.gen_empty_block(&mock_store);
+    }
+    for _ in 0..2 {
+        chain2.gen_empty_block_with_nonce(100u128, &mock_store);
+    }
+
+    for block in chain1.blocks().iter().chain(chain2.blocks()[2..].iter()) {
+        chain_controller
+            .process_block(Arc::new(block.clone()))
+            .expect("process block ok");
+    }
+    assert_eq!(shared.snapshot().tip_header(), &chain1.tip_header());
+
+    // the main chain switches to the best remaining fork
+    let invalid_block = chain1.blocks()[2].clone();
+    chain_controller
+        .invalidate_block(invalid_block.hash())
+        .expect("invalidate block ok");
+    assert_eq!(shared.snapshot().tip_header(), &chain2.tip_header());
+    for block in &chain1.blocks()[2..] {
+        assert!(!shared.store().is_main_chain(&block.hash()));
+        let ext = shared.store().get_block_ext(&block.hash()).unwrap();
+        assert_eq!(ext.verified, Some(false));
+    }
+
+    // descendants of the invalid block are rejected
+    let mut chain3 = chain1.clone();
+    chain3.gen_empty_block(&mock_store);
+    assert!(chain_controller
+        .process_block(Arc::new(chain3.tip().clone()))
+        .is_err());
+
+    chain_controller
+        .reconsider_block(invalid_block.hash())
+        .expect("reconsider block ok");
+    assert_eq!(shared.snapshot().tip_header(), &chain1.tip_header());
+    for block in &chain1.blocks()[2..] {
+        assert!(shared.store().is_main_chain(&block.hash()));
+    }
+
+    assert!(chain_controller.invalidate_block(parent.hash()).is_err());
+}
+
+#[test]
+fn test_invalidate_block_skips_invalid_fork() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain1 = MockChain::new(parent.clone(), shared.consensus());
+    for _ in 0..2 {
+        chain1.gen_empty_block(&mock_store);
+    }
+    let mut chain2 = chain1.clone();
+    let mut chain3 = chain1.clone();
+    for _ in 0..3 {
+        chain1.gen_empty_block(&mock_store);
+    }
+    for _ in 0..2 {
+        chain2.gen_empty_block_with_nonce(100u128, &mock_store);
+    }
+    // the best fork after chain1, but its first block spends an unknown cell
+    let invalid_tx = create_transaction(&h256!("0x1").pack(), 1);
+    chain3.gen_block_with_commit_txs(vec![invalid_tx], &mock_store, true);
+    for _ in 0..2 {
+        chain3.gen_empty_block_with_nonce(200u128, &mock_store);
+    }
+
+    for block in chain1
+        .blocks()
+        .iter()
+        .chain(chain2.blocks()[2..].iter())
+        .chain(chain3.blocks()[2..].iter())
+    {
+        chain_controller
+            .process_block(Arc::new(block.clone()))
+            .expect("process block ok");
+    }
+    assert_eq!(shared.snapshot().tip_header(), &chain1.tip_header());
+
+    // switching to chain3 fails, its blocks keep the invalid marks and chain2 is the next best
+    chain_controller
+        .invalidate_block(chain1.blocks()[2].hash())
+        .expect("invalidate block ok");
+    assert_eq!(shared.snapshot().tip_header(), &chain2.tip_header());
+    for block in chain1.blocks()[2..]
+        .iter()
+        .chain(chain3.blocks()[2..].iter())
+    {
+        let ext = shared.store().get_block_ext(&block.hash()).unwrap();
+        assert_eq!(ext.verified, Some(false));
+    }
+}
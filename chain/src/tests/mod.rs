@@ -1,6 +1,10 @@
+mod assume_valid;
 mod basic;
 mod block_assembler;
+mod cell_undo;
 mod delay_verify;
 mod find_fork;
+mod invalidate;
+mod reorg;
 mod reward;
 mod util;
@@ -0,0 +1,82 @@
+use crate::chain::ChainService;
+use crate::switch::Switch;
+use crate::tests::util::{start_chain_with_reorg_config, MockChain, MockStore};
+use crate::ReorgConfig;
+use ckb_shared::shared::SharedBuilder;
+use ckb_store::ChainStore;
+use ckb_types::{h256, prelude::*};
+use std::sync::Arc;
+
+#[test]
+fn test_max_reorg_depth() {
+    let reorg_config = ReorgConfig {
+        max_reorg_depth: Some(3),
+        notify_script: None,
+    };
+    let (chain_controller, shared, parent) = start_chain_with_reorg_config(None, reorg_config);
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain1 = MockChain::new(parent.clone(), shared.consensus());
+    for _ in 0..3 {
+        chain1.gen_empty_block_with_diff(100u64, &mock_store);
+    }
+    let mut chain2 = chain1.clone();
+    let mut chain3 = MockChain::new(parent.clone(), shared.consensus());
+
+    for _ in 0..2 {
+        chain1.gen_empty_block_with_diff(100u64, &mock_store);
+    }
+    for block in chain1.blocks() {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block ok");
+    }
+
+    // detaches 2 blocks
+    for _ in 0..3 {
+        chain2.gen_empty_block_with_diff(110u64, &mock_store);
+    }
+    for block in &chain2.blocks()[3..] {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block ok");
+    }
+    let chain2_tip = chain2.tip_header().hash();
+    assert_eq!(shared.snapshot().tip_header().hash(), chain2_tip);
+
+    // detaches 6 blocks
+    for _ in 0..7 {
+        chain3.gen_empty_block_with_diff(100u64, &mock_store);
+    }
+    for block in chain3.blocks() {
+        chain_controller
+            .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+            .expect("process block ok");
+    }
+    let chain3_tip = chain3.tip_header().hash();
+    assert_eq!(shared.snapshot().tip_header().hash(), chain2_tip);
+    assert!(!shared.store().is_main_chain(&chain3_tip));
+    assert!(shared.store().get_block_ext(&chain3_tip).is_some());
+
+    let history = shared.reorg_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].depth, 2);
+    assert!(!history[0].refused);
+    assert_eq!(history[0].attached_blocks.last(), Some(&chain2_tip));
+    assert_eq!(history[1].depth, 6);
+    assert!(history[1].refused);
+    assert_eq!(history[1].attached_blocks.last(), Some(&chain3_tip));
+}
+
+#[test]
+fn test_refused_fork_notify_interval() {
+    let (shared, table) = SharedBuilder::default().build().unwrap();
+    let mut chain_service = ChainService::new(shared, table);
+    let fork1 = h256!("0x1").pack();
+    let fork2 = h256!("0x2").pack();
+
+    assert!(chain_service.should_notify_refused_fork(fork1.clone(), 0));
+    assert!(!chain_service.should_notify_refused_fork(fork1.clone(), 1000));
+    assert!(chain_service.should_notify_refused_fork(fork2, 1000));
+    // 10 minutes later
+    assert!(chain_service.should_notify_refused_fork(fork1, 600_000));
+}
@@ -1,4 +1,5 @@
 use crate::chain::{ChainController, ChainService};
+use crate::ReorgConfig;
 use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
 use ckb_dao::DaoCalculator;
 use ckb_dao_utils::genesis_dao_data;
@@ -41,6 +42,13 @@ pub(crate) fn create_always_success_out_point() -> OutPoint {
 }
 
 pub(crate) fn start_chain(consensus: Option<Consensus>) -> (ChainController, Shared, HeaderView) {
+    start_chain_with_reorg_config(consensus, ReorgConfig::default())
+}
+
+pub(crate) fn start_chain_with_reorg_config(
+    consensus: Option<Consensus>,
+    reorg_config: ReorgConfig,
+) -> (ChainController, Shared, HeaderView) {
     let builder = SharedBuilder::default();
     let (_, _, always_success_script) = always_success_cell();
     let consensus = consensus.unwrap_or_else(|| {
@@ -76,7 +84,7 @@ pub(crate) fn start_chain(consensus: Option<Consensus>) -> (ChainController, Sha
     });
     let (shared, table) = builder.consensus(consensus).build().unwrap();
 
-    let chain_service = ChainService::new(shared.clone(), table);
+    let chain_service = ChainService::new(shared.clone(), table).with_reorg_config(reorg_config);
     let chain_controller = chain_service.start::<&str>(None);
     let parent = {
         let snapshot = shared.snapshot();
@@ -23,6 +23,9 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
                 (cli::CMD_HASHES, Some(sub_matches)) => {
                     subcommand::cli::hashes(Setup::root_dir_from_matches(&matches)?, sub_matches)
                 }
+                (cli::CMD_PROFILE_SCRIPT, Some(sub_matches)) => {
+                    subcommand::cli::profile_script(Setup::profile_script(&matches, sub_matches)?)
+                }
                 _ => unreachable!(),
             };
         }
@@ -42,6 +45,16 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
         (cli::CMD_IMPORT, Some(matches)) => subcommand::import(setup.import(&matches)?),
         (cli::CMD_STATS, Some(matches)) => subcommand::stats(setup.stats(&matches)?),
         (cli::CMD_RESET_DATA, Some(matches)) => subcommand::reset_data(setup.reset_data(&matches)?),
+        (cli::CMD_MIGRATE, Some(matches)) => subcommand::migrate(setup.migrate(&matches)?),
+        (cli::CMD_SNAPSHOT, Some(matches)) => match matches.subcommand() {
+            (cli::CMD_EXPORT, Some(sub_matches)) => {
+                subcommand::snapshot_export(setup.snapshot_export(&sub_matches)?)
+            }
+            (cli::CMD_IMPORT, Some(sub_matches)) => {
+                subcommand::snapshot_import(setup.snapshot_import(&sub_matches)?)
+            }
+            _ => unreachable!(),
+        },
         _ => unreachable!(),
     }
 }
@@ -1,9 +1,11 @@
 mod blake;
 mod hashes;
+mod profile_script;
 mod secp256k1_lock;
 
 pub use blake::{blake160, blake256};
 pub use hashes::hashes;
+pub use profile_script::profile_script;
 pub use secp256k1_lock::secp256k1_lock;
 
 use ckb_app_config::ExitCode;
@@ -0,0 +1,55 @@
+use ckb_app_config::{ExitCode, ProfileScriptArgs};
+use ckb_jsonrpc_types::Transaction;
+use ckb_shared::{shared::SharedBuilder, Snapshot};
+use ckb_types::{core::cell::resolve_transaction, packed, prelude::*};
+use ckb_verification::ScriptVerifier;
+use std::collections::HashSet;
+use std::fs;
+
+pub fn profile_script(args: ProfileScriptArgs) -> Result<(), ExitCode> {
+    let content = fs::read_to_string(&args.source).map_err(|err| {
+        eprintln!("Failed to read {}: {}", args.source.display(), err);
+        ExitCode::IO
+    })?;
+    let tx: Transaction = serde_json::from_str(&content).map_err(|err| {
+        eprintln!("Malformed transaction JSON: {}", err);
+        ExitCode::Cli
+    })?;
+    let tx: packed::Transaction = tx.into();
+
+    let (shared, _table) = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .tx_pool_config(args.config.tx_pool)
+        .build()
+        .map_err(|err| {
+            eprintln!("Profile script error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    let snapshot: &Snapshot = &shared.snapshot();
+
+    let resolved = resolve_transaction(tx.into_view(), &mut HashSet::new(), snapshot, snapshot)
+        .map_err(|err| {
+            eprintln!("Failed to resolve transaction: {:?}", err);
+            ExitCode::Failure
+        })?;
+    let consensus = snapshot.consensus();
+    let max_cycles = consensus.max_block_cycles;
+    let epoch = snapshot.tip_header().epoch().number();
+    let mut verifier = ScriptVerifier::new(&resolved, snapshot);
+    verifier.set_native_crypto(consensus.native_crypto_syscalls_enabled(epoch));
+    verifier.set_exec(consensus.exec_syscall_enabled(epoch));
+    let profiles = verifier.profile(max_cycles).map_err(|err| {
+        eprintln!("Failed to run scripts: {:?}", err);
+        ExitCode::Failure
+    })?;
+
+    let folded_stacks: String = profiles
+        .iter()
+        .map(|profile| profile.folded_stacks())
+        .collect();
+    match args.target {
+        Some(target) => fs::write(&target, folded_stacks)?,
+        None => print!("{}", folded_stacks),
+    }
+    Ok(())
+}
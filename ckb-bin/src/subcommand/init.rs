@@ -5,24 +5,28 @@ use std::path::PathBuf;
 use crate::helper::prompt;
 use ckb_app_config::{ExitCode, InitArgs};
 use ckb_chain_spec::ChainSpec;
-use ckb_db::{db::RocksDB, DBConfig};
+use ckb_db::{db::RocksDB, DBConfig, Result as DBResult};
 use ckb_jsonrpc_types::ScriptHashType;
 use ckb_resource::{
     Resource, TemplateContext, AVAILABLE_SPECS, CKB_CONFIG_FILE_NAME, DEFAULT_SPEC,
     MINER_CONFIG_FILE_NAME, SPEC_DEV_FILE_NAME,
 };
+use ckb_store::{migrations, COLUMNS};
 use ckb_types::{prelude::*, H256};
 
 const DEFAULT_LOCK_SCRIPT_HASH_TYPE: &str = "type";
 const SECP256K1_BLAKE160_SIGHASH_ALL_ARG_LEN: usize = 20 * 2 + 2; // 42 = 20 x 2 + prefix 0x
 
-fn check_db_compatibility(path: PathBuf) {
+fn check_db_compatibility<F>(path: PathBuf, check: F)
+where
+    F: FnOnce(&DBConfig) -> DBResult<()>,
+{
     if path.exists() {
         let config = DBConfig {
             path: path.clone(),
             ..Default::default()
         };
-        if let Some(err) = RocksDB::open_with_error(&config, 1).err() {
+        if let Some(err) = check(&config).err() {
             if err
                 .to_string()
                 .contains("the database version is not matched")
@@ -70,8 +74,14 @@ pub fn init(args: InitArgs) -> Result<(), ExitCode> {
         let db_path = data_dir.join("db");
         let indexer_db_path = data_dir.join("indexer_db");
 
-        check_db_compatibility(db_path);
-        check_db_compatibility(indexer_db_path);
+        // the chain database requires the version of its latest migration, older versions of
+        // the same major version are migrated when the node starts
+        check_db_compatibility(db_path, |config| {
+            RocksDB::pending_migrations(config, COLUMNS, &migrations()).map(|_| ())
+        });
+        check_db_compatibility(indexer_db_path, |config| {
+            RocksDB::open_with_error(config, 1).map(|_| ())
+        });
 
         let in_block_assembler_code_hash = prompt("code hash: ");
         let in_args = prompt("args: ");
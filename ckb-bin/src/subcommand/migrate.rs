@@ -0,0 +1,30 @@
+use ckb_app_config::{ExitCode, MigrateArgs};
+use ckb_db::RocksDB;
+use ckb_store::{chain_db_config, migrations, COLUMNS};
+
+pub fn migrate(args: MigrateArgs) -> Result<(), ExitCode> {
+    let config = chain_db_config(&args.config.db);
+    let migrations = migrations();
+    if args.check {
+        let pending =
+            RocksDB::pending_migrations(&config, COLUMNS, &migrations).map_err(|err| {
+                eprintln!("Migrate error: {:?}", err);
+                ExitCode::Failure
+            })?;
+        if pending.is_empty() {
+            println!("The database is up to date");
+            return Ok(());
+        }
+        for version in pending {
+            println!("Pending migration: {}", version);
+        }
+        return Err(ExitCode::Failure);
+    }
+
+    RocksDB::open_with_migrations(&config, COLUMNS, &migrations).map_err(|err| {
+        eprintln!("Migrate error: {:?}", err);
+        ExitCode::Failure
+    })?;
+    println!("The database is up to date");
+    Ok(())
+}
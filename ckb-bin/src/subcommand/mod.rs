@@ -2,17 +2,21 @@ pub mod cli;
 mod export;
 mod import;
 mod init;
+mod migrate;
 mod miner;
 mod prof;
 mod reset_data;
 mod run;
+mod snapshot;
 mod stats;
 
 pub use self::export::export;
 pub use self::import::import;
 pub use self::init::init;
+pub use self::migrate::migrate;
 pub use self::miner::miner;
 pub use self::prof::profile;
 pub use self::reset_data::reset_data;
 pub use self::run::run;
+pub use self::snapshot::{snapshot_export, snapshot_import};
 pub use self::stats::stats;
@@ -1,7 +1,7 @@
 use crate::helper::{deadlock_detection, wait_for_exit};
 use ckb_app_config::{BlockAssemblerConfig, ExitCode, RunArgs};
 use ckb_build_info::Version;
-use ckb_chain::chain::ChainService;
+use ckb_chain::chain::{ChainService, HeaderIndex};
 use ckb_jsonrpc_types::ScriptHashType;
 use ckb_logger::info_target;
 use ckb_network::{CKBProtocol, NetworkService, NetworkState};
@@ -38,7 +38,10 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     // Verify genesis every time starting node
     verify_genesis(&shared)?;
 
-    let chain_service = ChainService::new(shared.clone(), table);
+    let sync_shared_state = Arc::new(SyncSharedState::new(shared.clone()));
+    let chain_service = ChainService::new(shared.clone(), table)
+        .with_reorg_config(args.config.reorg)
+        .with_header_index(Arc::clone(&sync_shared_state) as Arc<dyn HeaderIndex>);
     let chain_controller = chain_service.start(Some("ChainService"));
     info_target!(
         crate::LOG_TARGET_MAIN,
@@ -46,7 +49,6 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
         shared.genesis_hash()
     );
 
-    let sync_shared_state = Arc::new(SyncSharedState::new(shared.clone()));
     let network_state = Arc::new(
         NetworkState::from_config(args.config.network).expect("Init network state failed"),
     );
@@ -111,7 +113,7 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     .expect("Start network service failed");
 
     let builder = ServiceBuilder::new(&args.config.rpc)
-        .enable_chain(shared.clone())
+        .enable_chain(shared.clone(), Arc::clone(&sync_shared_state))
         .enable_pool(
             shared.clone(),
             sync_shared_state,
@@ -132,6 +134,7 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
             chain_controller.clone(),
         )
         .enable_alert(alert_verifier, alert_notifier, network_controller)
+        .enable_admin(chain_controller.clone())
         .enable_indexer(&args.config.indexer, shared.clone());
     let io_handler = builder.build();
 
@@ -0,0 +1,47 @@
+use ckb_app_config::{ExitCode, SnapshotExportArgs, SnapshotImportArgs};
+use ckb_db::RocksDB;
+use ckb_instrument::{SnapshotExport, SnapshotImport};
+use ckb_shared::shared::SharedBuilder;
+use ckb_store::{chain_db_config, migrations, ChainDB, COLUMNS};
+
+pub fn snapshot_export(args: SnapshotExportArgs) -> Result<(), ExitCode> {
+    let (shared, _) = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .build()
+        .map_err(|err| {
+            eprintln!("Snapshot export error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    let (path, hash) = SnapshotExport::new(shared, args.target)
+        .execute()
+        .map_err(|err| {
+            eprintln!("Snapshot export error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    println!("Snapshot: {}", path.display());
+    println!("Hash: {:#x}", hash);
+    Ok(())
+}
+
+pub fn snapshot_import(args: SnapshotImportArgs) -> Result<(), ExitCode> {
+    // open the store without `Shared`, which would initialize an empty one with the genesis
+    let db =
+        RocksDB::open_with_migrations(&chain_db_config(&args.config.db), COLUMNS, &migrations())
+            .map_err(|err| {
+                eprintln!("Snapshot import error: {:?}", err);
+                ExitCode::Failure
+            })?;
+    let store = ChainDB::new(db, args.config.store);
+    let tip = SnapshotImport::new(store, args.consensus, args.source, args.trusted_hash)
+        .execute()
+        .map_err(|err| {
+            eprintln!("Snapshot import error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    println!(
+        "Imported the snapshot at block {} {}",
+        tip.number(),
+        tip.hash()
+    );
+    Ok(())
+}
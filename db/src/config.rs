@@ -1,3 +1,4 @@
+use rocksdb::{BlockBasedOptions, DBCompressionType, Options, SliceTransform};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -7,4 +8,140 @@ pub struct DBConfig {
     #[serde(default)]
     pub path: PathBuf,
     pub options: Option<HashMap<String, String>>,
+    /// Total size in bytes of the block caches, divided among the column families by their
+    /// `block_cache_share`. RocksDB gives each column family its own default cache if unset.
+    #[serde(default)]
+    pub block_cache_size: Option<usize>,
+    /// Per column family settings, keyed by the column name
+    #[serde(default)]
+    pub columns: HashMap<String, ColumnConfig>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnConfig {
+    /// The fraction of `block_cache_size` used by the column family
+    pub block_cache_share: Option<f64>,
+    /// Bits per key of the bloom filter, no bloom filter if unset or 0
+    pub bloom_filter_bits: Option<u32>,
+    pub compression: Option<CompressionType>,
+    /// Length of the fixed key prefix used by prefix scans
+    pub prefix_len: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl From<CompressionType> for DBCompressionType {
+    fn from(compression: CompressionType) -> Self {
+        match compression {
+            CompressionType::None => DBCompressionType::None,
+            CompressionType::Snappy => DBCompressionType::Snappy,
+            CompressionType::Lz4 => DBCompressionType::Lz4,
+            CompressionType::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+impl ColumnConfig {
+    /// Takes the settings of `default` which are not set in `self`
+    pub fn or(self, default: &ColumnConfig) -> ColumnConfig {
+        ColumnConfig {
+            block_cache_share: self.block_cache_share.or(default.block_cache_share),
+            bloom_filter_bits: self.bloom_filter_bits.or(default.bloom_filter_bits),
+            compression: self.compression.or(default.compression),
+            prefix_len: self.prefix_len.or(default.prefix_len),
+        }
+    }
+}
+
+impl DBConfig {
+    /// Fills the column family settings which are not configured with `defaults`
+    pub fn with_column_defaults(mut self, defaults: HashMap<String, ColumnConfig>) -> Self {
+        for (col, default) in defaults {
+            let column = self.columns.remove(&col).unwrap_or_default().or(&default);
+            self.columns.insert(col, column);
+        }
+        self
+    }
+
+    pub(crate) fn column_options(&self, col: &str) -> Options {
+        let mut opts = Options::default();
+        let column = match self.columns.get(col) {
+            Some(column) => column,
+            None => return opts,
+        };
+
+        let mut block_opts = BlockBasedOptions::default();
+        if let (Some(size), Some(share)) = (self.block_cache_size, column.block_cache_share) {
+            block_opts.set_lru_cache((size as f64 * share.max(0.0).min(1.0)) as usize);
+        }
+        if let Some(bits) = column.bloom_filter_bits.filter(|bits| *bits > 0) {
+            block_opts.set_bloom_filter(bits as i32, false);
+        }
+        opts.set_block_based_table_factory(&block_opts);
+        if let Some(compression) = column.compression {
+            opts.set_compression_type(compression.into());
+        }
+        if let Some(len) = column.prefix_len {
+            opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(len));
+        }
+        opts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_defaults() {
+        let config: DBConfig = toml::from_str(
+            r#"
+            block_cache_size = 1024
+            [columns.1]
+            compression = "zstd"
+            [columns.2]
+            bloom_filter_bits = 0
+            "#,
+        )
+        .unwrap();
+        let mut defaults = HashMap::new();
+        defaults.insert(
+            "1".to_owned(),
+            ColumnConfig {
+                block_cache_share: Some(0.5),
+                compression: Some(CompressionType::Lz4),
+                ..Default::default()
+            },
+        );
+        defaults.insert(
+            "3".to_owned(),
+            ColumnConfig {
+                prefix_len: Some(32),
+                ..Default::default()
+            },
+        );
+
+        let config = config.with_column_defaults(defaults);
+        assert_eq!(config.block_cache_size, Some(1024));
+        assert_eq!(
+            config.columns["1"],
+            ColumnConfig {
+                block_cache_share: Some(0.5),
+                compression: Some(CompressionType::Zstd),
+                ..Default::default()
+            }
+        );
+        assert_eq!(config.columns["2"].bloom_filter_bits, Some(0));
+        assert_eq!(config.columns["3"].prefix_len, Some(32));
+
+        assert!(toml::from_str::<DBConfig>("[columns.1]\nunknown = 1").is_err());
+    }
 }
@@ -1,11 +1,12 @@
+use crate::migration::Migrations;
 use crate::snapshot::RocksDBSnapshot;
 use crate::transaction::RocksDBTransaction;
 use crate::{internal_error, Col, DBConfig, Result};
 use ckb_logger::{info, warn};
 use rocksdb::ops::{Get, GetColumnFamilys, GetPinnedCF, IterateCF, OpenCF, Put, SetOptions};
 use rocksdb::{
-    ffi, ColumnFamily, DBPinnableSlice, IteratorMode, OptimisticTransactionDB,
-    OptimisticTransactionOptions, Options, WriteOptions,
+    ffi, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, IteratorMode,
+    OptimisticTransactionDB, OptimisticTransactionOptions, Options, WriteOptions,
 };
 use std::sync::Arc;
 
@@ -13,6 +14,8 @@ use std::sync::Arc;
 //      - If the data can be migrated at startup automatically: update "x.y.z1" to "x.y.z2".
 //      - If the data can be migrated manually: update "x.y1.z" to "x.y2.0".
 //      - If the data can not be migrated: update "x1.y.z" to "x2.0.0".
+// Databases opened with `RocksDB::open_with_migrations` require the version of their latest
+// migration instead, a format change registers a `Migration` rather than bumping this constant.
 pub(crate) const VERSION_KEY: &str = "db-version";
 pub(crate) const VERSION_VALUE: &str = "0.2200.0";
 
@@ -27,62 +30,7 @@ impl RocksDB {
         ver_key: &str,
         ver_val: &str,
     ) -> Result<Self> {
-        let mut opts = Options::default();
-        opts.create_if_missing(false);
-        opts.create_missing_column_families(true);
-
-        let cfnames: Vec<_> = (0..columns).map(|c| c.to_string()).collect();
-        let cf_options: Vec<&str> = cfnames.iter().map(|n| n as &str).collect();
-
-        let db =
-            OptimisticTransactionDB::open_cf(&opts, &config.path, &cf_options).or_else(|err| {
-                let err_str = err.as_ref();
-                if err_str.starts_with("Invalid argument:")
-                    && err_str.ends_with("does not exist (create_if_missing is false)")
-                {
-                    info!("Initialize a new database");
-                    opts.create_if_missing(true);
-                    let db = OptimisticTransactionDB::open_cf(&opts, &config.path, &cf_options)
-                        .map_err(|err| {
-                            internal_error(format!(
-                                "failed to open a new created database: {}",
-                                err
-                            ))
-                        })?;
-                    db.put(ver_key, ver_val).map_err(|err| {
-                        internal_error(format!("failed to initiate the database: {}", err))
-                    })?;
-                    Ok(db)
-                } else if err.as_ref().starts_with("Corruption:") {
-                    warn!("Repairing the rocksdb since {} ...", err);
-                    let mut repair_opts = Options::default();
-                    repair_opts.create_if_missing(false);
-                    repair_opts.create_missing_column_families(false);
-                    OptimisticTransactionDB::repair(repair_opts, &config.path).map_err(|err| {
-                        internal_error(format!("failed to repair the database: {}", err))
-                    })?;
-                    warn!("Opening the repaired rocksdb ...");
-                    OptimisticTransactionDB::open_cf(&opts, &config.path, &cf_options).map_err(
-                        |err| {
-                            internal_error(format!("failed to open the repaired database: {}", err))
-                        },
-                    )
-                } else {
-                    Err(internal_error(format!(
-                        "failed to open the database: {}",
-                        err
-                    )))
-                }
-            })?;
-
-        if let Some(db_opt) = config.options.as_ref() {
-            let rocksdb_options: Vec<(&str, &str)> = db_opt
-                .iter()
-                .map(|(k, v)| (k.as_str(), v.as_str()))
-                .collect();
-            db.set_options(&rocksdb_options)
-                .map_err(|_| internal_error("failed to set database option"))?;
-        }
+        let db = Self::open_inner(config, columns, ver_key, ver_val)?;
 
         let version_bytes = db
             .get(ver_key)
@@ -120,6 +68,125 @@ impl RocksDB {
         })
     }
 
+    /// Opens the database and applies its pending migrations.
+    ///
+    /// A new database starts at the version of the latest migration. Without any registered
+    /// migration, the database requires `VERSION_VALUE` like `RocksDB::open`.
+    pub fn open_with_migrations(
+        config: &DBConfig,
+        columns: u32,
+        migrations: &Migrations,
+    ) -> Result<Self> {
+        match migrations.latest_version() {
+            Some(latest) => {
+                let db = Self::open_inner(config, columns, VERSION_KEY, &latest.to_string())?;
+                let db = RocksDB {
+                    inner: Arc::new(db),
+                };
+                migrations.migrate(&db)?;
+                Ok(db)
+            }
+            None => Self::open_with_check(config, columns, VERSION_KEY, VERSION_VALUE),
+        }
+    }
+
+    /// Lists the versions of the pending migrations without applying them.
+    pub fn pending_migrations(
+        config: &DBConfig,
+        columns: u32,
+        migrations: &Migrations,
+    ) -> Result<Vec<semver::Version>> {
+        match migrations.latest_version() {
+            Some(latest) => {
+                let db = Self::open_inner(config, columns, VERSION_KEY, &latest.to_string())?;
+                migrations.pending(&RocksDB {
+                    inner: Arc::new(db),
+                })
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Opens the database, a new created one is initiated with the version `ver_val`
+    fn open_inner(
+        config: &DBConfig,
+        columns: u32,
+        ver_key: &str,
+        ver_val: &str,
+    ) -> Result<OptimisticTransactionDB> {
+        let mut opts = Options::default();
+        opts.create_if_missing(false);
+        opts.create_missing_column_families(true);
+
+        let cfnames: Vec<_> = (0..columns).map(|c| c.to_string()).collect();
+        let cf_descriptors = || {
+            cfnames
+                .iter()
+                .map(|name| ColumnFamilyDescriptor::new(name, config.column_options(name)))
+                .collect::<Vec<_>>()
+        };
+
+        let db =
+            OptimisticTransactionDB::open_cf_descriptors(&opts, &config.path, cf_descriptors())
+                .or_else(|err| {
+                    let err_str = err.as_ref();
+                    if err_str.starts_with("Invalid argument:")
+                        && err_str.ends_with("does not exist (create_if_missing is false)")
+                    {
+                        info!("Initialize a new database");
+                        opts.create_if_missing(true);
+                        let db = OptimisticTransactionDB::open_cf_descriptors(
+                            &opts,
+                            &config.path,
+                            cf_descriptors(),
+                        )
+                        .map_err(|err| {
+                            internal_error(format!(
+                                "failed to open a new created database: {}",
+                                err
+                            ))
+                        })?;
+                        db.put(ver_key, ver_val).map_err(|err| {
+                            internal_error(format!("failed to initiate the database: {}", err))
+                        })?;
+                        Ok(db)
+                    } else if err.as_ref().starts_with("Corruption:") {
+                        warn!("Repairing the rocksdb since {} ...", err);
+                        let mut repair_opts = Options::default();
+                        repair_opts.create_if_missing(false);
+                        repair_opts.create_missing_column_families(false);
+                        OptimisticTransactionDB::repair(repair_opts, &config.path).map_err(
+                            |err| internal_error(format!("failed to repair the database: {}", err)),
+                        )?;
+                        warn!("Opening the repaired rocksdb ...");
+                        OptimisticTransactionDB::open_cf_descriptors(
+                            &opts,
+                            &config.path,
+                            cf_descriptors(),
+                        )
+                        .map_err(|err| {
+                            internal_error(format!("failed to open the repaired database: {}", err))
+                        })
+                    } else {
+                        Err(internal_error(format!(
+                            "failed to open the database: {}",
+                            err
+                        )))
+                    }
+                })?;
+
+        if let Some(db_opt) = config.options.as_ref() {
+            let rocksdb_options: Vec<(&str, &str)> = db_opt
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            db.set_options(&rocksdb_options)
+                .map_err(|_| internal_error("failed to set database option"))?;
+        }
+
+        Ok(db)
+    }
+
     // TODO Change `panic(...)` to `Result<...>`
     pub fn open(config: &DBConfig, columns: u32) -> Self {
         Self::open_with_check(config, columns, VERSION_KEY, VERSION_VALUE)
@@ -225,6 +292,7 @@ mod tests {
                 opts.insert("disable_auto_compactions".to_owned(), "true".to_owned());
                 opts
             }),
+            ..Default::default()
         };
         RocksDB::open(&config, 2); // no panic
     }
@@ -243,6 +311,7 @@ mod tests {
                 opts.insert("letsrock".to_owned(), "true".to_owned());
                 opts
             }),
+            ..Default::default()
         };
         RocksDB::open(&config, 2); // panic
     }
@@ -10,12 +10,14 @@ use std::result;
 pub mod config;
 pub mod db;
 pub mod iter;
+pub mod migration;
 pub mod snapshot;
 pub mod transaction;
 
 pub use crate::config::DBConfig;
 pub use crate::db::RocksDB;
 pub use crate::iter::{DBIterator, Direction};
+pub use crate::migration::{Migration, Migrations};
 pub use crate::snapshot::RocksDBSnapshot;
 pub use crate::transaction::{RocksDBTransaction, RocksDBTransactionSnapshot};
 pub use rocksdb::{DBPinnableSlice, DBVector, Error as DBError};
@@ -0,0 +1,246 @@
+//! Database schema migrations
+//!
+//! A migration upgrades the data in place to the database version it is registered with.
+//! Migrations run in the order of their versions, and the version stored in the database is
+//! updated after each one, so an interrupted upgrade resumes from the first unfinished
+//! migration.
+
+use crate::db::VERSION_KEY;
+use crate::{internal_error, Result, RocksDB};
+use ckb_logger::info;
+use rocksdb::ops::{Get, Put};
+use semver::Version;
+use std::collections::BTreeMap;
+
+/// A data migration of the database.
+///
+/// A migration may be interrupted and run again, so it must be idempotent: running it on a
+/// database which it has already migrated, partially or fully, must give the same result.
+pub trait Migration: Send + Sync {
+    /// The database version after the migration, in semver.
+    fn version(&self) -> &str;
+
+    /// Migrates the data, calling `progress` with the done and total amount of work.
+    fn migrate(&self, db: &RocksDB, progress: &dyn Fn(u64, u64)) -> Result<()>;
+}
+
+/// The ordered registry of the migrations of a database.
+pub struct Migrations {
+    migrations: BTreeMap<Version, Box<dyn Migration>>,
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        Migrations {
+            migrations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a migration, panics if its version is malformed or already registered.
+    pub fn add_migration(&mut self, migration: Box<dyn Migration>) {
+        let version = Version::parse(migration.version())
+            .unwrap_or_else(|err| panic!("migration version is malformed: {}", err));
+        if self.migrations.insert(version.clone(), migration).is_some() {
+            panic!("migration {} is registered twice", version);
+        }
+    }
+
+    /// The version of the database after all the migrations.
+    pub fn latest_version(&self) -> Option<&Version> {
+        self.migrations.keys().next_back()
+    }
+
+    /// The versions of the migrations which have not been applied to the database yet.
+    pub fn pending(&self, db: &RocksDB) -> Result<Vec<Version>> {
+        let current = db_version(db)?;
+        self.check_version(&current)?;
+        Ok(self
+            .migrations
+            .keys()
+            .filter(|version| **version > current)
+            .cloned()
+            .collect())
+    }
+
+    /// Applies the pending migrations in order.
+    pub fn migrate(&self, db: &RocksDB) -> Result<()> {
+        let current = db_version(db)?;
+        self.check_version(&current)?;
+
+        for (version, migration) in self.migrations.iter().filter(|(v, _)| **v > current) {
+            info!("Migrating the database to {} ...", version);
+            migration.migrate(db, &|done, total| {
+                info!("Migrating the database to {}: {}/{}", version, done, total);
+            })?;
+            set_db_version(db, version)?;
+            info!("Migrated the database to {}", version);
+        }
+        Ok(())
+    }
+
+    // The database can be migrated to the latest version only within the same major version
+    fn check_version(&self, current: &Version) -> Result<()> {
+        if let Some(latest) = self.latest_version() {
+            if current.major != latest.major {
+                return Err(internal_error(format!(
+                    "the database version is not matched, require {} but it's {}",
+                    latest, current
+                )));
+            } else if current > latest {
+                return Err(internal_error(format!(
+                    "the database version {} is newer than the latest known version {}",
+                    current, latest
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn db_version(db: &RocksDB) -> Result<Version> {
+    let version_bytes = db
+        .inner
+        .get(VERSION_KEY)
+        .map_err(|err| internal_error(format!("failed to check the version of database: {}", err)))?
+        .ok_or_else(|| internal_error("version info about database is lost"))?;
+    let version_str = String::from_utf8_lossy(&version_bytes);
+    Version::parse(&version_str)
+        .map_err(|err| internal_error(format!("database version is malformed: {}", err)))
+}
+
+fn set_db_version(db: &RocksDB, version: &Version) -> Result<()> {
+    db.inner
+        .put(VERSION_KEY, version.to_string())
+        .map_err(|err| internal_error(format!("Failed to update database version: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DBConfig;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Doubles every value of column "0", deliberately not idempotent so reruns are visible
+    struct DoubleValues {
+        version: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Migration for DoubleValues {
+        fn version(&self) -> &str {
+            self.version
+        }
+
+        fn migrate(&self, db: &RocksDB, progress: &dyn Fn(u64, u64)) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let mut items = Vec::new();
+            db.traverse("0", |key, value| {
+                items.push((key.to_vec(), value.to_vec()));
+                Ok(())
+            })?;
+            let txn = db.transaction();
+            for (done, (key, value)) in items.iter().enumerate() {
+                txn.put("0", key, &[value.as_slice(), value.as_slice()].concat())?;
+                progress(done as u64 + 1, items.len() as u64);
+            }
+            txn.commit()
+        }
+    }
+
+    fn migrations(versions: &[&'static str], runs: &Arc<AtomicUsize>) -> Migrations {
+        let mut migrations = Migrations::new();
+        for &version in versions {
+            migrations.add_migration(Box::new(DoubleValues {
+                version,
+                runs: Arc::clone(runs),
+            }));
+        }
+        migrations
+    }
+
+    // A database at `version` holding a single value in column "0"
+    fn fixture(prefix: &str, version: &str) -> (tempfile::TempDir, DBConfig) {
+        let tmp_dir = tempfile::Builder::new().prefix(prefix).tempdir().unwrap();
+        let config = DBConfig {
+            path: tmp_dir.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+        let db = RocksDB::open_with_check(&config, 1, VERSION_KEY, version).unwrap();
+        let txn = db.transaction();
+        txn.put("0", &[0], &[1]).unwrap();
+        txn.commit().unwrap();
+        (tmp_dir, config)
+    }
+
+    #[test]
+    fn test_migrate_in_order() {
+        let (_tmp_dir, config) = fixture("test_migrate_in_order", "0.1.0");
+        let runs = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&["0.3.0", "0.1.0", "0.2.0"], &runs);
+
+        let db = RocksDB::open_with_migrations(&config, 1, &migrations).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(db.get_pinned("0", &[0]).unwrap().unwrap().as_ref(), &[1; 4]);
+        assert_eq!(db_version(&db).unwrap(), Version::parse("0.3.0").unwrap());
+        assert!(migrations.pending(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_only_once() {
+        let (_tmp_dir, config) = fixture("test_migrate_only_once", "0.1.0");
+        let runs = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&["0.2.0"], &runs);
+
+        RocksDB::open_with_migrations(&config, 1, &migrations).unwrap();
+        let db = RocksDB::open_with_migrations(&config, 1, &migrations).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(db.get_pinned("0", &[0]).unwrap().unwrap().as_ref(), &[1; 2]);
+    }
+
+    #[test]
+    fn test_new_database_needs_no_migration() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_new_database_needs_no_migration")
+            .tempdir()
+            .unwrap();
+        let config = DBConfig {
+            path: tmp_dir.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+        let runs = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&["0.1.0", "0.2.0"], &runs);
+
+        let db = RocksDB::open_with_migrations(&config, 1, &migrations).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        assert_eq!(db_version(&db).unwrap(), Version::parse("0.2.0").unwrap());
+    }
+
+    #[test]
+    fn test_newer_database_is_refused() {
+        let (_tmp_dir, config) = fixture("test_newer_database_is_refused", "0.3.0");
+        let runs = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&["0.2.0"], &runs);
+
+        assert!(RocksDB::open_with_migrations(&config, 1, &migrations).is_err());
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_pending_checks_major_version() {
+        let (_tmp_dir, config) = fixture("test_pending_checks_major_version", "1.0.0");
+        let runs = Arc::new(AtomicUsize::new(0));
+        let migrations = migrations(&["0.2.0"], &runs);
+
+        let err = RocksDB::pending_migrations(&config, 1, &migrations).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the database version is not matched"));
+    }
+}
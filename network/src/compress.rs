@@ -1,17 +1,105 @@
+use crate::MAX_FRAME_LENGTH;
 use bytes::{Bytes, BytesMut};
 use ckb_logger::debug;
+use serde_derive::{Deserialize, Serialize};
 use snap::{Decoder as SnapDecoder, Encoder as SnapEncoder};
 
-use std::io;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
 
-const COMPRESSION_SIZE_THRESHOLD: usize = 1024;
+pub(crate) const DEFAULT_COMPRESSION_SIZE_THRESHOLD: usize = 1024;
 const UNCOMPRESS_FLAG: u8 = 0b0000_0000;
 const COMPRESS_FLAG: u8 = 0b1000_0000;
+const CODEC_MASK: u8 = 0b0111_0000;
+const CODEC_SHIFT: u8 = 4;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression formats
+///
+/// Snappy is the legacy format and every peer can decode it, other formats are only used
+/// after the remote peer announces them through identify flags.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    Snappy,
+    Zstd,
+    Lz4,
+}
+
+impl CompressionType {
+    fn codec_bits(self) -> u8 {
+        let id = match self {
+            CompressionType::Snappy => 0,
+            CompressionType::Zstd => 1,
+            CompressionType::Lz4 => 2,
+        };
+        id << CODEC_SHIFT
+    }
+
+    fn from_codec_bits(flag: u8) -> Option<Self> {
+        match (flag & CODEC_MASK) >> CODEC_SHIFT {
+            0 => Some(CompressionType::Snappy),
+            1 => Some(CompressionType::Zstd),
+            2 => Some(CompressionType::Lz4),
+            _ => None,
+        }
+    }
+
+    fn encode(self, input: &[u8]) -> Result<Vec<u8>, io::Error> {
+        match self {
+            CompressionType::Snappy => SnapEncoder::new()
+                .compress_vec(input)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            CompressionType::Zstd => zstd::stream::encode_all(input, ZSTD_LEVEL),
+            CompressionType::Lz4 => lz4::block::compress(input, None, true),
+        }
+    }
+
+    fn decode(self, input: &[u8]) -> Result<Vec<u8>, io::Error> {
+        match self {
+            CompressionType::Snappy => SnapDecoder::new()
+                .decompress_vec(input)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            CompressionType::Zstd => {
+                // Limit the output size, the frame may lie about its content size
+                let mut output = Vec::new();
+                zstd::stream::Decoder::new(input)?
+                    .take(MAX_FRAME_LENGTH as u64 + 1)
+                    .read_to_end(&mut output)?;
+                if output.len() > MAX_FRAME_LENGTH {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                Ok(output)
+            }
+            CompressionType::Lz4 => {
+                // The uncompressed size is prepended as a little endian i32
+                if input.len() < 4 {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                let size = u32::from(input[0])
+                    | u32::from(input[1]) << 8
+                    | u32::from(input[2]) << 16
+                    | u32::from(input[3]) << 24;
+                if size as usize > MAX_FRAME_LENGTH {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                lz4::block::decompress(input, None)
+            }
+        }
+    }
+}
+
+impl Default for CompressionType {
+    fn default() -> Self {
+        CompressionType::Snappy
+    }
+}
 
 /// Compressed decompression structure
 ///
-/// If you want to support multiple compression formats in the future,
-/// you can simply think that 0b1000 is in snappy format and 0b0000 is in uncompressed format.
+/// The highest bit of the first byte tells whether the payload is compressed, the next
+/// three bits select the format. Old nodes only check the highest bit and assume snappy,
+/// so `0b1000` is still snappy and other formats must be negotiated before use.
 ///
 /// # Message in Bytes:
 ///
@@ -19,7 +107,8 @@ const COMPRESS_FLAG: u8 = 0b1000_0000;
 /// | Bytes | Type | Function                                       |
 /// |-------+------+------------------------------------------------|
 /// |   0   |  u1  | Compress: true 1, false 0                      |
-/// |       |  u7  | Reserved                                       |
+/// |       |  u3  | Codec: snappy 0, zstd 1, lz4 2                 |
+/// |       |  u4  | Reserved                                       |
 /// +-------+------+------------------------------------------------+
 /// |  1~   |      | Payload (Serialized Data with Compress)        |
 /// +-------+------+------------------------------------------------+
@@ -41,17 +130,17 @@ impl Message {
         Self { inner: data }
     }
 
-    /// Compress message
-    fn compress(mut self) -> Bytes {
-        if self.inner.len() > COMPRESSION_SIZE_THRESHOLD {
+    /// Compress message if its payload is larger than threshold
+    fn compress(mut self, codec: CompressionType, threshold: usize) -> Bytes {
+        if self.inner.len() > threshold {
             let input = self.inner.split_off(1);
-            match SnapEncoder::new().compress_vec(&input) {
+            match codec.encode(&input) {
                 Ok(res) => {
                     self.inner.unsplit(BytesMut::from(res));
-                    self.set_compress_flag();
+                    self.set_compress_flag(codec);
                 }
                 Err(e) => {
-                    debug!("snappy compress error: {}", e);
+                    debug!("{:?} compress error: {}", codec, e);
                     self.inner.unsplit(input);
                 }
             }
@@ -64,10 +153,17 @@ impl Message {
         if self.inner.is_empty() {
             Err(io::ErrorKind::InvalidData.into())
         } else if self.compress_flag() {
-            match SnapDecoder::new().decompress_vec(&self.inner[1..]) {
+            let codec = match self.codec() {
+                Some(codec) => codec,
+                None => {
+                    debug!("unknown compress codec flag: {:#010b}", self.inner[0]);
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+            };
+            match codec.decode(&self.inner[1..]) {
                 Ok(res) => Ok(Bytes::from(res)),
                 Err(e) => {
-                    debug!("snappy decompress error: {:?}", e);
+                    debug!("{:?} decompress error: {:?}", codec, e);
                     Err(io::ErrorKind::InvalidData.into())
                 }
             }
@@ -77,18 +173,73 @@ impl Message {
         }
     }
 
-    fn set_compress_flag(&mut self) {
-        self.inner[0] = COMPRESS_FLAG;
+    fn set_compress_flag(&mut self, codec: CompressionType) {
+        self.inner[0] = COMPRESS_FLAG | codec.codec_bits();
     }
 
     fn compress_flag(&self) -> bool {
         (self.inner[0] & COMPRESS_FLAG) != 0
     }
+
+    fn codec(&self) -> Option<CompressionType> {
+        CompressionType::from_codec_bits(self.inner[0])
+    }
+}
+
+/// Compression statistics of a protocol
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompressionStat {
+    pub messages: u64,
+    pub compressed_messages: u64,
+    pub raw_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+impl CompressionStat {
+    /// The ratio of sent bytes to raw bytes, lower is better
+    pub fn ratio(&self) -> f64 {
+        if self.raw_bytes == 0 {
+            1.0
+        } else {
+            self.compressed_bytes as f64 / self.raw_bytes as f64
+        }
+    }
+}
+
+/// Compression statistics of a protocol updated without locking
+#[derive(Debug, Default)]
+pub(crate) struct AtomicCompressionStat {
+    messages: AtomicU64,
+    compressed_messages: AtomicU64,
+    raw_bytes: AtomicU64,
+    compressed_bytes: AtomicU64,
+}
+
+impl AtomicCompressionStat {
+    pub(crate) fn record(&self, raw_size: usize, compressed_size: usize) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        // compressed output contains the one byte flag
+        if compressed_size <= raw_size {
+            self.compressed_messages.fetch_add(1, Ordering::Relaxed);
+        }
+        self.raw_bytes.fetch_add(raw_size as u64, Ordering::Relaxed);
+        self.compressed_bytes
+            .fetch_add(compressed_size as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn load(&self) -> CompressionStat {
+        CompressionStat {
+            messages: self.messages.load(Ordering::Relaxed),
+            compressed_messages: self.compressed_messages.load(Ordering::Relaxed),
+            raw_bytes: self.raw_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// Compress data
-pub fn compress(src: Bytes) -> Bytes {
-    Message::from_raw(src).compress()
+pub fn compress(src: Bytes, codec: CompressionType, threshold: usize) -> Bytes {
+    Message::from_raw(src).compress(codec, threshold)
 }
 
 /// Decompress data
@@ -98,11 +249,15 @@ pub fn decompress(src: BytesMut) -> Result<Bytes, io::Error> {
 
 #[cfg(test)]
 mod test {
-    use super::{Bytes, Message, COMPRESSION_SIZE_THRESHOLD};
+    use super::{
+        compress, decompress, Bytes, BytesMut, CompressionType, Message,
+        DEFAULT_COMPRESSION_SIZE_THRESHOLD,
+    };
 
     #[test]
     fn test_no_need_compress() {
-        let cmp_data = Message::from_raw(Bytes::from("1222")).compress();
+        let cmp_data = Message::from_raw(Bytes::from("1222"))
+            .compress(CompressionType::Snappy, DEFAULT_COMPRESSION_SIZE_THRESHOLD);
 
         let msg = Message::from_compressed(cmp_data.into());
 
@@ -115,14 +270,50 @@ mod test {
 
     #[test]
     fn test_compress_and_decompress() {
-        let raw_data = Bytes::from(vec![1; COMPRESSION_SIZE_THRESHOLD + 1]);
-        let cmp_data = Message::from_raw(raw_data.clone()).compress();
+        for codec in &[
+            CompressionType::Snappy,
+            CompressionType::Zstd,
+            CompressionType::Lz4,
+        ] {
+            let raw_data = Bytes::from(vec![1; DEFAULT_COMPRESSION_SIZE_THRESHOLD + 1]);
+            let cmp_data = Message::from_raw(raw_data.clone())
+                .compress(*codec, DEFAULT_COMPRESSION_SIZE_THRESHOLD);
 
-        let msg = Message::from_compressed(cmp_data.into());
-        assert!(msg.compress_flag());
+            let msg = Message::from_compressed(cmp_data.into());
+            assert!(msg.compress_flag());
+            assert_eq!(msg.codec(), Some(*codec));
 
-        let demsg = msg.decompress().unwrap();
+            let demsg = msg.decompress().unwrap();
 
-        assert_eq!(raw_data, demsg)
+            assert_eq!(raw_data, demsg)
+        }
+    }
+
+    #[test]
+    fn test_snappy_compatible_with_legacy_flag() {
+        let raw_data = Bytes::from(vec![1; DEFAULT_COMPRESSION_SIZE_THRESHOLD + 1]);
+        let cmp_data = compress(
+            raw_data,
+            CompressionType::Snappy,
+            DEFAULT_COMPRESSION_SIZE_THRESHOLD,
+        );
+        assert_eq!(cmp_data[0], 0b1000_0000);
+    }
+
+    #[test]
+    fn test_custom_threshold() {
+        let raw_data = Bytes::from(vec![1; 64]);
+        let cmp_data = compress(raw_data.clone(), CompressionType::Zstd, 32);
+        assert_eq!(
+            Message::from_compressed(cmp_data.clone().into()).codec(),
+            Some(CompressionType::Zstd)
+        );
+        assert_eq!(decompress(cmp_data.into()).unwrap(), raw_data);
+    }
+
+    #[test]
+    fn test_unknown_codec() {
+        let data = BytesMut::from(&[0b1111_0000, 1, 2, 3][..]);
+        assert!(decompress(data).is_err());
     }
 }
@@ -1,5 +1,8 @@
 use crate::{
+    compress::{CompressionType, DEFAULT_COMPRESSION_SIZE_THRESHOLD},
     errors::{ConfigError, Error},
+    protocols::pnet::{PreSharedKey, PSK_LENGTH},
+    proxy::Socks5Proxy,
     PeerId, DEFAULT_SEND_BUFFER,
 };
 use ckb_logger::info;
@@ -19,6 +22,9 @@ use std::path::PathBuf;
 pub struct NetworkConfig {
     #[serde(default)]
     pub whitelist_only: bool,
+    // Only connect to peers which own the same pre-shared key
+    #[serde(default)]
+    pub private_network: bool,
     pub max_peers: u32,
     pub max_outbound_peers: u32,
     #[serde(default)]
@@ -43,6 +49,48 @@ pub struct NetworkConfig {
     pub bootnode_mode: bool,
     // Max send buffer size
     pub max_send_buffer: Option<usize>,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    // Route all outbound connections through this proxy, e.g. "socks5://127.0.0.1:9050"
+    pub proxy: Option<String>,
+    // Only advertise `public_addresses`, never listen addresses or observed addresses
+    #[serde(default)]
+    pub hide_listen_addresses: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct CompressionConfig {
+    // Only compress messages larger than this size
+    pub threshold: Option<usize>,
+    // Compression formats in order of preference, snappy is always supported
+    #[serde(default)]
+    pub codecs: Vec<CompressionType>,
+}
+
+impl CompressionConfig {
+    pub fn threshold(&self) -> usize {
+        self.threshold.unwrap_or(DEFAULT_COMPRESSION_SIZE_THRESHOLD)
+    }
+
+    pub fn codecs(&self) -> Vec<CompressionType> {
+        if self.codecs.is_empty() {
+            vec![
+                CompressionType::Zstd,
+                CompressionType::Lz4,
+                CompressionType::Snappy,
+            ]
+        } else {
+            self.codecs.clone()
+        }
+    }
+
+    /// Pick the most preferred format which the remote peer supports
+    pub fn negotiate<F: Fn(CompressionType) -> bool>(&self, remote_supports: F) -> CompressionType {
+        self.codecs()
+            .into_iter()
+            .find(|codec| *codec == CompressionType::Snappy || remote_supports(*codec))
+            .unwrap_or(CompressionType::Snappy)
+    }
 }
 
 fn generate_random_key() -> [u8; 32] {
@@ -55,6 +103,18 @@ fn generate_random_key() -> [u8; 32] {
     }
 }
 
+// Split `/ip4/.../p2p/<peer_id>` into peer id and address
+fn split_peer_id(addr: &Multiaddr) -> Result<(PeerId, Multiaddr), Error> {
+    let mut addr = addr.to_owned();
+    let peer_id = match addr.pop() {
+        Some(Protocol::P2p(key)) => {
+            PeerId::from_bytes(key.into_bytes()).map_err(|_| ConfigError::BadAddress)?
+        }
+        _ => return Err(ConfigError::BadAddress.into()),
+    };
+    Ok((peer_id, addr))
+}
+
 impl NetworkConfig {
     pub fn secret_key_path(&self) -> PathBuf {
         let mut path = self.path.clone();
@@ -62,12 +122,24 @@ impl NetworkConfig {
         path
     }
 
+    pub fn psk_path(&self) -> PathBuf {
+        let mut path = self.path.clone();
+        path.push("psk");
+        path
+    }
+
     pub fn peer_store_path(&self) -> PathBuf {
         let mut path = self.path.clone();
         path.push("peer_store");
         path
     }
 
+    pub fn reserved_peers_path(&self) -> PathBuf {
+        let mut path = self.path.clone();
+        path.push("reserved_peers");
+        path
+    }
+
     pub fn create_dir_if_not_exists(&self) -> Result<(), Error> {
         if !self.path.exists() {
             fs::create_dir(&self.path)?;
@@ -123,34 +195,68 @@ impl NetworkConfig {
         }
     }
 
-    pub fn whitelist_peers(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
-        let mut peers = Vec::with_capacity(self.whitelist_peers.len());
-        for addr_str in &self.whitelist_peers {
-            let mut addr = addr_str.to_owned();
-            let peer_id = match addr.pop() {
-                Some(Protocol::P2p(key)) => {
-                    PeerId::from_bytes(key.into_bytes()).map_err(|_| ConfigError::BadAddress)?
-                }
-                _ => return Err(ConfigError::BadAddress.into()),
-            };
-            peers.push((peer_id, addr))
+    /// Read the pre-shared key when private network mode is enabled.
+    ///
+    /// The key must be distributed to every member, so it is never generated automatically.
+    pub fn fetch_psk(&self) -> Result<Option<PreSharedKey>, Error> {
+        if !self.private_network {
+            return Ok(None);
         }
-        Ok(peers)
+        let path = self.psk_path();
+        let mut file = fs::File::open(&path).map_err(|err| {
+            info!("open private network key file {:?} failed: {}", path, err);
+            ConfigError::InvalidPsk
+        })?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        if buf.len() != PSK_LENGTH {
+            return Err(ConfigError::InvalidPsk.into());
+        }
+        let mut psk = [0u8; PSK_LENGTH];
+        psk.copy_from_slice(&buf);
+        Ok(Some(psk))
+    }
+
+    pub fn whitelist_peers(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
+        self.whitelist_peers.iter().map(split_peer_id).collect()
     }
 
     pub fn bootnodes(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
-        let mut peers = Vec::with_capacity(self.bootnodes.len());
-        for addr_str in &self.bootnodes {
-            let mut addr = addr_str.to_owned();
-            let peer_id = match addr.pop() {
-                Some(Protocol::P2p(key)) => {
-                    PeerId::from_bytes(key.into_bytes()).map_err(|_| ConfigError::BadAddress)?
-                }
-                _ => return Err(ConfigError::BadAddress.into()),
-            };
-            peers.push((peer_id, addr));
-        }
-        Ok(peers)
+        self.bootnodes.iter().map(split_peer_id).collect()
+    }
+
+    /// Read reserved peers added at runtime, one `/ip4/.../p2p/<peer_id>` address per line.
+    pub fn read_reserved_peers(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
+        let path = self.reserved_peers_path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Ok(Vec::new()),
+        };
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.parse::<Multiaddr>()
+                    .map_err(|_| Error::from(ConfigError::BadAddress))
+                    .and_then(|addr| split_peer_id(&addr))
+            })
+            .collect()
+    }
+
+    pub fn write_reserved_peers(&self, peers: &[(PeerId, Multiaddr)]) -> Result<(), Error> {
+        let content = peers
+            .iter()
+            .map(|(peer_id, addr)| format!("{}/p2p/{}\n", addr, peer_id.to_base58()))
+            .collect::<String>();
+        fs::write(self.reserved_peers_path(), content).map_err(Into::into)
+    }
+
+    pub(crate) fn proxy(&self) -> Result<Option<Socks5Proxy>, Error> {
+        self.proxy
+            .as_ref()
+            .map(|url| Socks5Proxy::parse(url))
+            .transpose()
     }
 
     pub fn outbound_peer_service_enabled(&self) -> bool {
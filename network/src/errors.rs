@@ -21,8 +21,7 @@ pub enum Error {
 
 #[derive(Debug)]
 pub enum PeerStoreError {
-    /// indicate the peer store is full
-    EvictionFailed,
+    DB(ckb_error::Error),
     Serde(serde_json::Error),
 }
 
@@ -30,6 +29,8 @@ pub enum PeerStoreError {
 pub enum ConfigError {
     BadAddress,
     InvalidKey,
+    InvalidPsk,
+    InvalidProxy,
 }
 
 #[derive(Debug, Eq, PartialEq)]
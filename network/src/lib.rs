@@ -8,6 +8,7 @@ mod peer;
 pub mod peer_registry;
 pub mod peer_store;
 mod protocols;
+mod proxy;
 mod services;
 
 #[cfg(test)]
@@ -15,7 +16,8 @@ mod tests;
 
 pub use crate::{
     behaviour::Behaviour,
-    config::NetworkConfig,
+    compress::{CompressionStat, CompressionType},
+    config::{CompressionConfig, NetworkConfig},
     errors::Error,
     network::{NetworkController, NetworkService, NetworkState},
     peer::{Peer, PeerIdentifyInfo},
@@ -1,4 +1,5 @@
-use crate::errors::Error;
+use crate::compress::{compress, AtomicCompressionStat, CompressionStat, CompressionType};
+use crate::errors::{Error, PeerError};
 use crate::peer_registry::{ConnectionStatus, PeerRegistry};
 use crate::peer_store::{
     types::{BannedAddr, MultiaddrExt},
@@ -10,7 +11,9 @@ use crate::protocols::{
     feeler::Feeler,
     identify::IdentifyCallback,
     ping::PingService,
+    pnet::{PnetGuard, PnetProtocol, PreSharedKey},
 };
+use crate::proxy::Socks5Proxy;
 use crate::services::{
     dns_seeding::DnsSeedingService, dump_peer_store::DumpPeerStoreService,
     outbound_peer::OutboundPeerService,
@@ -49,6 +52,7 @@ use std::boxed::Box;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -60,6 +64,7 @@ pub(crate) const DISCOVERY_PROTOCOL_ID: usize = 1;
 pub(crate) const IDENTIFY_PROTOCOL_ID: usize = 2;
 pub(crate) const FEELER_PROTOCOL_ID: usize = 3;
 pub(crate) const DISCONNECT_MESSAGE_PROTOCOL_ID: usize = 4;
+pub(crate) const PNET_PROTOCOL_ID: usize = 5;
 
 const P2P_SEND_TIMEOUT: Duration = Duration::from_secs(6);
 const P2P_TRY_SEND_INTERVAL: Duration = Duration::from_millis(100);
@@ -89,6 +94,21 @@ pub struct NetworkState {
     local_private_key: secio::SecioKeyPair,
     local_peer_id: PeerId,
     bootnodes: Vec<(PeerId, Multiaddr)>,
+    /// Compression format negotiated with each session by identify
+    session_compressions: RwLock<HashMap<SessionId, CompressionType>>,
+    compression_stats: RwLock<HashMap<ProtocolId, AtomicCompressionStat>>,
+    /// Pre-shared key of private network
+    psk: Option<PreSharedKey>,
+    /// Sessions passed private network handshake
+    authorized_sessions: RwLock<HashSet<SessionId>>,
+    /// Peers added by `add_reserved_peer`, they are treated like whitelist peers
+    reserved_peers: RwLock<HashMap<PeerId, Multiaddr>>,
+    /// All outbound connections are dialed through this proxy
+    proxy: Option<Socks5Proxy>,
+    /// Local relay address => the real address dialed through proxy
+    proxied_addrs: RwLock<HashMap<Multiaddr, Multiaddr>>,
+    /// All p2p activity is paused if false
+    active: AtomicBool,
     pub(crate) config: NetworkConfig,
 }
 
@@ -96,9 +116,15 @@ impl NetworkState {
     pub fn from_config(config: NetworkConfig) -> Result<NetworkState, Error> {
         config.create_dir_if_not_exists()?;
         let local_private_key = config.fetch_private_key()?;
+        let psk = config.fetch_psk()?;
+        let proxy = config.proxy()?;
         // set max score to public addresses
-        let public_addrs: HashMap<Multiaddr, u8> = config
-            .listen_addresses
+        let listen_addresses = if config.hide_listen_addresses {
+            &[][..]
+        } else {
+            &config.listen_addresses[..]
+        };
+        let public_addrs: HashMap<Multiaddr, u8> = listen_addresses
             .iter()
             .chain(config.public_addresses.iter())
             .map(|addr| (addr.to_owned(), std::u8::MAX))
@@ -106,10 +132,15 @@ impl NetworkState {
         let peer_store = Mutex::new(PeerStore::load_from_dir(config.peer_store_path())?);
         let bootnodes = config.bootnodes()?;
 
+        let reserved_peers = config
+            .read_reserved_peers()?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
         let whitelist_peers = config
             .whitelist_peers()?
             .iter()
             .map(|(peer_id, _)| peer_id.to_owned())
+            .chain(reserved_peers.keys().cloned())
             .collect::<Vec<_>>();
         let peer_registry = PeerRegistry::new(
             config.max_inbound_peers(),
@@ -131,6 +162,14 @@ impl NetworkState {
             local_private_key: local_private_key.clone(),
             local_peer_id: local_private_key.public_key().peer_id(),
             protocol_ids: RwLock::new(HashSet::default()),
+            session_compressions: RwLock::new(HashMap::default()),
+            compression_stats: RwLock::new(HashMap::default()),
+            psk,
+            authorized_sessions: RwLock::new(HashSet::default()),
+            reserved_peers: RwLock::new(reserved_peers),
+            proxy,
+            proxied_addrs: RwLock::new(HashMap::default()),
+            active: AtomicBool::new(true),
         })
     }
 
@@ -248,7 +287,7 @@ impl NetworkState {
         let accept_peer_result = {
             self.peer_registry.write().accept_peer(
                 peer_id.clone(),
-                session_context.address.clone(),
+                self.original_addr(&session_context.address),
                 session_context.id,
                 session_context.ty,
                 &mut peer_store,
@@ -257,6 +296,105 @@ impl NetworkState {
         accept_peer_result.map_err(Into::into)
     }
 
+    /// Registers an opened session to the peer registry, evicts peers if needed
+    ///
+    /// Returns false if the session is rejected and disconnected.
+    pub(crate) fn register_session(
+        &self,
+        p2p_control: &ServiceControl,
+        session_context: &SessionContext,
+    ) -> bool {
+        let peer_id = session_context
+            .remote_pubkey
+            .as_ref()
+            .map(PublicKey::peer_id)
+            .expect("Secio must enabled");
+
+        self.inbound_eviction(p2p_control);
+
+        if self.with_peer_registry(|reg| reg.is_feeler(&peer_id)) {
+            debug!(
+                "feeler connected {} => {}",
+                session_context.id, session_context.address,
+            );
+            true
+        } else {
+            match self.accept_peer(session_context) {
+                Ok(Some(evicted_peer)) => {
+                    debug!(
+                        "evict peer (disonnect it), {} => {}",
+                        evicted_peer.session_id, evicted_peer.connected_addr,
+                    );
+                    if let Err(err) = disconnect_with_message(
+                        p2p_control,
+                        evicted_peer.session_id,
+                        "evict because accepted better peer",
+                    ) {
+                        debug!(
+                            "Disconnect failed {:?}, error: {:?}",
+                            evicted_peer.session_id, err
+                        );
+                    }
+                    true
+                }
+                Ok(None) => {
+                    debug!(
+                        "{} open, registry {} success",
+                        session_context.id, session_context.address,
+                    );
+                    true
+                }
+                Err(err) => {
+                    debug!(
+                        "registry peer failed {:?} disconnect it, {} => {}",
+                        err, session_context.id, session_context.address,
+                    );
+                    if let Err(err) = disconnect_with_message(
+                        p2p_control,
+                        session_context.id,
+                        "reject peer connection",
+                    ) {
+                        debug!(
+                            "Disconnect failed {:?}, error: {:?}",
+                            session_context.id, err
+                        );
+                    }
+                    false
+                }
+            }
+        }
+    }
+
+    fn inbound_eviction(&self, p2p_control: &ServiceControl) {
+        if self.config.bootnode_mode {
+            let status = self.connection_status();
+
+            if status.max_inbound <= status.non_whitelist_inbound.saturating_add(10) {
+                for (index, peer) in self
+                    .with_peer_registry(|registry| {
+                        registry
+                            .peers()
+                            .values()
+                            .filter(|peer| peer.is_inbound() && !peer.is_whitelist)
+                            .map(|peer| peer.session_id)
+                            .collect::<Vec<SessionId>>()
+                    })
+                    .into_iter()
+                    .enumerate()
+                {
+                    if index & 0x1 != 0 {
+                        if let Err(err) =
+                            disconnect_with_message(p2p_control, peer, "bootnode random eviction")
+                        {
+                            debug!("Inbound eviction failed {:?}, error: {:?}", peer, err);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     // For restrict lock in inner scope
     pub(crate) fn with_peer_registry<F, T>(&self, callback: F) -> T
     where
@@ -281,6 +419,162 @@ impl NetworkState {
         callback(&mut self.peer_store.lock())
     }
 
+    /// Compression format negotiated with the session, fallback to snappy
+    pub(crate) fn session_compression(&self, session_id: SessionId) -> CompressionType {
+        self.session_compressions
+            .read()
+            .get(&session_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_session_compression(&self, session_id: SessionId, codec: CompressionType) {
+        self.session_compressions.write().insert(session_id, codec);
+    }
+
+    /// Compress message with the format negotiated with the session
+    pub(crate) fn compress(
+        &self,
+        proto_id: ProtocolId,
+        session_id: SessionId,
+        data: Bytes,
+    ) -> Bytes {
+        self.compress_with(proto_id, self.session_compression(session_id), data)
+    }
+
+    /// Compress message before broadcasting it to target sessions
+    ///
+    /// The sessions are grouped by their negotiated format and every group is sent its own
+    /// copy, so a message is compressed once per format rather than once per session.
+    pub(crate) fn compress_for_sessions(
+        &self,
+        proto_id: ProtocolId,
+        target: TargetSession,
+        data: Bytes,
+    ) -> Vec<(TargetSession, Bytes)> {
+        let session_ids = match target {
+            TargetSession::Single(session_id) => vec![session_id],
+            TargetSession::Multi(session_ids) => session_ids,
+            TargetSession::All => self.peer_registry.read().connected_peers(),
+        };
+        let mut groups: HashMap<CompressionType, Vec<SessionId>> = HashMap::default();
+        {
+            let session_compressions = self.session_compressions.read();
+            for session_id in session_ids {
+                let codec = session_compressions
+                    .get(&session_id)
+                    .cloned()
+                    .unwrap_or_default();
+                groups.entry(codec).or_default().push(session_id);
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(codec, session_ids)| {
+                let data = self.compress_with(proto_id, codec, data.clone());
+                (TargetSession::Multi(session_ids), data)
+            })
+            .collect()
+    }
+
+    fn compress_with(&self, proto_id: ProtocolId, codec: CompressionType, data: Bytes) -> Bytes {
+        let raw_size = data.len();
+        let data = compress(data, codec, self.config.compression.threshold());
+        let recorded = self
+            .compression_stats
+            .read()
+            .get(&proto_id)
+            .map(|stat| stat.record(raw_size, data.len()))
+            .is_some();
+        if !recorded {
+            self.compression_stats
+                .write()
+                .entry(proto_id)
+                .or_default()
+                .record(raw_size, data.len());
+        }
+        data
+    }
+
+    pub fn compression_stats(&self) -> HashMap<ProtocolId, CompressionStat> {
+        self.compression_stats
+            .read()
+            .iter()
+            .map(|(proto_id, stat)| (*proto_id, stat.load()))
+            .collect()
+    }
+
+    pub(crate) fn is_proxy_enabled(&self) -> bool {
+        self.proxy.is_some()
+    }
+
+    /// Addresses should not be leaked to other peers
+    pub(crate) fn hide_listen_addresses(&self) -> bool {
+        self.config.hide_listen_addresses
+    }
+
+    /// The real address of a session dialed through proxy
+    pub(crate) fn original_addr(&self, addr: &Multiaddr) -> Multiaddr {
+        self.proxied_addrs
+            .read()
+            .get(&addr.exclude_p2p())
+            .cloned()
+            .unwrap_or_else(|| addr.to_owned())
+    }
+
+    pub(crate) fn is_private_network(&self) -> bool {
+        self.psk.is_some()
+    }
+
+    /// All sessions are authorized if private network mode is disabled
+    pub(crate) fn is_session_authorized(&self, session_id: SessionId) -> bool {
+        !self.is_private_network() || self.authorized_sessions.read().contains(&session_id)
+    }
+
+    pub(crate) fn authorize_session(&self, session_id: SessionId) {
+        self.authorized_sessions.write().insert(session_id);
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn reserved_peers(&self) -> Vec<(PeerId, Multiaddr)> {
+        self.reserved_peers
+            .read()
+            .iter()
+            .map(|(peer_id, addr)| (peer_id.to_owned(), addr.to_owned()))
+            .collect()
+    }
+
+    fn add_reserved_peer(&self, peer_id: PeerId, addr: Multiaddr) -> Result<(), Error> {
+        let mut reserved_peers = self.reserved_peers.write();
+        reserved_peers.insert(peer_id.clone(), addr);
+        self.config
+            .write_reserved_peers(&reserved_peers.clone().into_iter().collect::<Vec<_>>())?;
+        self.with_peer_registry_mut(|reg| reg.add_whitelist_peer(peer_id));
+        Ok(())
+    }
+
+    fn remove_reserved_peer(&self, peer_id: &PeerId) -> Result<(), Error> {
+        let mut reserved_peers = self.reserved_peers.write();
+        if reserved_peers.remove(peer_id).is_none() {
+            return Err(PeerError::NotFound(peer_id.to_owned()).into());
+        }
+        self.config
+            .write_reserved_peers(&reserved_peers.clone().into_iter().collect::<Vec<_>>())?;
+        // peers in config whitelist are still protected
+        let in_whitelist = self
+            .config
+            .whitelist_peers()?
+            .iter()
+            .any(|(whitelist_peer_id, _)| whitelist_peer_id == peer_id);
+        if !in_whitelist {
+            self.with_peer_registry_mut(|reg| reg.remove_whitelist_peer(peer_id));
+        }
+        Ok(())
+    }
+
     pub fn local_peer_id(&self) -> &PeerId {
         &self.local_peer_id
     }
@@ -350,6 +644,14 @@ impl NetworkState {
         addr: &Multiaddr,
         allow_dial_to_self: bool,
     ) -> bool {
+        if !self.is_active() {
+            trace!(
+                "Do not dial when network is inactive: {:?}, {}",
+                peer_id,
+                addr
+            );
+            return false;
+        }
         if !allow_dial_to_self && self.local_peer_id() == peer_id {
             trace!("Do not dial self: {:?}, {}", peer_id, addr);
             return false;
@@ -362,6 +664,14 @@ impl NetworkState {
             );
             return false;
         }
+        if addr.is_onion() && !self.is_proxy_enabled() {
+            trace!(
+                "Do not dial onion address without proxy: {:?}, {}",
+                peer_id,
+                addr
+            );
+            return false;
+        }
 
         let peer_in_registry = self.with_peer_registry(|reg| {
             reg.get_key_by_peer_id(peer_id).is_some() || reg.is_feeler(peer_id)
@@ -412,6 +722,11 @@ impl NetworkState {
         self.dialing_addrs.write().remove(&peer_id);
     }
 
+    /// Forget the relay address once the session is closed or the dialing failed
+    pub(crate) fn remove_proxied_addr(&self, addr: &Multiaddr) {
+        self.proxied_addrs.write().remove(&addr.exclude_p2p());
+    }
+
     /// Dial
     /// return value indicates the dialing is actually sent or denied.
     fn dial_inner(
@@ -429,7 +744,24 @@ impl NetworkState {
             )));
         }
 
-        let addr = addr.attach_p2p(peer_id)?;
+        let addr = match self.proxy {
+            Some(ref proxy) => {
+                let (relay_addr, relay) = proxy.relay(&addr)?;
+                p2p_control.future_task(relay)?;
+                debug!("dialing {} through proxy relay {}", addr, relay_addr);
+                self.proxied_addrs
+                    .write()
+                    .insert(relay_addr.clone(), addr.attach_p2p(peer_id)?);
+                relay_addr.attach_p2p(peer_id)?
+            }
+            None => addr.attach_p2p(peer_id)?,
+        };
+        // In private network, the handshake must be done before opening target protocol
+        let target = if self.is_private_network() {
+            DialProtocol::Single(PNET_PROTOCOL_ID.into())
+        } else {
+            target
+        };
         debug!("dialing {} with {:?}", addr, target);
         p2p_control.dial(addr, target)?;
         self.dialing_addrs
@@ -486,6 +818,9 @@ impl NetworkState {
     }
 
     pub fn add_observed_addrs(&self, iter: impl Iterator<Item = Multiaddr>) {
+        if self.hide_listen_addresses() {
+            return;
+        }
         let mut public_addrs = self.public_addrs.write();
         let mut pending_observed_addrs = self.pending_observed_addrs.write();
         for addr in iter {
@@ -509,41 +844,6 @@ pub struct EventHandler {
     pub(crate) exit_condvar: Arc<(Mutex<()>, Condvar)>,
 }
 
-impl EventHandler {
-    fn inbound_eviction(&self, context: &mut ServiceContext) {
-        if self.network_state.config.bootnode_mode {
-            let status = self.network_state.connection_status();
-
-            if status.max_inbound <= status.non_whitelist_inbound.saturating_add(10) {
-                for (index, peer) in self
-                    .network_state
-                    .with_peer_registry(|registry| {
-                        registry
-                            .peers()
-                            .values()
-                            .filter(|peer| peer.is_inbound() && !peer.is_whitelist)
-                            .map(|peer| peer.session_id)
-                            .collect::<Vec<SessionId>>()
-                    })
-                    .into_iter()
-                    .enumerate()
-                {
-                    if index & 0x1 != 0 {
-                        if let Err(err) = disconnect_with_message(
-                            context.control(),
-                            peer,
-                            "bootnode random eviction",
-                        ) {
-                            debug!("Inbound eviction failed {:?}, error: {:?}", peer, err);
-                            return;
-                        }
-                    }
-                }
-            }
-        }
-    }
-}
-
 impl ServiceHandle for EventHandler {
     fn handle_error(&mut self, context: &mut ServiceContext, error: ServiceError) {
         match error {
@@ -554,15 +854,20 @@ impl ServiceHandle for EventHandler {
                 debug!("DialerError({}) {}", address, error);
                 if error == &P2pError::ConnectSelf {
                     debug!("dial observed address success: {:?}", address);
-                    let addr = address
+                    let addr = self
+                        .network_state
+                        .original_addr(address)
                         .iter()
                         .filter(|proto| match proto {
                             multiaddr::Protocol::P2p(_) => false,
                             _ => true,
                         })
                         .collect();
-                    self.network_state.vote_listened_addr(addr, 1);
+                    if !self.network_state.hide_listen_addresses() {
+                        self.network_state.vote_listened_addr(addr, 1);
+                    }
                 }
+                self.network_state.remove_proxied_addr(address);
                 let peer_id = extract_peer_id(address).expect("Secio must enabled");
                 self.network_state.dial_failed(peer_id);
             }
@@ -649,56 +954,34 @@ impl ServiceHandle for EventHandler {
 
                 self.network_state.dial_success(&peer_id);
 
-                self.inbound_eviction(context);
-
-                if self
-                    .network_state
-                    .with_peer_registry(|reg| reg.is_feeler(&peer_id))
-                {
+                if !self.network_state.is_active() {
                     debug!(
-                        "feeler connected {} => {}",
+                        "network is inactive, disconnect {} => {}",
                         session_context.id, session_context.address,
                     );
-                } else {
-                    match self.network_state.accept_peer(&session_context) {
-                        Ok(Some(evicted_peer)) => {
-                            debug!(
-                                "evict peer (disonnect it), {} => {}",
-                                evicted_peer.session_id, evicted_peer.connected_addr,
-                            );
-                            if let Err(err) = disconnect_with_message(
-                                context.control(),
-                                evicted_peer.session_id,
-                                "evict because accepted better peer",
-                            ) {
-                                debug!(
-                                    "Disconnect failed {:?}, error: {:?}",
-                                    evicted_peer.session_id, err
-                                );
-                            }
-                        }
-                        Ok(None) => debug!(
-                            "{} open, registry {} success",
-                            session_context.id, session_context.address,
-                        ),
-                        Err(err) => {
-                            debug!(
-                                "registry peer failed {:?} disconnect it, {} => {}",
-                                err, session_context.id, session_context.address,
-                            );
-                            if let Err(err) = disconnect_with_message(
-                                context.control(),
-                                session_context.id,
-                                "reject peer connection",
-                            ) {
-                                debug!(
-                                    "Disconnect failed {:?}, error: {:?}",
-                                    session_context.id, err
-                                );
-                            }
-                        }
+                    if let Err(err) = disconnect_with_message(
+                        context.control(),
+                        session_context.id,
+                        "network is inactive",
+                    ) {
+                        debug!(
+                            "Disconnect failed {:?}, error: {:?}",
+                            session_context.id, err
+                        );
                     }
+                    return;
+                }
+
+                if self.network_state.is_private_network() {
+                    // Not registered until the private network handshake succeeds
+                    debug!(
+                        "{} open, waiting for private network handshake {}",
+                        session_context.id, session_context.address,
+                    );
+                    return;
                 }
+                self.network_state
+                    .register_session(context.control(), &session_context);
             }
             ServiceEvent::SessionClose { session_context } => {
                 debug!(
@@ -715,12 +998,22 @@ impl ServiceHandle for EventHandler {
                     .disconnecting_sessions
                     .write()
                     .remove(&session_context.id);
+                self.network_state
+                    .authorized_sessions
+                    .write()
+                    .remove(&session_context.id);
+                self.network_state
+                    .session_compressions
+                    .write()
+                    .remove(&session_context.id);
                 let peer_exists = self
                     .network_state
                     .peer_registry
                     .write()
                     .remove_peer(session_context.id)
                     .is_some();
+                self.network_state
+                    .remove_proxied_addr(&session_context.address);
                 if peer_exists {
                     debug!(
                         "{} closed, remove {} from peer_registry",
@@ -839,12 +1132,14 @@ impl NetworkService {
         let ping_meta = MetaBuilder::default()
             .id(PING_PROTOCOL_ID.into())
             .name(move |_| "/ckb/ping".to_string())
-            .service_handle(move || {
-                ProtocolHandle::Both(Box::new(PingHandler::new(
-                    ping_interval,
-                    ping_timeout,
-                    ping_sender.clone(),
-                )))
+            .service_handle({
+                let network_state = Arc::clone(&network_state);
+                move || {
+                    ProtocolHandle::Both(Box::new(PnetGuard::new(
+                        PingHandler::new(ping_interval, ping_timeout, ping_sender.clone()),
+                        Arc::clone(&network_state),
+                    )))
+                }
             })
             .build();
 
@@ -853,11 +1148,15 @@ impl NetworkService {
         let disc_meta = MetaBuilder::default()
             .id(DISCOVERY_PROTOCOL_ID.into())
             .name(move |_| "/ckb/discovery".to_string())
-            .service_handle(move || {
-                ProtocolHandle::Both(Box::new(
-                    DiscoveryProtocol::new(disc_sender.clone())
-                        .global_ip_only(!config.discovery_local_address),
-                ))
+            .service_handle({
+                let network_state = Arc::clone(&network_state);
+                move || {
+                    ProtocolHandle::Both(Box::new(PnetGuard::new(
+                        DiscoveryProtocol::new(disc_sender.clone())
+                            .global_ip_only(!config.discovery_local_address),
+                        Arc::clone(&network_state),
+                    )))
+                }
             })
             .build();
 
@@ -867,8 +1166,14 @@ impl NetworkService {
         let identify_meta = MetaBuilder::default()
             .id(IDENTIFY_PROTOCOL_ID.into())
             .name(move |_| "/ckb/identify".to_string())
-            .service_handle(move || {
-                ProtocolHandle::Both(Box::new(IdentifyProtocol::new(identify_callback.clone())))
+            .service_handle({
+                let network_state = Arc::clone(&network_state);
+                move || {
+                    ProtocolHandle::Both(Box::new(PnetGuard::new(
+                        IdentifyProtocol::new(identify_callback.clone()),
+                        Arc::clone(&network_state),
+                    )))
+                }
             })
             .build();
 
@@ -879,7 +1184,12 @@ impl NetworkService {
             .name(move |_| "/ckb/flr".to_string())
             .service_handle({
                 let network_state = Arc::clone(&network_state);
-                move || ProtocolHandle::Both(Box::new(Feeler::new(Arc::clone(&network_state))))
+                move || {
+                    ProtocolHandle::Both(Box::new(PnetGuard::new(
+                        Feeler::new(Arc::clone(&network_state)),
+                        Arc::clone(&network_state),
+                    )))
+                }
             })
             .build();
 
@@ -899,6 +1209,22 @@ impl NetworkService {
         protocol_metas.push(ping_meta);
         protocol_metas.push(disc_meta);
         protocol_metas.push(identify_meta);
+        if let Some(psk) = network_state.psk {
+            let pnet_meta = MetaBuilder::default()
+                .id(PNET_PROTOCOL_ID.into())
+                .name(move |_| "/ckb/pnet".to_string())
+                .service_handle({
+                    let network_state = Arc::clone(&network_state);
+                    move || {
+                        ProtocolHandle::Both(Box::new(PnetProtocol::new(
+                            Arc::clone(&network_state),
+                            psk,
+                        )))
+                    }
+                })
+                .build();
+            protocol_metas.push(pnet_meta);
+        }
 
         let mut service_builder = ServiceBuilder::default();
         for meta in protocol_metas.into_iter() {
@@ -941,7 +1267,11 @@ impl NetworkService {
             bg_services.push(Box::new(outbound_peer_service) as Box<_>);
         };
 
-        if config.dns_seeding_service_enabled() {
+        // The seeds would be resolved by the local resolver, which leaks DNS queries outside
+        // the proxy
+        if network_state.is_proxy_enabled() {
+            info!("DNS seeding is disabled since all connections go through the proxy");
+        } else if config.dns_seeding_service_enabled() {
             let dns_seeding_service =
                 DnsSeedingService::new(Arc::clone(&network_state), config.dns_seeds.clone());
             bg_services.push(Box::new(dns_seeding_service) as Box<_>);
@@ -984,8 +1314,12 @@ impl NetworkService {
             };
         }
 
-        // dial whitelist_nodes
-        for (peer_id, addr) in config.whitelist_peers()? {
+        // dial whitelist_nodes and reserved peers
+        for (peer_id, addr) in config
+            .whitelist_peers()?
+            .into_iter()
+            .chain(self.network_state.reserved_peers())
+        {
             debug!("dial whitelist_peers {:?} {:?}", peer_id, addr);
             self.network_state
                 .dial_identify(self.p2p_service.control(), &peer_id, addr);
@@ -1112,19 +1446,61 @@ impl NetworkController {
     }
 
     pub fn remove_node(&self, peer_id: &PeerId) {
-        if let Some(session_id) = self
+        if let Err(err) = self.disconnect_peer(peer_id) {
+            error!("Cannot disconnect peer {:?}: {}", peer_id, err);
+        }
+    }
+
+    pub fn disconnect_peer(&self, peer_id: &PeerId) -> Result<(), Error> {
+        let session_id = self
             .network_state
-            .peer_registry
-            .read()
-            .get_key_by_peer_id(peer_id)
-        {
-            if let Err(err) =
-                disconnect_with_message(&self.p2p_control, session_id, "disconnect manually")
-            {
-                debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+            .query_session_id(peer_id)
+            .ok_or_else(|| PeerError::NotFound(peer_id.to_owned()))?;
+        disconnect_with_message(&self.p2p_control, session_id, "disconnect manually")?;
+        Ok(())
+    }
+
+    /// Reserved peers are persisted and always stay connected like whitelist peers
+    pub fn add_reserved_peer(&self, peer_id: PeerId, address: Multiaddr) -> Result<(), Error> {
+        self.network_state
+            .add_reserved_peer(peer_id.clone(), address.clone())?;
+        if self.network_state.query_session_id(&peer_id).is_none() {
+            self.network_state
+                .dial_identify(&self.p2p_control, &peer_id, address);
+        }
+        Ok(())
+    }
+
+    pub fn remove_reserved_peer(&self, peer_id: &PeerId) -> Result<(), Error> {
+        self.network_state.remove_reserved_peer(peer_id)
+    }
+
+    pub fn reserved_peers(&self) -> Vec<(PeerId, Multiaddr)> {
+        self.network_state.reserved_peers()
+    }
+
+    pub fn is_network_active(&self) -> bool {
+        self.network_state.is_active()
+    }
+
+    /// Pause or resume all p2p activity, all peers are disconnected when pausing
+    pub fn set_network_active(&self, active: bool) {
+        let was_active = self.network_state.active.swap(active, Ordering::AcqRel);
+        if was_active == active {
+            return;
+        }
+        info!("set network active: {}", active);
+        if !active {
+            let session_ids = self
+                .network_state
+                .with_peer_registry(|reg| reg.connected_peers());
+            for session_id in session_ids {
+                if let Err(err) =
+                    disconnect_with_message(&self.p2p_control, session_id, "network is inactive")
+                {
+                    debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+                }
             }
-        } else {
-            error!("Cannot find peer {:?}", peer_id);
         }
     }
 
@@ -1143,6 +1519,10 @@ impl NetworkController {
             .ban_network(address, ban_until, ban_reason)
     }
 
+    pub fn clear_banned_addrs(&self) {
+        self.network_state.peer_store.lock().mut_ban_list().clear();
+    }
+
     pub fn unban(&self, address: &IpNetwork) {
         self.network_state
             .peer_store
@@ -1161,12 +1541,32 @@ impl NetworkController {
             .collect()
     }
 
+    pub fn compression_stats(&self) -> HashMap<ProtocolId, CompressionStat> {
+        self.network_state.compression_stats()
+    }
+
     fn try_broadcast(
         &self,
         quick: bool,
         target: TargetSession,
         proto_id: ProtocolId,
         data: Bytes,
+    ) -> Result<(), P2pError> {
+        for (target, data) in self
+            .network_state
+            .compress_for_sessions(proto_id, target, data)
+        {
+            self.try_send(quick, target, proto_id, data)?;
+        }
+        Ok(())
+    }
+
+    fn try_send(
+        &self,
+        quick: bool,
+        target: TargetSession,
+        proto_id: ProtocolId,
+        data: Bytes,
     ) -> Result<(), P2pError> {
         let now = Instant::now();
         loop {
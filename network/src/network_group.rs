@@ -1,13 +1,29 @@
-use crate::peer_store::types::MultiaddrExt;
+use crate::peer_store::types::{is_onion_ip, MultiaddrExt};
 use p2p::multiaddr::Multiaddr;
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum Group {
     NoGroup,
     LocalNetwork,
     IP4([u8; 2]),
     IP6([u8; 4]),
+    // Onion addresses are mapped into a single /48, they are grouped by their hash instead
+    Onion(u8),
+}
+
+impl Group {
+    /// Encode group with a leading type byte, used to compute peer store buckets
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Group::NoGroup => vec![0],
+            Group::LocalNetwork => vec![1],
+            Group::IP4(bits) => vec![2, bits[0], bits[1]],
+            Group::IP6(bits) => vec![3, bits[0], bits[1], bits[2], bits[3]],
+            Group::Onion(bits) => vec![4, *bits],
+        }
+    }
 }
 
 pub trait NetworkGroup {
@@ -33,6 +49,11 @@ impl NetworkGroup for Multiaddr {
             }
             // IPv6 NetworkGroup
             if let IpAddr::V6(ipv6) = ip_addr {
+                if is_onion_ip(&ip_addr) {
+                    // Only 16 groups, so generating many onion names does not give a single
+                    // operator many groups
+                    return Group::Onion(ipv6.octets()[6] >> 4);
+                }
                 if let Some(ipv4) = ipv6.to_ipv4() {
                     let bits = ipv4.octets();
                     return Group::IP4([bits[0], bits[1]]);
@@ -1,5 +1,5 @@
 use crate::network_group::{Group, NetworkGroup};
-use crate::{multiaddr::Multiaddr, ProtocolId, ProtocolVersion, SessionType};
+use crate::{multiaddr::Multiaddr, CompressionType, ProtocolId, ProtocolVersion, SessionType};
 use p2p::{secio::PeerId, SessionId};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -7,6 +7,8 @@ use std::time::{Duration, Instant};
 #[derive(Clone, Debug)]
 pub struct PeerIdentifyInfo {
     pub client_version: String,
+    // Compression format negotiated through identify flags
+    pub compression: CompressionType,
 }
 
 #[derive(Clone, Debug)]
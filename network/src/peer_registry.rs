@@ -190,6 +190,29 @@ impl PeerRegistry {
         self.feeler_peers.contains(peer_id)
     }
 
+    /// Whitelist a peer at runtime, the connected session is also protected from eviction
+    pub(crate) fn add_whitelist_peer(&mut self, peer_id: PeerId) {
+        for peer in self
+            .peers
+            .values_mut()
+            .filter(|peer| peer.peer_id == peer_id)
+        {
+            peer.is_whitelist = true;
+        }
+        self.whitelist_peers.insert(peer_id);
+    }
+
+    pub(crate) fn remove_whitelist_peer(&mut self, peer_id: &PeerId) {
+        for peer in self
+            .peers
+            .values_mut()
+            .filter(|peer| &peer.peer_id == peer_id)
+        {
+            peer.is_whitelist = false;
+        }
+        self.whitelist_peers.remove(peer_id);
+    }
+
     pub fn get_peer(&self, session_id: SessionId) -> Option<&Peer> {
         self.peers.get(&session_id)
     }
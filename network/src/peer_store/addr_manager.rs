@@ -1,36 +1,214 @@
+use crate::network_group::{Group, NetworkGroup};
 use crate::peer_store::types::{AddrInfo, IpPort};
+use ckb_hash::new_blake2b;
 use rand::Rng;
 use std::collections::{HashMap, HashSet};
 
-#[derive(Default)]
+/// Buckets for addresses we never connected to
+pub(crate) const NEW_BUCKET_COUNT: usize = 256;
+/// Buckets for addresses we had connected to
+pub(crate) const TRIED_BUCKET_COUNT: usize = 64;
+pub(crate) const BUCKET_SIZE: usize = 64;
+/// Addresses from the same source group are spread into at most this number of new buckets
+pub(crate) const NEW_BUCKETS_PER_SOURCE_GROUP: u64 = 32;
+/// Addresses in the same group are spread into at most this number of tried buckets
+pub(crate) const TRIED_BUCKETS_PER_GROUP: u64 = 8;
+
+pub(crate) type BucketKey = [u8; 32];
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Table {
+    New,
+    Tried,
+}
+
+/// The position of an address in the bucket tables
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Slot {
+    pub table: Table,
+    pub bucket: usize,
+    pub position: usize,
+}
+
+/// Address manager with bitcoin style new/tried buckets.
+///
+/// The bucket of an address is decided by a secret key and the network groups of the address
+/// and its source, so a single network group can only fill a small part of the tables, this
+/// makes eclipse attack harder and also limits the total number of addresses.
 pub struct AddrManager {
+    key: BucketKey,
     next_id: u64,
     addr_to_id: HashMap<IpPort, u64>,
     id_to_info: HashMap<u64, AddrInfo>,
+    id_to_slot: HashMap<u64, Slot>,
+    slots: HashMap<Slot, u64>,
     random_ids: Vec<u64>,
+    // addresses changed since last `take_dirty`
+    dirty: HashSet<IpPort>,
+}
+
+impl Default for AddrManager {
+    fn default() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill(&mut key);
+        AddrManager::with_key(key)
+    }
 }
 
 impl AddrManager {
-    pub fn add(&mut self, mut addr_info: AddrInfo) {
-        let id = self.next_id;
+    pub fn with_key(key: BucketKey) -> Self {
+        AddrManager {
+            key,
+            next_id: 0,
+            addr_to_id: HashMap::default(),
+            id_to_info: HashMap::default(),
+            id_to_slot: HashMap::default(),
+            slots: HashMap::default(),
+            random_ids: Vec::new(),
+            dirty: HashSet::default(),
+        }
+    }
+
+    pub fn key(&self) -> &BucketKey {
+        &self.key
+    }
+
+    /// Add an address with its recorded source group, the address itself is treated as source
+    /// if the source is unknown.
+    pub fn add(&mut self, addr_info: AddrInfo) -> bool {
+        let source = addr_info.source_group();
+        self.add_to_slot(addr_info, &source)
+    }
+
+    /// Add an address learned from source group,
+    /// return false if the address is rejected because its slot is occupied by a better one.
+    pub fn add_with_source(&mut self, mut addr_info: AddrInfo, source: &Group) -> bool {
+        addr_info.source = Some(source.to_owned());
+        self.add_to_slot(addr_info, source)
+    }
+
+    fn add_to_slot(&mut self, addr_info: AddrInfo, source: &Group) -> bool {
         let key = addr_info.ip_port();
-        if let Some(exists_last_connected_at_ms) =
-            self.get(&key).map(|addr| addr.last_connected_at_ms)
+        let exists_last_connected_at_ms = self.get(&key).map(|addr| addr.last_connected_at_ms);
+        // replace exists addr only if has later last_connected_at_ms
+        if let Some(exists_last_connected_at_ms) = exists_last_connected_at_ms {
+            if addr_info.last_connected_at_ms <= exists_last_connected_at_ms {
+                return false;
+            }
+        }
+        let slot = if addr_info.last_connected_at_ms > 0 {
+            self.tried_slot(&addr_info)
+        } else {
+            self.new_slot(&addr_info, source)
+        };
+        let mut demoted = None;
+        if let Some(occupant_key) = self
+            .slots
+            .get(&slot)
+            .map(|id| self.id_to_info[id].ip_port())
+            .filter(|occupant_key| occupant_key != &key)
         {
-            // replace exists addr if has later last_connected_at_ms
-            if addr_info.last_connected_at_ms > exists_last_connected_at_ms {
-                self.remove(&key);
-            } else {
-                return;
+            let now_ms = faketime::unix_time_as_millis();
+            match slot.table {
+                Table::New => {
+                    // only replace terrible addresses in new table
+                    if !self.get(&occupant_key).expect("exists").is_terrible(now_ms) {
+                        return false;
+                    }
+                    self.remove(&occupant_key);
+                }
+                Table::Tried => {
+                    // move the old one back to new table
+                    demoted = self.remove(&occupant_key);
+                }
             }
         }
+        if exists_last_connected_at_ms.is_some() {
+            self.remove(&key);
+        }
+        self.insert(addr_info, slot);
+        if let Some(addr_info) = demoted {
+            let source = addr_info.source_group();
+            let slot = self.new_slot(&addr_info, &source);
+            if !self.slots.contains_key(&slot) {
+                self.insert(addr_info, slot);
+            }
+        }
+        true
+    }
+
+    fn insert(&mut self, mut addr_info: AddrInfo, slot: Slot) {
+        let id = self.next_id;
+        let key = addr_info.ip_port();
         self.addr_to_id.insert(key, id);
         addr_info.random_id_pos = self.random_ids.len();
         self.id_to_info.insert(id, addr_info);
+        self.id_to_slot.insert(id, slot);
+        self.slots.insert(slot, id);
         self.random_ids.push(id);
+        self.dirty.insert(key);
         self.next_id += 1;
     }
 
+    fn hash(&self, parts: &[&[u8]]) -> u64 {
+        let mut result = [0u8; 32];
+        let mut blake2b = new_blake2b();
+        blake2b.update(&self.key);
+        for part in parts {
+            blake2b.update(part);
+        }
+        blake2b.finalize(&mut result);
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&result[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn position(&self, table: Table, bucket: usize, addr_info: &AddrInfo) -> usize {
+        let table_flag: &[u8] = match table {
+            Table::New => b"new",
+            Table::Tried => b"tried",
+        };
+        let position = self.hash(&[
+            table_flag,
+            &(bucket as u64).to_le_bytes()[..],
+            &addr_info.ip_port().to_bytes()[..],
+        ]) % BUCKET_SIZE as u64;
+        position as usize
+    }
+
+    pub fn new_slot(&self, addr_info: &AddrInfo, source: &Group) -> Slot {
+        let group = addr_info.addr.network_group().to_bytes();
+        let source = source.to_bytes();
+        let source_bucket = self.hash(&[&group[..], &source[..]]) % NEW_BUCKETS_PER_SOURCE_GROUP;
+        let bucket = (self.hash(&[&source[..], &source_bucket.to_le_bytes()[..]])
+            % NEW_BUCKET_COUNT as u64) as usize;
+        Slot {
+            table: Table::New,
+            bucket,
+            position: self.position(Table::New, bucket, addr_info),
+        }
+    }
+
+    pub fn tried_slot(&self, addr_info: &AddrInfo) -> Slot {
+        let group = addr_info.addr.network_group().to_bytes();
+        let addr_bucket =
+            self.hash(&[&addr_info.ip_port().to_bytes()[..]]) % TRIED_BUCKETS_PER_GROUP;
+        let bucket = (self.hash(&[&group[..], &addr_bucket.to_le_bytes()[..]])
+            % TRIED_BUCKET_COUNT as u64) as usize;
+        Slot {
+            table: Table::Tried,
+            bucket,
+            position: self.position(Table::Tried, bucket, addr_info),
+        }
+    }
+
+    pub fn slot(&self, addr: &IpPort) -> Option<Slot> {
+        self.addr_to_id
+            .get(addr)
+            .and_then(|id| self.id_to_slot.get(id))
+            .cloned()
+    }
+
     /// randomly return addrs that worth to try or connect.
     pub fn fetch_random<F>(&mut self, count: usize, filter: F) -> Vec<AddrInfo>
     where
@@ -76,6 +254,10 @@ impl AddrManager {
             // swap with last index, then remove the last index
             self.swap_random_id(random_id_pos, self.random_ids.len() - 1);
             self.random_ids.pop();
+            if let Some(slot) = self.id_to_slot.remove(&id) {
+                self.slots.remove(&slot);
+            }
+            self.dirty.insert(*addr);
             self.id_to_info.remove(&id)
         } else {
             None
@@ -90,12 +272,22 @@ impl AddrManager {
 
     pub fn get_mut(&mut self, addr: &IpPort) -> Option<&mut AddrInfo> {
         if let Some(id) = self.addr_to_id.get(addr) {
+            self.dirty.insert(*addr);
             self.id_to_info.get_mut(&id)
         } else {
             None
         }
     }
 
+    /// Take the addresses changed since last call, `None` means the address was removed.
+    pub fn take_dirty(&mut self) -> Vec<(IpPort, Option<AddrInfo>)> {
+        let dirty = self.dirty.drain().collect::<Vec<_>>();
+        dirty
+            .into_iter()
+            .map(|key| (key, self.get(&key).cloned()))
+            .collect()
+    }
+
     /// swap random_id i and j,
     /// this function keep random_id_pos in consistency
     fn swap_random_id(&mut self, i: usize, j: usize) {
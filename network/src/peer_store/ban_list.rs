@@ -39,6 +39,10 @@ impl BanList {
         self.inner.remove(&ip_network);
     }
 
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
     fn is_ip_banned_until(&self, ip: IpAddr, now_ms: u64) -> bool {
         let ip_network = ip_to_network(ip);
         if let Some(banned_addr) = self.inner.get(&ip_network) {
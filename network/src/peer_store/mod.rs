@@ -10,8 +10,6 @@ pub(crate) use crate::{Behaviour, PeerId};
 use p2p::multiaddr::Multiaddr;
 pub use peer_store_impl::PeerStore;
 
-/// peer store evict peers after reach this limitation
-pub(crate) const ADDR_COUNT_LIMIT: usize = 16384;
 /// Consider we never seen a peer if peer's last_connected_at beyond this timeout
 const ADDR_TIMEOUT_MS: u64 = 7 * 24 * 3600 * 1000;
 const ADDR_MAX_RETRIES: u32 = 3;
@@ -1,36 +1,174 @@
 use crate::{
     errors::{Error, PeerStoreError},
     peer_store::{
-        addr_manager::AddrManager,
+        addr_manager::{AddrManager, BucketKey},
         ban_list::BanList,
-        types::{AddrInfo, BannedAddr},
+        types::{AddrInfo, BannedAddr, IpPort},
         PeerStore,
     },
 };
-use ckb_logger::debug;
-use std::fs::{copy, create_dir_all, remove_file, rename, File, OpenOptions};
-use std::io::{Read, Write};
+use ckb_db::{Col, DBConfig, RocksDB};
+use ckb_logger::{debug, info};
+use std::fs::{create_dir_all, remove_file, File};
+use std::io::Read;
 use std::path::Path;
 
-const DEFAULT_ADDR_MANAGER_DB: &str = "addr_manager.db";
-const DEFAULT_BAN_LIST_DB: &str = "ban_list.db";
+/// Addresses, keyed by `IpPort::to_bytes`, value is JSON encoded `AddrInfo`
+const COLUMN_ADDRS: Col = "0";
+/// Banned networks, keyed by network string, value is JSON encoded `BannedAddr`
+const COLUMN_BANS: Col = "1";
+const COLUMN_META: Col = "2";
+const COLUMNS: u32 = 3;
+
+const META_BUCKET_KEY: &[u8] = b"bucket_key";
+
+const DEFAULT_DB_DIR: &str = "db";
+// Peer store was dumped to these JSON files before, import them once if exists
+const LEGACY_ADDR_MANAGER_DB: &str = "addr_manager.db";
+const LEGACY_BAN_LIST_DB: &str = "ban_list.db";
+
+/// On-disk peer store, only changed entries are written on each flush
+pub struct PeerStoreDB {
+    db: RocksDB,
+}
+
+impl PeerStoreDB {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        create_dir_all(&path)?;
+        let config = DBConfig {
+            path: path.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+        let db = RocksDB::open_with_error(&config, COLUMNS).map_err(PeerStoreError::DB)?;
+        Ok(PeerStoreDB { db })
+    }
+
+    fn load_bucket_key(&self) -> Result<Option<BucketKey>, Error> {
+        let value = self
+            .db
+            .get_pinned(COLUMN_META, META_BUCKET_KEY)
+            .map_err(PeerStoreError::DB)?;
+        Ok(value.filter(|value| value.len() == 32).map(|value| {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&value);
+            key
+        }))
+    }
+
+    /// Load addresses and ban list, addresses don't fit in buckets anymore are dropped.
+    pub fn load(&self) -> Result<(AddrManager, BanList), Error> {
+        let mut addr_manager = match self.load_bucket_key()? {
+            Some(key) => AddrManager::with_key(key),
+            None => AddrManager::default(),
+        };
+        let mut addrs = Vec::new();
+        self.db
+            .traverse(COLUMN_ADDRS, |key, value| {
+                addrs.push((key.to_vec(), serde_json::from_slice::<AddrInfo>(value).ok()));
+                Ok(())
+            })
+            .map_err(PeerStoreError::DB)?;
+        // drop broken addresses and addresses that don't fit in buckets,
+        // this happens if the bucket key or the bucket parameters changed
+        let txn = self.db.transaction();
+        for (key, addr) in addrs {
+            let accepted = match addr {
+                Some(addr) => addr_manager.add(addr),
+                None => false,
+            };
+            if !accepted {
+                txn.delete(COLUMN_ADDRS, &key).map_err(PeerStoreError::DB)?;
+            }
+        }
+        // addresses evicted by later ones while loading
+        for (key, addr) in addr_manager.take_dirty() {
+            if addr.is_none() {
+                txn.delete(COLUMN_ADDRS, &key.to_bytes())
+                    .map_err(PeerStoreError::DB)?;
+            }
+        }
+        txn.commit().map_err(PeerStoreError::DB)?;
+
+        let mut ban_list = BanList::default();
+        self.db
+            .traverse(COLUMN_BANS, |_key, value| {
+                match serde_json::from_slice::<BannedAddr>(value) {
+                    Ok(banned_addr) => ban_list.ban(banned_addr),
+                    Err(err) => debug!("skip broken banned addr in peer store: {}", err),
+                }
+                Ok(())
+            })
+            .map_err(PeerStoreError::DB)?;
+        debug!(
+            "load {} addrs and {} banned addrs from peer store",
+            addr_manager.count(),
+            ban_list.get_banned_addrs().len()
+        );
+        Ok((addr_manager, ban_list))
+    }
+
+    /// Write changed addresses and the whole ban list if it is given, in one transaction.
+    pub fn write(
+        &self,
+        bucket_key: &BucketKey,
+        addrs: &[(IpPort, Option<AddrInfo>)],
+        ban_list: Option<&BanList>,
+    ) -> Result<(), Error> {
+        let txn = self.db.transaction();
+        txn.put(COLUMN_META, META_BUCKET_KEY, &bucket_key[..])
+            .map_err(PeerStoreError::DB)?;
+        for (key, addr) in addrs {
+            let key = key.to_bytes();
+            match addr {
+                Some(addr) => {
+                    let value = serde_json::to_vec(addr).map_err(PeerStoreError::Serde)?;
+                    txn.put(COLUMN_ADDRS, &key, &value)
+                }
+                None => txn.delete(COLUMN_ADDRS, &key),
+            }
+            .map_err(PeerStoreError::DB)?;
+        }
+        if let Some(ban_list) = ban_list {
+            let mut stale_keys = Vec::new();
+            self.db
+                .traverse(COLUMN_BANS, |key, _value| {
+                    stale_keys.push(key.to_vec());
+                    Ok(())
+                })
+                .map_err(PeerStoreError::DB)?;
+            for key in stale_keys {
+                txn.delete(COLUMN_BANS, &key).map_err(PeerStoreError::DB)?;
+            }
+            for banned_addr in ban_list.get_banned_addrs() {
+                let value = serde_json::to_vec(&banned_addr).map_err(PeerStoreError::Serde)?;
+                txn.put(
+                    COLUMN_BANS,
+                    banned_addr.address.to_string().as_bytes(),
+                    &value,
+                )
+                .map_err(PeerStoreError::DB)?;
+            }
+        }
+        txn.commit().map_err(PeerStoreError::DB)?;
+        debug!("write {} changed addrs to peer store", addrs.len());
+        Ok(())
+    }
+}
 
 impl AddrManager {
+    /// Load addresses from a legacy JSON dump
     pub fn load<R: Read>(r: R) -> Result<Self, Error> {
         let addrs: Vec<AddrInfo> = serde_json::from_reader(r).map_err(PeerStoreError::Serde)?;
         let mut addr_manager = AddrManager::default();
-        addrs.into_iter().for_each(|addr| addr_manager.add(addr));
+        for addr in addrs {
+            addr_manager.add(addr);
+        }
         Ok(addr_manager)
     }
-
-    pub fn dump<W: Write>(&self, w: W) -> Result<(), Error> {
-        let addrs: Vec<_> = self.addrs_iter().collect();
-        debug!("dump {} addrs", addrs.len());
-        serde_json::to_writer(w, &addrs).map_err(|err| PeerStoreError::Serde(err).into())
-    }
 }
 
 impl BanList {
+    /// Load ban list from a legacy JSON dump
     pub fn load<R: Read>(r: R) -> Result<Self, Error> {
         let banned_addrs: Vec<BannedAddr> =
             serde_json::from_reader(r).map_err(PeerStoreError::Serde)?;
@@ -40,72 +178,47 @@ impl BanList {
             .for_each(|banned_addr| ban_list.ban(banned_addr));
         Ok(ban_list)
     }
-
-    pub fn dump<W: Write>(&self, w: W) -> Result<(), Error> {
-        let banned_addrs = self.get_banned_addrs();
-        debug!("dump {} banned addrs", banned_addrs.len());
-        serde_json::to_writer(w, &banned_addrs).map_err(|err| PeerStoreError::Serde(err).into())
-    }
 }
 
 impl PeerStore {
+    /// Open the peer store database under `path`, legacy JSON dumps in `path` are imported and
+    /// then removed.
     pub fn load_from_dir<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
-        let addr_manager_path = path.as_ref().join(DEFAULT_ADDR_MANAGER_DB);
-        let ban_list_path = path.as_ref().join(DEFAULT_BAN_LIST_DB);
-
-        let addr_manager = if addr_manager_path.exists() {
-            AddrManager::load(File::open(addr_manager_path)?)?
-        } else {
-            debug!("Failed to load addr manager from {:?}", addr_manager_path);
-            AddrManager::default()
-        };
+        let db = PeerStoreDB::open(path.as_ref().join(DEFAULT_DB_DIR))?;
+        let (mut addr_manager, mut ban_list) = db.load()?;
 
-        let ban_list = if ban_list_path.exists() {
-            BanList::load(File::open(ban_list_path)?)?
-        } else {
-            debug!("Failed to load ban list from {:?}", ban_list_path);
-            BanList::default()
-        };
-
-        Ok(PeerStore::new(addr_manager, ban_list))
-    }
-
-    pub fn dump_to_dir<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
-        // create dir
-        create_dir_all(&path)?;
-        // dump file to temp dir
-        let tmp_dir = tempfile::tempdir()?;
-        let tmp_addr_manager = tmp_dir.path().join(DEFAULT_ADDR_MANAGER_DB);
-        let tmp_ban_list = tmp_dir.path().join(DEFAULT_BAN_LIST_DB);
-        self.addr_manager().dump(
-            OpenOptions::new()
-                .write(true)
-                .create(true)
-                .append(false)
-                .open(&tmp_addr_manager)?,
-        )?;
-        move_file(
-            tmp_addr_manager,
-            path.as_ref().join(DEFAULT_ADDR_MANAGER_DB),
-        )?;
-        self.ban_list().dump(
-            OpenOptions::new()
-                .write(true)
-                .create(true)
-                .append(false)
-                .open(&tmp_ban_list)?,
-        )?;
-        move_file(tmp_ban_list, path.as_ref().join(DEFAULT_BAN_LIST_DB))?;
-        Ok(())
-    }
-}
+        let legacy_addr_manager_path = path.as_ref().join(LEGACY_ADDR_MANAGER_DB);
+        let legacy_ban_list_path = path.as_ref().join(LEGACY_BAN_LIST_DB);
+        let has_legacy_addr_manager = legacy_addr_manager_path.exists();
+        let has_legacy_ban_list = legacy_ban_list_path.exists();
+        if has_legacy_addr_manager {
+            let legacy = AddrManager::load(File::open(&legacy_addr_manager_path)?)?;
+            info!(
+                "import {} addrs from {:?}",
+                legacy.count(),
+                legacy_addr_manager_path
+            );
+            for addr in legacy.addrs_iter() {
+                addr_manager.add(addr.to_owned());
+            }
+        }
+        if has_legacy_ban_list {
+            let legacy = BanList::load(File::open(&legacy_ban_list_path)?)?;
+            for banned_addr in legacy.get_banned_addrs() {
+                ban_list.ban(banned_addr);
+            }
+        }
 
-/// This function use `copy` then `remove_file` as a fallback when `rename` failed,
-/// this maybe happen when src and dst on different file systems.
-fn move_file<P: AsRef<Path>>(src: P, dst: P) -> Result<(), Error> {
-    if rename(&src, &dst).is_err() {
-        copy(&src, &dst)?;
-        remove_file(&src)?;
+        let mut peer_store = PeerStore::new(addr_manager, ban_list);
+        peer_store.set_ban_list_dirty(has_legacy_ban_list);
+        peer_store.set_db(db);
+        peer_store.flush()?;
+        if has_legacy_addr_manager {
+            remove_file(legacy_addr_manager_path)?;
+        }
+        if has_legacy_ban_list {
+            remove_file(legacy_ban_list_path)?;
+        }
+        Ok(peer_store)
     }
-    Ok(())
 }
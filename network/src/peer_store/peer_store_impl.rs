@@ -1,12 +1,12 @@
 use crate::{
-    errors::{PeerStoreError, Result},
+    errors::Result,
     network_group::{Group, NetworkGroup},
     peer_store::{
         addr_manager::AddrManager,
         ban_list::BanList,
+        peer_store_db::PeerStoreDB,
         types::{ip_to_network, AddrInfo, BannedAddr, MultiaddrExt, PeerInfo},
-        Behaviour, Multiaddr, PeerScoreConfig, ReportResult, Status, ADDR_COUNT_LIMIT,
-        ADDR_TIMEOUT_MS,
+        Behaviour, Multiaddr, PeerScoreConfig, ReportResult, Status, ADDR_TIMEOUT_MS,
     },
     PeerId, SessionType,
 };
@@ -20,6 +20,9 @@ pub struct PeerStore {
     ban_list: RefCell<BanList>,
     peers: RefCell<HashMap<PeerId, PeerInfo>>,
     score_config: PeerScoreConfig,
+    // `None` means a memory only peer store
+    db: Option<PeerStoreDB>,
+    ban_list_dirty: bool,
 }
 
 impl PeerStore {
@@ -29,9 +32,38 @@ impl PeerStore {
             ban_list: RefCell::new(ban_list),
             peers: Default::default(),
             score_config: Default::default(),
+            db: None,
+            ban_list_dirty: false,
         }
     }
 
+    pub(crate) fn set_db(&mut self, db: PeerStoreDB) {
+        self.db = Some(db);
+    }
+
+    pub(crate) fn set_ban_list_dirty(&mut self, dirty: bool) {
+        self.ban_list_dirty = dirty;
+    }
+
+    /// Write addresses and ban list changed since last flush to disk
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(db) = self.db.as_ref() {
+            let addrs = self.addr_manager.take_dirty();
+            let ban_list = if self.ban_list_dirty {
+                Some(self.ban_list.borrow())
+            } else {
+                None
+            };
+            db.write(
+                self.addr_manager.key(),
+                &addrs,
+                ban_list.as_ref().map(|ban_list| &**ban_list),
+            )?;
+            self.ban_list_dirty = false;
+        }
+        Ok(())
+    }
+
     /// Add a peer and address into peer_store
     /// this method will assume peer is connected, which implies address is "verified".
     pub fn add_connected_peer(
@@ -69,15 +101,29 @@ impl PeerStore {
     /// Add discovered peer addresses
     /// this method will assume peer and addr is untrust since we have not connected to it.
     pub fn add_addr(&mut self, peer_id: PeerId, addr: Multiaddr) -> Result<()> {
-        self.check_purge()?;
+        let source = addr.network_group();
+        self.add_addr_from(peer_id, addr, &source)
+    }
+
+    /// Add peer addresses learned from `source`,
+    /// addresses from the same source network group can only occupy limited new buckets.
+    pub fn add_addr_from(
+        &mut self,
+        peer_id: PeerId,
+        addr: Multiaddr,
+        source: &Group,
+    ) -> Result<()> {
         let score = self.score_config.default_score;
-        self.addr_manager.add(AddrInfo::new(
-            peer_id,
-            addr.extract_ip_addr()?,
-            addr.exclude_p2p(),
-            0,
-            score,
-        ));
+        self.addr_manager.add_with_source(
+            AddrInfo::new(
+                peer_id,
+                addr.extract_ip_addr()?,
+                addr.exclude_p2p(),
+                0,
+                score,
+            ),
+            source,
+        );
         Ok(())
     }
 
@@ -96,9 +142,17 @@ impl PeerStore {
             peers.get(peer_id).map(ToOwned::to_owned)
         } {
             let key = peer.connected_addr.extract_ip_addr()?;
-            let mut peer_addr = self.addr_manager.get_mut(&key).expect("peer addr exists");
-            let score = peer_addr.score.saturating_add(behaviour.score());
-            peer_addr.score = score;
+            // the addr may not in peer store if its bucket slot is occupied by a better one
+            let score = match self.addr_manager.get_mut(&key) {
+                Some(peer_addr) => {
+                    peer_addr.score = peer_addr.score.saturating_add(behaviour.score());
+                    peer_addr.score
+                }
+                None => self
+                    .score_config
+                    .default_score
+                    .saturating_add(behaviour.score()),
+            };
             if score < self.score_config.ban_score {
                 self.ban_addr(
                     &peer.connected_addr,
@@ -204,45 +258,7 @@ impl PeerStore {
     }
 
     pub fn mut_ban_list(&mut self) -> &mut BanList {
+        self.ban_list_dirty = true;
         self.ban_list.get_mut()
     }
-
-    /// check and try delete addrs if reach limit
-    /// return Err if peer_store is full and can't be purge
-    fn check_purge(&mut self) -> Result<()> {
-        if self.addr_manager.count() < ADDR_COUNT_LIMIT {
-            return Ok(());
-        }
-        let now_ms = faketime::unix_time_as_millis();
-        let candidate_peers: Vec<_> = {
-            // find candidate peers by network group
-            let mut peers_by_network_group: HashMap<Group, Vec<_>> = HashMap::default();
-            for addr in self.addr_manager.addrs_iter() {
-                let network_group = addr.addr.network_group();
-                peers_by_network_group
-                    .entry(network_group)
-                    .or_default()
-                    .push(addr);
-            }
-            let ban_score = self.score_config.ban_score;
-            // find the largest network group
-            peers_by_network_group
-                .values()
-                .max_by_key(|peers| peers.len())
-                .expect("largest network group")
-                .iter()
-                .filter(move |addr| addr.is_terrible(now_ms) || addr.score <= ban_score)
-                .map(|addr| addr.ip_port())
-                .collect()
-        };
-
-        if candidate_peers.is_empty() {
-            return Err(PeerStoreError::EvictionFailed.into());
-        }
-
-        for key in candidate_peers {
-            self.addr_manager.remove(&key);
-        }
-        Ok(())
-    }
 }
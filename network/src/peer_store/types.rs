@@ -1,14 +1,20 @@
 use crate::{
     errors::{AddrError, Error},
+    network_group::{Group, NetworkGroup},
     peer_store::{
         peer_id_serde, PeerId, Score, SessionType, ADDR_MAX_FAILURES, ADDR_MAX_RETRIES,
         ADDR_TIMEOUT_MS,
     },
 };
+use ckb_hash::blake2b_256;
 use ipnetwork::IpNetwork;
 use p2p::multiaddr::{self, multihash::Multihash, Multiaddr, Protocol};
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv6Addr};
+
+/// Onion addresses are mapped into the OnionCat IPv6 range, so they can be keyed, bucketed and
+/// banned like IP addresses.
+const ONION_PREFIX: [u8; 6] = [0xfd, 0x87, 0xd8, 0x7e, 0xeb, 0x43];
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IpPort {
@@ -16,6 +22,20 @@ pub struct IpPort {
     pub port: u16,
 }
 
+impl IpPort {
+    /// 16 bytes IPv6 (IPv4 is mapped) followed by big endian port
+    pub fn to_bytes(&self) -> [u8; 18] {
+        let mut bytes = [0u8; 18];
+        let octets = match self.ip {
+            IpAddr::V4(ipv4) => ipv4.to_ipv6_mapped().octets(),
+            IpAddr::V6(ipv6) => ipv6.octets(),
+        };
+        bytes[..16].copy_from_slice(&octets);
+        bytes[16..].copy_from_slice(&self.port.to_be_bytes());
+        bytes
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
     pub peer_id: PeerId,
@@ -51,6 +71,9 @@ pub struct AddrInfo {
     pub last_tried_at_ms: u64,
     pub attempts_count: u32,
     pub random_id_pos: usize,
+    /// Network group of the peer which the address is learned from
+    #[serde(default)]
+    pub source: Option<Group>,
 }
 
 impl AddrInfo {
@@ -70,9 +93,17 @@ impl AddrInfo {
             last_tried_at_ms: 0,
             attempts_count: 0,
             random_id_pos: 0,
+            source: None,
         }
     }
 
+    /// The source group which decides the new bucket, the address itself if unknown
+    pub fn source_group(&self) -> Group {
+        self.source
+            .clone()
+            .unwrap_or_else(|| self.addr.network_group())
+    }
+
     pub fn ip_port(&self) -> IpPort {
         self.ip_port
     }
@@ -141,9 +172,27 @@ pub fn ip_to_network(ip: IpAddr) -> IpNetwork {
     }
 }
 
+/// Map onion host name to an IP in the onion range
+pub fn onion_to_ip(host: &str) -> IpAddr {
+    let hash = blake2b_256(host.to_lowercase().as_bytes());
+    let mut octets = [0u8; 16];
+    octets[..6].copy_from_slice(&ONION_PREFIX);
+    octets[6..].copy_from_slice(&hash[..10]);
+    IpAddr::V6(Ipv6Addr::from(octets))
+}
+
+pub fn is_onion_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V6(ipv6) => ipv6.octets()[..6] == ONION_PREFIX,
+        IpAddr::V4(_) => false,
+    }
+}
+
 pub trait MultiaddrExt {
     /// extract IP from multiaddr,
     fn extract_ip_addr(&self) -> Result<IpPort, Error>;
+    /// `/dns4/<name>.onion/tcp/<port>` is an onion address, it is only reachable through proxy
+    fn is_onion(&self) -> bool;
     fn exclude_p2p(&self) -> Multiaddr;
     fn attach_p2p(&self, peer_id: &PeerId) -> Result<Multiaddr, Error>;
 }
@@ -156,6 +205,9 @@ impl MultiaddrExt for Multiaddr {
             match component {
                 Protocol::Ip4(ipv4) => ip = Some(IpAddr::V4(ipv4)),
                 Protocol::Ip6(ipv6) => ip = Some(IpAddr::V6(ipv6)),
+                Protocol::Dns4(ref host) | Protocol::Dns6(ref host) if is_onion_host(host) => {
+                    ip = Some(onion_to_ip(host))
+                }
                 Protocol::Tcp(tcp_port) => port = Some(tcp_port),
                 _ => (),
             }
@@ -166,6 +218,13 @@ impl MultiaddrExt for Multiaddr {
         })
     }
 
+    fn is_onion(&self) -> bool {
+        self.iter().any(|component| match component {
+            Protocol::Dns4(ref host) | Protocol::Dns6(ref host) => is_onion_host(host),
+            _ => false,
+        })
+    }
+
     fn exclude_p2p(&self) -> Multiaddr {
         self.iter()
             .filter_map(|proto| match proto {
@@ -183,3 +242,7 @@ impl MultiaddrExt for Multiaddr {
         Ok(addr)
     }
 }
+
+fn is_onion_host(host: &str) -> bool {
+    host.to_lowercase().ends_with(".onion")
+}
@@ -1,5 +1,5 @@
 // use crate::peer_store::Behaviour;
-use crate::peer_store::types::AddrInfo;
+use crate::peer_store::types::{AddrInfo, MultiaddrExt};
 use crate::NetworkState;
 use ckb_logger::{debug, error, trace, warn};
 use futures::{sync::mpsc, sync::oneshot, Async, Future, Stream};
@@ -176,6 +176,10 @@ impl DiscoveryService {
     }
 
     fn is_valid_addr(&self, addr: &Multiaddr) -> bool {
+        // onion addresses are only dialed through proxy, keep them anyway
+        if addr.is_onion() {
+            return true;
+        }
         if !self.discovery_local_address {
             let local_or_invalid = multiaddr_to_socketaddr(&addr)
                 .map(|socket_addr| !is_reachable(socket_addr.ip()))
@@ -201,12 +205,20 @@ impl DiscoveryService {
             }
             DiscoveryEvent::AddNewAddrs { session_id, addrs } => {
                 if let Some(_peer_id) = self.sessions.get(&session_id) {
-                    // TODO: wait for peer store update
+                    // addresses are bucketed by the network group of the session sent them
+                    let source = match self.network_state.with_peer_registry(|reg| {
+                        reg.get_peer(session_id).map(|peer| peer.network_group())
+                    }) {
+                        Some(source) => source,
+                        None => return,
+                    };
                     for addr in addrs.into_iter().filter(|addr| self.is_valid_addr(addr)) {
                         trace!("Add discovered address:{:?}", addr);
                         if let Some(peer_id) = extract_peer_id(&addr) {
                             self.network_state.with_peer_store_mut(|peer_store| {
-                                if let Err(err) = peer_store.add_addr(peer_id.clone(), addr) {
+                                if let Err(err) =
+                                    peer_store.add_addr_from(peer_id.clone(), addr, &source)
+                                {
                                     debug!(
                                         "Failed to add discoved address to peer_store {:?} {:?}",
                                         err, peer_id
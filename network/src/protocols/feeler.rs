@@ -33,10 +33,9 @@ impl ServiceProtocol for Feeler {
             .as_ref()
             .map(PublicKey::peer_id)
             .expect("Secio must enabled");
+        let addr = self.network_state.original_addr(&session.address);
         self.network_state.with_peer_store_mut(|peer_store| {
-            if let Err(err) =
-                peer_store.add_connected_peer(peer_id.clone(), session.address.clone(), session.ty)
-            {
+            if let Err(err) = peer_store.add_connected_peer(peer_id.clone(), addr, session.ty) {
                 debug!(
                     "Failed to add connected peer to peer_store {:?} {:?} {:?}",
                     err, peer_id, session
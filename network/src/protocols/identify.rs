@@ -1,5 +1,9 @@
 // use crate::peer_store::Behaviour;
-use crate::{network::FEELER_PROTOCOL_ID, NetworkState, PeerIdentifyInfo};
+use crate::{
+    config::CompressionConfig,
+    network::{FEELER_PROTOCOL_ID, PNET_PROTOCOL_ID},
+    CompressionType, NetworkState, PeerIdentifyInfo,
+};
 use ckb_logger::{debug, trace};
 use ckb_types::{bytes::Bytes, packed, prelude::*};
 use p2p::{
@@ -26,7 +30,7 @@ impl IdentifyCallback {
         name: String,
         client_version: String,
     ) -> IdentifyCallback {
-        let flags = Flags(Flag::FullNode as u64);
+        let flags = local_flags(&network_state.config.compression);
 
         IdentifyCallback {
             network_state,
@@ -58,24 +62,30 @@ impl Callback for IdentifyCallback {
         match self.identify.verify(identify) {
             None => MisbehaveResult::Disconnect,
             Some((flags, client_version)) => {
+                let compression =
+                    negotiate_compression(&self.network_state.config.compression, flags);
                 let registry_client_version = |version: String| {
                     self.network_state.with_peer_registry_mut(|registry| {
                         if let Some(peer) = registry.get_peer_mut(context.session.id) {
                             peer.identify_info = Some(PeerIdentifyInfo {
                                 client_version: version,
+                                compression,
                             })
                         }
                     });
+                    self.network_state
+                        .set_session_compression(context.session.id, compression);
                 };
 
                 if context.session.ty.is_outbound() {
-                    if flags.contains(self.identify.flags) {
+                    // Only the full node flag is required, the other flags are optional features
+                    if flags.contains(Flag::FullNode.into()) {
                         registry_client_version(client_version);
 
                         // The remote end can support all local protocols.
-                        let protos = self
-                            .network_state
-                            .get_protocol_ids(|id| id != FEELER_PROTOCOL_ID.into());
+                        let protos = self.network_state.get_protocol_ids(|id| {
+                            id != FEELER_PROTOCOL_ID.into() && id != PNET_PROTOCOL_ID.into()
+                        });
 
                         let _ = context
                             .open_protocols(context.session.id, TargetProtocol::Multi(protos));
@@ -102,17 +112,21 @@ impl Callback for IdentifyCallback {
             peer_id,
             addrs,
         );
-        self.network_state.with_peer_registry_mut(|reg| {
-            if let Some(peer) = reg
-                .get_key_by_peer_id(peer_id)
+        let source = self.network_state.with_peer_registry_mut(|reg| {
+            reg.get_key_by_peer_id(peer_id)
                 .and_then(|session_id| reg.get_peer_mut(session_id))
-            {
-                peer.listened_addrs = addrs.clone();
-            }
+                .map(|peer| {
+                    peer.listened_addrs = addrs.clone();
+                    peer.network_group()
+                })
         });
         self.network_state.with_peer_store_mut(|peer_store| {
             for addr in addrs {
-                if let Err(err) = peer_store.add_addr(peer_id.clone(), addr) {
+                let result = match source {
+                    Some(ref source) => peer_store.add_addr_from(peer_id.clone(), addr, source),
+                    None => peer_store.add_addr(peer_id.clone(), addr),
+                };
+                if let Err(err) = result {
                     debug!("Failed to add addrs to peer_store {:?} {:?}", err, peer_id);
                 }
             }
@@ -135,6 +149,11 @@ impl Callback for IdentifyCallback {
             return MisbehaveResult::Continue;
         }
 
+        // never learn or advertise the real address
+        if self.network_state.hide_listen_addresses() {
+            return MisbehaveResult::Continue;
+        }
+
         // observed addr is not a reachable ip
         if !multiaddr_to_socketaddr(&addr)
             .map(|socket_addr| is_reachable(socket_addr.ip()))
@@ -217,11 +236,44 @@ impl Identify {
     }
 }
 
+// The flags announced by the local node, one for each supported compression format
+fn local_flags(compression: &CompressionConfig) -> Flags {
+    compression
+        .codecs()
+        .into_iter()
+        .filter_map(Flag::from_compression)
+        .fold(Flags::from(Flag::FullNode), |flags, flag| flags.with(flag))
+}
+
+// The compression format used with a remote node which announced `remote_flags`
+fn negotiate_compression(compression: &CompressionConfig, remote_flags: Flags) -> CompressionType {
+    compression.negotiate(|codec| {
+        Flag::from_compression(codec)
+            .map(|flag| remote_flags.contains(flag.into()))
+            .unwrap_or(false)
+    })
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(u64)]
 enum Flag {
     /// Support all protocol
     FullNode = 0x1,
+    /// Support zstd compressed messages
+    CompressZstd = 0x2,
+    /// Support lz4 compressed messages
+    CompressLz4 = 0x4,
+}
+
+impl Flag {
+    /// Snappy needs no flag since all nodes support it
+    fn from_compression(codec: CompressionType) -> Option<Flag> {
+        match codec {
+            CompressionType::Snappy => None,
+            CompressionType::Zstd => Some(Flag::CompressZstd),
+            CompressionType::Lz4 => Some(Flag::CompressLz4),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
@@ -232,6 +284,10 @@ impl Flags {
     fn contains(self, flags: Flags) -> bool {
         (self.0 & flags.0) == flags.0
     }
+
+    fn with(self, flag: Flag) -> Flags {
+        Flags(self.0 | flag as u64)
+    }
 }
 
 impl From<Flag> for Flags {
@@ -245,3 +301,49 @@ impl From<u64> for Flags {
         Flags(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(codecs: Vec<CompressionType>) -> CompressionConfig {
+        CompressionConfig {
+            threshold: None,
+            codecs,
+        }
+    }
+
+    #[test]
+    fn test_negotiate_compression() {
+        let default = config(vec![]);
+        let lz4 = config(vec![CompressionType::Lz4, CompressionType::Snappy]);
+        let zstd = config(vec![CompressionType::Zstd, CompressionType::Snappy]);
+        let snappy = config(vec![CompressionType::Snappy]);
+
+        assert_eq!(
+            negotiate_compression(&default, local_flags(&default)),
+            CompressionType::Zstd
+        );
+        assert_eq!(
+            negotiate_compression(&lz4, local_flags(&default)),
+            CompressionType::Lz4
+        );
+        assert_eq!(
+            negotiate_compression(&default, local_flags(&lz4)),
+            CompressionType::Lz4
+        );
+        assert_eq!(
+            negotiate_compression(&lz4, local_flags(&zstd)),
+            CompressionType::Snappy
+        );
+        assert_eq!(
+            negotiate_compression(&default, local_flags(&snappy)),
+            CompressionType::Snappy
+        );
+        // legacy nodes only announce the full node flag
+        assert_eq!(
+            negotiate_compression(&default, Flags::from(Flag::FullNode)),
+            CompressionType::Snappy
+        );
+    }
+}
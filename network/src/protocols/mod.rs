@@ -3,6 +3,7 @@ pub(crate) mod discovery;
 pub(crate) mod feeler;
 pub(crate) mod identify;
 pub(crate) mod ping;
+pub(crate) mod pnet;
 #[cfg(test)]
 mod test;
 
@@ -23,10 +24,10 @@ use tokio::codec::length_delimited;
 pub type PeerIndex = SessionId;
 pub type BoxedFutureTask = Box<dyn Future<Item = (), Error = ()> + 'static + Send>;
 
+use self::pnet::PnetGuard;
 use crate::{
-    compress::{compress, decompress},
-    network::disconnect_with_message,
-    Behaviour, Error, NetworkState, Peer, PeerRegistry, ProtocolVersion, MAX_FRAME_LENGTH,
+    compress::decompress, network::disconnect_with_message, Behaviour, Error, NetworkState, Peer,
+    PeerRegistry, ProtocolVersion, MAX_FRAME_LENGTH,
 };
 
 pub trait CKBProtocolContext: Send {
@@ -150,13 +151,15 @@ impl CKBProtocol {
             })
             .support_versions(supported_versions)
             .service_handle(move || {
-                ProtocolHandle::Both(Box::new(CKBHandler {
-                    proto_id: self.id,
-                    network_state: Arc::clone(&self.network_state),
-                    handler: (self.handler)(),
-                }))
+                ProtocolHandle::Both(Box::new(PnetGuard::new(
+                    CKBHandler {
+                        proto_id: self.id,
+                        network_state: Arc::clone(&self.network_state),
+                        handler: (self.handler)(),
+                    },
+                    Arc::clone(&self.network_state),
+                )))
             })
-            .before_send(compress)
             .before_receive(|| Some(Box::new(decompress)))
             .build()
     }
@@ -277,6 +280,7 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        let data = self.network_state.compress(proto_id, peer_index, data);
         self.p2p_control
             .quick_send_message_to(peer_index, proto_id, data)?;
         Ok(())
@@ -288,13 +292,19 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        let data = self.network_state.compress(self.proto_id, peer_index, data);
         self.p2p_control
             .quick_send_message_to(peer_index, self.proto_id, data)?;
         Ok(())
     }
     fn quick_filter_broadcast(&self, target: TargetSession, data: Bytes) -> Result<(), Error> {
-        self.p2p_control
-            .quick_filter_broadcast(target, self.proto_id, data)?;
+        for (target, data) in self
+            .network_state
+            .compress_for_sessions(self.proto_id, target, data)
+        {
+            self.p2p_control
+                .quick_filter_broadcast(target, self.proto_id, data)?;
+        }
         Ok(())
     }
     fn future_task(&self, task: BoxedFutureTask, blocking: bool) -> Result<(), Error> {
@@ -318,6 +328,7 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        let data = self.network_state.compress(proto_id, peer_index, data);
         self.p2p_control
             .send_message_to(peer_index, proto_id, data)?;
         Ok(())
@@ -329,13 +340,19 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
             peer_index,
             data.len()
         );
+        let data = self.network_state.compress(self.proto_id, peer_index, data);
         self.p2p_control
             .send_message_to(peer_index, self.proto_id, data)?;
         Ok(())
     }
     fn filter_broadcast(&self, target: TargetSession, data: Bytes) -> Result<(), Error> {
-        self.p2p_control
-            .filter_broadcast(target, self.proto_id, data)?;
+        for (target, data) in self
+            .network_state
+            .compress_for_sessions(self.proto_id, target, data)
+        {
+            self.p2p_control
+                .filter_broadcast(target, self.proto_id, data)?;
+        }
         Ok(())
     }
     fn disconnect(&self, peer_index: PeerIndex, message: &str) -> Result<(), Error> {
@@ -0,0 +1,585 @@
+use crate::network::{disconnect_with_message, FEELER_PROTOCOL_ID, IDENTIFY_PROTOCOL_ID};
+use crate::NetworkState;
+use ckb_hash::new_blake2b;
+use ckb_logger::{debug, trace};
+use p2p::{
+    bytes::Bytes,
+    context::{ProtocolContext, ProtocolContextMutRef},
+    secio::PublicKey,
+    service::TargetProtocol,
+    traits::ServiceProtocol,
+    SessionId,
+};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub(crate) const PSK_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 32;
+const PROOF_LENGTH: usize = 32;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const CHECK_TIMEOUT_INTERVAL: Duration = Duration::from_secs(5);
+const CHECK_TIMEOUT_TOKEN: u64 = 0;
+
+const TAG_NONCE: u8 = 1;
+const TAG_CHALLENGE: u8 = 2;
+const TAG_PROOF: u8 = 3;
+const TAG_ACCEPT: u8 = 4;
+
+const ROLE_INBOUND: &[u8] = b"pnet-inbound";
+const ROLE_OUTBOUND: &[u8] = b"pnet-outbound";
+
+pub(crate) type PreSharedKey = [u8; PSK_LENGTH];
+
+enum HandshakeState {
+    /// Inbound: waiting for the nonce of outbound side
+    WaitNonce,
+    /// Outbound: sent nonce, waiting for challenge
+    WaitChallenge { local_nonce: [u8; NONCE_LENGTH] },
+    /// Inbound: sent challenge, waiting for proof
+    WaitProof {
+        outbound_nonce: [u8; NONCE_LENGTH],
+        inbound_nonce: [u8; NONCE_LENGTH],
+    },
+    /// Outbound: sent proof, waiting for accept
+    WaitAccept,
+}
+
+/// The handshake messages and their verification, independent of sessions
+struct Handshake {
+    psk: PreSharedKey,
+}
+
+/// The peer ids of both ends of a secio session, as seen by the local node
+struct SessionPeers {
+    outbound: Vec<u8>,
+    inbound: Vec<u8>,
+}
+
+/// What to do after a handshake message
+struct Transition {
+    /// The next state, `None` if the session is authorized
+    next: Option<HandshakeState>,
+    reply: Option<Vec<u8>>,
+}
+
+impl Handshake {
+    fn proof(
+        &self,
+        role: &[u8],
+        session: &SessionPeers,
+        outbound_nonce: &[u8; NONCE_LENGTH],
+        inbound_nonce: &[u8; NONCE_LENGTH],
+    ) -> [u8; PROOF_LENGTH] {
+        let mut result = [0u8; PROOF_LENGTH];
+        let mut blake2b = new_blake2b();
+        blake2b.update(&self.psk);
+        blake2b.update(role);
+        blake2b.update(&session.outbound);
+        blake2b.update(&session.inbound);
+        blake2b.update(outbound_nonce);
+        blake2b.update(inbound_nonce);
+        blake2b.finalize(&mut result);
+        result
+    }
+
+    /// The initial state of a session and the message to send
+    fn start(&self, outbound: bool) -> (HandshakeState, Option<Vec<u8>>) {
+        if outbound {
+            let local_nonce = random_nonce();
+            (
+                HandshakeState::WaitChallenge { local_nonce },
+                Some(message(TAG_NONCE, &[&local_nonce])),
+            )
+        } else {
+            (HandshakeState::WaitNonce, None)
+        }
+    }
+
+    fn process(
+        &self,
+        state: HandshakeState,
+        session: &SessionPeers,
+        data: &[u8],
+    ) -> Result<Transition, &'static str> {
+        if data.is_empty() {
+            return Err("empty message");
+        }
+        let (tag, payload) = (data[0], &data[1..]);
+        match (state, tag) {
+            (HandshakeState::WaitNonce, TAG_NONCE) if payload.len() == NONCE_LENGTH => {
+                let outbound_nonce = read_array(payload);
+                let inbound_nonce = random_nonce();
+                let proof = self.proof(ROLE_INBOUND, session, &outbound_nonce, &inbound_nonce);
+                Ok(Transition {
+                    next: Some(HandshakeState::WaitProof {
+                        outbound_nonce,
+                        inbound_nonce,
+                    }),
+                    reply: Some(message(TAG_CHALLENGE, &[&inbound_nonce, &proof])),
+                })
+            }
+            (HandshakeState::WaitChallenge { local_nonce }, TAG_CHALLENGE)
+                if payload.len() == NONCE_LENGTH + PROOF_LENGTH =>
+            {
+                let inbound_nonce = read_array(&payload[..NONCE_LENGTH]);
+                let expected = self.proof(ROLE_INBOUND, session, &local_nonce, &inbound_nonce);
+                if !proof_eq(&expected, &payload[NONCE_LENGTH..]) {
+                    return Err("invalid challenge proof");
+                }
+                let proof = self.proof(ROLE_OUTBOUND, session, &local_nonce, &inbound_nonce);
+                Ok(Transition {
+                    next: Some(HandshakeState::WaitAccept),
+                    reply: Some(message(TAG_PROOF, &[&proof])),
+                })
+            }
+            (
+                HandshakeState::WaitProof {
+                    outbound_nonce,
+                    inbound_nonce,
+                },
+                TAG_PROOF,
+            ) if payload.len() == PROOF_LENGTH => {
+                let expected = self.proof(ROLE_OUTBOUND, session, &outbound_nonce, &inbound_nonce);
+                if !proof_eq(&expected, payload) {
+                    return Err("invalid proof");
+                }
+                Ok(Transition {
+                    next: None,
+                    reply: Some(message(TAG_ACCEPT, &[])),
+                })
+            }
+            (HandshakeState::WaitAccept, TAG_ACCEPT) if payload.is_empty() => Ok(Transition {
+                next: None,
+                reply: None,
+            }),
+            _ => Err("malformed message"),
+        }
+    }
+}
+
+/// Private network handshake
+///
+/// Every session must prove it knows the pre-shared key before other protocols are usable:
+///
+/// ```text
+/// outbound                                     inbound
+///    | ---- NONCE(outbound_nonce) ----------------> |
+///    | <--- CHALLENGE(inbound_nonce, inbound_proof) |
+///    | ---- PROOF(outbound_proof) ----------------> |  authorized
+///    | <--- ACCEPT -------------------------------- |
+/// authorized, open identify
+/// ```
+///
+/// The proof is `blake2b(psk | role | outbound_peer_id | inbound_peer_id | outbound_nonce |
+/// inbound_nonce)`. The role makes a proof unusable in the reverse direction, and the peer ids
+/// of the secio session bind it to the session, so a man in the middle can not relay the
+/// proofs of a member between its own sessions.
+///
+/// NOTE: tentacle does not expose the raw transport, so the handshake runs on top of secio,
+/// and other protocols are guarded by `PnetGuard` until it finished. A session is registered
+/// to the peer registry only after the handshake, so unauthorized sessions can not occupy
+/// peer slots or evict other peers.
+pub(crate) struct PnetProtocol {
+    network_state: Arc<NetworkState>,
+    handshake: Handshake,
+    pending: HashMap<SessionId, (Instant, HandshakeState)>,
+}
+
+impl PnetProtocol {
+    pub(crate) fn new(network_state: Arc<NetworkState>, psk: PreSharedKey) -> Self {
+        PnetProtocol {
+            network_state,
+            handshake: Handshake { psk },
+            pending: HashMap::default(),
+        }
+    }
+
+    fn reject(&mut self, context: &ProtocolContextMutRef, reason: &str) {
+        let session_id = context.session.id;
+        debug!(
+            "private network handshake with {} failed: {}",
+            context.session.address, reason
+        );
+        self.pending.remove(&session_id);
+        if let Err(err) = disconnect_with_message(
+            context.control(),
+            session_id,
+            "private network authentication failed",
+        ) {
+            debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+        }
+    }
+
+    fn send(&self, context: &ProtocolContextMutRef, data: Vec<u8>) {
+        if let Err(err) = context.control().quick_send_message_to(
+            context.session.id,
+            context.proto_id,
+            Bytes::from(data),
+        ) {
+            debug!(
+                "send private network message to {} failed: {:?}",
+                context.session.id, err
+            );
+        }
+    }
+
+    fn session_peers(&self, context: &ProtocolContextMutRef) -> SessionPeers {
+        let local = self.network_state.local_peer_id().as_bytes().to_vec();
+        let remote = context
+            .session
+            .remote_pubkey
+            .as_ref()
+            .map(|pubkey| pubkey.peer_id().as_bytes().to_vec())
+            .expect("Secio must enabled");
+        if context.session.ty.is_outbound() {
+            SessionPeers {
+                outbound: local,
+                inbound: remote,
+            }
+        } else {
+            SessionPeers {
+                outbound: remote,
+                inbound: local,
+            }
+        }
+    }
+
+    fn authorized(&mut self, context: &ProtocolContextMutRef) {
+        let session = context.session;
+        self.pending.remove(&session.id);
+        // Unauthorized sessions are neither registered nor counted by the eviction
+        if !self
+            .network_state
+            .register_session(context.control(), session)
+        {
+            return;
+        }
+        self.network_state.authorize_session(session.id);
+        trace!("private network session {} authorized", session.id);
+        if session.ty.is_outbound() {
+            let peer_id = session
+                .remote_pubkey
+                .as_ref()
+                .map(PublicKey::peer_id)
+                .expect("Secio must enabled");
+            let proto_id = if self
+                .network_state
+                .with_peer_registry(|reg| reg.is_feeler(&peer_id))
+            {
+                FEELER_PROTOCOL_ID
+            } else {
+                IDENTIFY_PROTOCOL_ID
+            };
+            if let Err(err) = context
+                .control()
+                .open_protocols(session.id, TargetProtocol::Single(proto_id.into()))
+            {
+                debug!("open protocol {} failed: {:?}", proto_id, err);
+            }
+        }
+    }
+}
+
+fn message(tag: u8, payload: &[&[u8]]) -> Vec<u8> {
+    let mut data = vec![tag];
+    for part in payload {
+        data.extend_from_slice(part);
+    }
+    data
+}
+
+fn read_array(data: &[u8]) -> [u8; 32] {
+    let mut array = [0u8; 32];
+    array.copy_from_slice(data);
+    array
+}
+
+fn random_nonce() -> [u8; NONCE_LENGTH] {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rand::thread_rng().fill(&mut nonce);
+    nonce
+}
+
+// Compare proof in constant time
+fn proof_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+impl ServiceProtocol for PnetProtocol {
+    fn init(&mut self, context: &mut ProtocolContext) {
+        context
+            .control()
+            .set_service_notify(
+                context.proto_id,
+                CHECK_TIMEOUT_INTERVAL,
+                CHECK_TIMEOUT_TOKEN,
+            )
+            .expect("set_notify at init should be ok");
+    }
+
+    fn connected(&mut self, context: ProtocolContextMutRef, _: &str) {
+        let (state, data) = self.handshake.start(context.session.ty.is_outbound());
+        if let Some(data) = data {
+            self.send(&context, data);
+        }
+        self.pending
+            .insert(context.session.id, (Instant::now(), state));
+    }
+
+    fn disconnected(&mut self, context: ProtocolContextMutRef) {
+        self.pending.remove(&context.session.id);
+    }
+
+    fn received(&mut self, context: ProtocolContextMutRef, data: Bytes) {
+        let session_id = context.session.id;
+        let state = match self.pending.remove(&session_id) {
+            Some((_, state)) => state,
+            None => {
+                self.reject(&context, "unexpected message");
+                return;
+            }
+        };
+        let session = self.session_peers(&context);
+        match self.handshake.process(state, &session, &data) {
+            Ok(Transition { next, reply }) => {
+                match next {
+                    Some(next) => {
+                        self.pending.insert(session_id, (Instant::now(), next));
+                    }
+                    None => self.authorized(&context),
+                }
+                if let Some(reply) = reply {
+                    self.send(&context, reply);
+                }
+            }
+            Err(reason) => self.reject(&context, reason),
+        }
+    }
+
+    fn notify(&mut self, context: &mut ProtocolContext, token: u64) {
+        if token != CHECK_TIMEOUT_TOKEN {
+            return;
+        }
+        let now = Instant::now();
+        let timeout_sessions = self
+            .pending
+            .iter()
+            .filter(|(_, (started, _))| now.duration_since(*started) > HANDSHAKE_TIMEOUT)
+            .map(|(session_id, _)| *session_id)
+            .collect::<Vec<_>>();
+        for session_id in timeout_sessions {
+            debug!("private network handshake with {} timeout", session_id);
+            self.pending.remove(&session_id);
+            if let Err(err) = disconnect_with_message(
+                context.control(),
+                session_id,
+                "private network handshake timeout",
+            ) {
+                debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+            }
+        }
+    }
+}
+
+/// Forward events to inner protocol only if the session passed private network handshake,
+/// unauthorized sessions are disconnected as soon as they open a guarded protocol.
+pub(crate) struct PnetGuard<P> {
+    inner: P,
+    network_state: Arc<NetworkState>,
+    sessions: HashSet<SessionId>,
+}
+
+impl<P: ServiceProtocol> PnetGuard<P> {
+    pub(crate) fn new(inner: P, network_state: Arc<NetworkState>) -> Self {
+        PnetGuard {
+            inner,
+            network_state,
+            sessions: HashSet::default(),
+        }
+    }
+}
+
+impl<P: ServiceProtocol> ServiceProtocol for PnetGuard<P> {
+    fn init(&mut self, context: &mut ProtocolContext) {
+        self.inner.init(context)
+    }
+
+    fn connected(&mut self, context: ProtocolContextMutRef, version: &str) {
+        let session_id = context.session.id;
+        if self.network_state.is_session_authorized(session_id) {
+            self.sessions.insert(session_id);
+            self.inner.connected(context, version);
+        } else {
+            debug!(
+                "unauthorized session {} open protocol {}, disconnect it",
+                session_id, context.proto_id
+            );
+            if let Err(err) =
+                disconnect_with_message(context.control(), session_id, "unauthorized session")
+            {
+                debug!("Disconnect failed {:?}, error: {:?}", session_id, err);
+            }
+        }
+    }
+
+    fn disconnected(&mut self, context: ProtocolContextMutRef) {
+        if self.sessions.remove(&context.session.id) {
+            self.inner.disconnected(context);
+        }
+    }
+
+    fn received(&mut self, context: ProtocolContextMutRef, data: Bytes) {
+        if self.sessions.contains(&context.session.id) {
+            self.inner.received(context, data);
+        }
+    }
+
+    fn notify(&mut self, context: &mut ProtocolContext, token: u64) {
+        self.inner.notify(context, token)
+    }
+
+    fn poll(&mut self, context: &mut ProtocolContext) {
+        self.inner.poll(context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Handshake, SessionPeers, Transition, PSK_LENGTH, TAG_ACCEPT, TAG_NONCE, TAG_PROOF,
+    };
+
+    fn session(outbound: u8, inbound: u8) -> SessionPeers {
+        SessionPeers {
+            outbound: vec![outbound; 32],
+            inbound: vec![inbound; 32],
+        }
+    }
+
+    // Runs the handshake over the sessions seen by each side, returns the failure reason and
+    // whether the outbound side failed
+    fn run(
+        outbound: (&Handshake, SessionPeers),
+        inbound: (&Handshake, SessionPeers),
+    ) -> Result<(), (&'static str, bool)> {
+        let (outbound, outbound_session) = outbound;
+        let (inbound, inbound_session) = inbound;
+        let (outbound_state, data) = outbound.start(true);
+        let (inbound_state, none) = inbound.start(false);
+        assert!(none.is_none());
+        // (handshake, state) of the receiver and the sender of the next message
+        let mut receiver = (inbound, &inbound_session, Some(inbound_state));
+        let mut sender = (outbound, &outbound_session, Some(outbound_state));
+        let mut data = data.expect("outbound side sends nonce");
+        loop {
+            let is_outbound = std::ptr::eq(receiver.1, &outbound_session);
+            let state = receiver.2.take().expect("handshake is not finished");
+            let Transition { next, reply } = receiver
+                .0
+                .process(state, receiver.1, &data)
+                .map_err(|reason| (reason, is_outbound))?;
+            receiver.2 = next;
+            match reply {
+                Some(reply) => data = reply,
+                None => break,
+            }
+            std::mem::swap(&mut receiver, &mut sender);
+        }
+        // Both sides are authorized
+        assert!(receiver.2.is_none() && sender.2.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_success() {
+        let psk = [1u8; PSK_LENGTH];
+        assert_eq!(
+            run(
+                (&Handshake { psk }, session(1, 2)),
+                (&Handshake { psk }, session(1, 2))
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_handshake_with_wrong_key() {
+        let outbound = Handshake {
+            psk: [1u8; PSK_LENGTH],
+        };
+        let inbound = Handshake {
+            psk: [2u8; PSK_LENGTH],
+        };
+        // The outbound side finds out first, it never reveals a proof of its key
+        assert_eq!(
+            run((&outbound, session(1, 2)), (&inbound, session(1, 2))),
+            Err(("invalid challenge proof", true))
+        );
+    }
+
+    #[test]
+    fn test_handshake_rejects_relayed_proofs() {
+        let handshake = Handshake {
+            psk: [1u8; PSK_LENGTH],
+        };
+        // A man in the middle (3) relays the messages between its session with the outbound
+        // member (1) and its session with the inbound member (2)
+        assert_eq!(
+            run((&handshake, session(1, 3)), (&handshake, session(3, 2))),
+            Err(("invalid challenge proof", true))
+        );
+    }
+
+    #[test]
+    fn test_handshake_rejects_forged_proof() {
+        let handshake = Handshake {
+            psk: [1u8; PSK_LENGTH],
+        };
+        let session = session(1, 2);
+        let (state, _) = handshake.start(false);
+        let challenge = handshake
+            .process(
+                state,
+                &session,
+                &[&[TAG_NONCE][..], &[0u8; 32][..]].concat(),
+            )
+            .unwrap();
+        let state = challenge.next.unwrap();
+        let forged = [&[TAG_PROOF][..], &[0u8; 32][..]].concat();
+        assert_eq!(
+            handshake.process(state, &session, &forged).err(),
+            Some("invalid proof")
+        );
+    }
+
+    #[test]
+    fn test_handshake_rejects_malformed_message() {
+        let handshake = Handshake {
+            psk: [1u8; PSK_LENGTH],
+        };
+        let session = session(1, 2);
+        let (state, _) = handshake.start(false);
+        assert_eq!(
+            handshake.process(state, &session, &[]).err(),
+            Some("empty message")
+        );
+        // Skipping the challenge is not allowed
+        let (state, _) = handshake.start(false);
+        assert_eq!(
+            handshake.process(state, &session, &[TAG_ACCEPT]).err(),
+            Some("malformed message")
+        );
+        // Short nonce
+        let (state, _) = handshake.start(false);
+        assert_eq!(
+            handshake.process(state, &session, &[TAG_NONCE, 0]).err(),
+            Some("malformed message")
+        );
+    }
+}
@@ -108,6 +108,7 @@ fn net_service_start(name: String) -> Node {
         dns_seeds: vec![],
         whitelist_peers: vec![],
         whitelist_only: false,
+        private_network: false,
         max_peers: 19,
         max_outbound_peers: 5,
         path: tempdir()
@@ -121,6 +122,9 @@ fn net_service_start(name: String) -> Node {
         upnp: false,
         bootnode_mode: true,
         max_send_buffer: None,
+        compression: Default::default(),
+        proxy: None,
+        hide_listen_addresses: false,
     };
 
     let network_state =
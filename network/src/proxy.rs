@@ -0,0 +1,350 @@
+//! SOCKS5 proxy for outbound connections
+//!
+//! tentacle dials with its own TCP transport, so a dial through the proxy is done by a one-shot
+//! local relay: the network service dials the relay on loopback, the relay connects to the proxy,
+//! sends a CONNECT request for the real target, then pipes bytes in both directions. The relay is
+//! a task on the network runtime, it relays the first connection it accepts from this process and
+//! then closes the listener, so it never connects to the proxy before being dialed and never
+//! serves a second connection. Connections from other local processes are dropped, on the
+//! platforms where the owner of a loopback connection can not be checked secio still
+//! authenticates the remote peer id end to end, so a racing process only makes the dial fail.
+//!
+//! Domain names (include onion addresses) are resolved by the proxy, never locally.
+
+use crate::errors::{ConfigError, Error};
+use ckb_logger::debug;
+use futures::{future, Future, Stream};
+use p2p::multiaddr::{Multiaddr, Protocol};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+use tokio::io::{copy, read_exact, shutdown, write_all, AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::reactor::Handle;
+use tokio::timer::Timeout;
+
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0;
+const METHOD_USERNAME_PASSWORD: u8 = 2;
+const COMMAND_CONNECT: u8 = 1;
+const ADDRESS_TYPE_IPV4: u8 = 1;
+const ADDRESS_TYPE_DOMAIN: u8 = 3;
+const ADDRESS_TYPE_IPV6: u8 = 4;
+
+const PROXY_TIMEOUT: Duration = Duration::from_secs(30);
+// The network service dials the relay right after it is created
+const RELAY_ACCEPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type IoFuture<T> = Box<dyn Future<Item = T, Error = io::Error> + Send>;
+/// Relay task of a proxied dial
+pub(crate) type RelayTask = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// Target of a CONNECT request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProxyTarget {
+    Ip(SocketAddr),
+    Domain(String, u16),
+}
+
+impl ProxyTarget {
+    pub(crate) fn from_multiaddr(addr: &Multiaddr) -> Option<ProxyTarget> {
+        let mut ip = None;
+        let mut domain = None;
+        let mut port = None;
+        for component in addr {
+            match component {
+                Protocol::Ip4(ipv4) => ip = Some(IpAddr::V4(ipv4)),
+                Protocol::Ip6(ipv6) => ip = Some(IpAddr::V6(ipv6)),
+                Protocol::Dns4(name) | Protocol::Dns6(name) => domain = Some(name.to_string()),
+                Protocol::Tcp(tcp_port) => port = Some(tcp_port),
+                _ => (),
+            }
+        }
+        let port = port?;
+        match (ip, domain) {
+            (Some(ip), _) => Some(ProxyTarget::Ip(SocketAddr::new(ip, port))),
+            (None, Some(domain)) => Some(ProxyTarget::Domain(domain, port)),
+            (None, None) => None,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), io::Error> {
+        match self {
+            ProxyTarget::Ip(SocketAddr::V4(addr)) => {
+                buf.push(ADDRESS_TYPE_IPV4);
+                buf.extend_from_slice(&addr.ip().octets());
+                buf.extend_from_slice(&addr.port().to_be_bytes());
+            }
+            ProxyTarget::Ip(SocketAddr::V6(addr)) => {
+                buf.push(ADDRESS_TYPE_IPV6);
+                buf.extend_from_slice(&addr.ip().octets());
+                buf.extend_from_slice(&addr.port().to_be_bytes());
+            }
+            ProxyTarget::Domain(domain, port) => {
+                if domain.is_empty() || domain.len() > 255 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "invalid domain length",
+                    ));
+                }
+                buf.push(ADDRESS_TYPE_DOMAIN);
+                buf.push(domain.len() as u8);
+                buf.extend_from_slice(domain.as_bytes());
+                buf.extend_from_slice(&port.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Socks5Proxy {
+    host: String,
+    port: u16,
+    auth: Option<(String, String)>,
+}
+
+impl Socks5Proxy {
+    /// Parse `socks5://[user:password@]host:port`
+    pub(crate) fn parse(url: &str) -> Result<Socks5Proxy, Error> {
+        let rest = if url.starts_with("socks5://") {
+            &url["socks5://".len()..]
+        } else {
+            return Err(ConfigError::InvalidProxy.into());
+        };
+        let (auth, host_port) = match rest.rfind('@') {
+            Some(index) => {
+                let mut parts = rest[..index].splitn(2, ':');
+                let user = parts.next().unwrap_or_default().to_owned();
+                let password = parts.next().unwrap_or_default().to_owned();
+                if user.is_empty() || user.len() > 255 || password.len() > 255 {
+                    return Err(ConfigError::InvalidProxy.into());
+                }
+                (Some((user, password)), &rest[index + 1..])
+            }
+            None => (None, rest),
+        };
+        let host_port = host_port.trim_end_matches('/');
+        let index = host_port.rfind(':').ok_or(ConfigError::InvalidProxy)?;
+        let host = host_port[..index]
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned();
+        let port = host_port[index + 1..]
+            .parse::<u16>()
+            .map_err(|_| ConfigError::InvalidProxy)?;
+        if host.is_empty() {
+            return Err(ConfigError::InvalidProxy.into());
+        }
+        Ok(Socks5Proxy { host, port, auth })
+    }
+
+    /// Connect to target through the proxy
+    pub(crate) fn connect(&self, target: ProxyTarget) -> IoFuture<TcpStream> {
+        let proxy_addr = match (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map(|mut addrs| addrs.next())
+        {
+            Ok(Some(proxy_addr)) => proxy_addr,
+            Ok(None) => {
+                return Box::new(future::err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "proxy address not found",
+                )))
+            }
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let proxy = self.clone();
+        let connect = TcpStream::connect(&proxy_addr)
+            .and_then(move |stream| proxy.handshake(stream, &target));
+        Box::new(Timeout::new(connect, PROXY_TIMEOUT).map_err(|err| {
+            err.into_inner()
+                .unwrap_or_else(|| io::ErrorKind::TimedOut.into())
+        }))
+    }
+
+    pub(crate) fn handshake<S>(&self, stream: S, target: &ProxyTarget) -> IoFuture<S>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let method = if self.auth.is_some() {
+            METHOD_USERNAME_PASSWORD
+        } else {
+            METHOD_NO_AUTH
+        };
+        let auth = self.auth.clone();
+        let mut request = vec![SOCKS_VERSION, COMMAND_CONNECT, 0];
+        if let Err(err) = target.encode(&mut request) {
+            return Box::new(future::err(err));
+        }
+        let handshake = write_all(stream, [SOCKS_VERSION, 1, method])
+            .and_then(|(stream, _)| read_exact(stream, [0u8; 2]))
+            .and_then(move |(stream, reply)| -> IoFuture<S> {
+                if reply[0] != SOCKS_VERSION || reply[1] != method {
+                    return Box::new(future::err(proxy_error(
+                        "no acceptable authentication method",
+                    )));
+                }
+                match auth {
+                    Some((user, password)) => {
+                        let mut request = vec![1, user.len() as u8];
+                        request.extend_from_slice(user.as_bytes());
+                        request.push(password.len() as u8);
+                        request.extend_from_slice(password.as_bytes());
+                        Box::new(
+                            write_all(stream, request)
+                                .and_then(|(stream, _)| read_exact(stream, [0u8; 2]))
+                                .and_then(|(stream, reply)| {
+                                    if reply[1] != 0 {
+                                        Err(proxy_error("authentication failed"))
+                                    } else {
+                                        Ok(stream)
+                                    }
+                                }),
+                        )
+                    }
+                    None => Box::new(future::ok(stream)),
+                }
+            })
+            .and_then(move |stream| write_all(stream, request))
+            .and_then(|(stream, _)| read_exact(stream, [0u8; 4]))
+            .and_then(|(stream, reply)| -> IoFuture<(S, usize)> {
+                if reply[0] != SOCKS_VERSION {
+                    return Box::new(future::err(proxy_error("invalid reply version")));
+                }
+                if reply[1] != 0 {
+                    return Box::new(future::err(proxy_error(&format!(
+                        "connect failed, reply code {}",
+                        reply[1]
+                    ))));
+                }
+                match reply[3] {
+                    ADDRESS_TYPE_IPV4 => Box::new(future::ok((stream, 4))),
+                    ADDRESS_TYPE_IPV6 => Box::new(future::ok((stream, 16))),
+                    ADDRESS_TYPE_DOMAIN => Box::new(
+                        read_exact(stream, [0u8; 1]).map(|(stream, len)| (stream, len[0] as usize)),
+                    ),
+                    _ => Box::new(future::err(proxy_error("invalid bound address type"))),
+                }
+            })
+            // skip bound address
+            .and_then(|(stream, bound_len)| read_exact(stream, vec![0u8; bound_len + 2]))
+            .map(|(stream, _)| stream);
+        Box::new(handshake)
+    }
+
+    /// Start a one-shot local relay to target, return the loopback address to dial and the relay
+    /// task, which must be spawned on the network runtime.
+    pub(crate) fn relay(&self, target: &Multiaddr) -> Result<(Multiaddr, RelayTask), Error> {
+        let proxy_target = ProxyTarget::from_multiaddr(target)
+            .ok_or_else(|| Error::Dial(format!("can't dial {} through proxy", target)))?;
+        let listener =
+            std::net::TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))?;
+        let local_addr = listener.local_addr()?;
+        let listener = TcpListener::from_std(listener, &Handle::default())?;
+        let proxy = self.clone();
+        let accept = listener
+            .incoming()
+            .filter(move |inbound| match inbound.peer_addr() {
+                Ok(peer_addr) if is_own_connection(peer_addr, local_addr) => true,
+                result => {
+                    debug!(
+                        "proxy relay {} dropped a connection from another process: {:?}",
+                        local_addr, result
+                    );
+                    false
+                }
+            })
+            .into_future()
+            .map_err(|(err, _)| err)
+            .and_then(|(inbound, _listener)| {
+                // The listener is dropped here, so only the first connection is relayed
+                inbound.ok_or_else(|| io::ErrorKind::ConnectionAborted.into())
+            });
+        let relay = Timeout::new(accept, RELAY_ACCEPT_TIMEOUT)
+            .map_err(|err| {
+                err.into_inner()
+                    .unwrap_or_else(|| io::ErrorKind::TimedOut.into())
+            })
+            .and_then(move |inbound| {
+                proxy
+                    .connect(proxy_target)
+                    .and_then(|outbound| pipe(inbound, outbound))
+            })
+            .map_err(move |err| debug!("proxy relay {} failed: {}", local_addr, err));
+        Ok((
+            vec![
+                Protocol::Ip4(Ipv4Addr::LOCALHOST),
+                Protocol::Tcp(local_addr.port()),
+            ]
+            .into_iter()
+            .collect(),
+            Box::new(relay),
+        ))
+    }
+}
+
+/// Whether the loopback connection from `peer_addr` to `local_addr` is dialed by this process:
+/// the dialing socket of the connection must be one of the file descriptors of this process.
+#[cfg(target_os = "linux")]
+pub(crate) fn is_own_connection(peer_addr: SocketAddr, local_addr: SocketAddr) -> bool {
+    let inode = match dialing_socket_inode(peer_addr, local_addr) {
+        Some(inode) => inode,
+        None => return false,
+    };
+    let socket = format!("socket:[{}]", inode);
+    std::fs::read_dir("/proc/self/fd")
+        .map(|entries| {
+            entries.filter_map(Result::ok).any(|entry| {
+                std::fs::read_link(entry.path())
+                    .map(|link| link.to_string_lossy() == socket)
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn is_own_connection(_peer_addr: SocketAddr, _local_addr: SocketAddr) -> bool {
+    true
+}
+
+// Finds the inode of the socket from `peer_addr` to `local_addr` in `/proc/net/tcp`, whose
+// lines are `sl local_address rem_address st ... inode ...`
+#[cfg(target_os = "linux")]
+fn dialing_socket_inode(peer_addr: SocketAddr, local_addr: SocketAddr) -> Option<String> {
+    // IPv4 addresses are printed as the u32 in host byte order and the port in hex
+    let encode = |addr: SocketAddr| match addr {
+        SocketAddr::V4(addr) => Some(format!(
+            "{:08X}:{:04X}",
+            u32::from_ne_bytes(addr.ip().octets()),
+            addr.port()
+        )),
+        SocketAddr::V6(_) => None,
+    };
+    let (peer_addr, local_addr) = (encode(peer_addr)?, encode(local_addr)?);
+    let table = std::fs::read_to_string("/proc/net/tcp").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() > 9 && fields[1] == peer_addr && fields[2] == local_addr {
+            Some(fields[9].to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+fn proxy_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("socks5 proxy: {}", message))
+}
+
+// Copy bytes in both directions until either side closed
+fn pipe(inbound: TcpStream, outbound: TcpStream) -> impl Future<Item = (), Error = io::Error> {
+    let (inbound_reader, inbound_writer) = inbound.split();
+    let (outbound_reader, outbound_writer) = outbound.split();
+    let upstream = copy(inbound_reader, outbound_writer)
+        .and_then(|(_, _, outbound_writer)| shutdown(outbound_writer));
+    let downstream = copy(outbound_reader, inbound_writer)
+        .and_then(|(_, _, inbound_writer)| shutdown(inbound_writer));
+    upstream.join(downstream).map(|_| ())
+}
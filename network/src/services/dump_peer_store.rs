@@ -5,7 +5,8 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::timer::Interval;
 
-const DEFAULT_DUMP_INTERVAL: Duration = Duration::from_secs(3600); // 1 hour
+// only changed addresses are written, so flush frequently
+const DEFAULT_DUMP_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct DumpPeerStoreService {
     network_state: Arc<NetworkState>,
@@ -21,12 +22,11 @@ impl DumpPeerStoreService {
     }
 
     fn dump_peer_store(&self) {
-        let path = self.network_state.config.peer_store_path().clone();
         self.network_state.with_peer_store_mut(|peer_store| {
-            if let Err(err) = peer_store.dump_to_dir(&path) {
-                warn!("Dump peer store error, path: {:?} error: {}", path, err);
+            if let Err(err) = peer_store.flush() {
+                warn!("Flush peer store error: {}", err);
             } else {
-                debug!("Dump peer store to {:?}", path);
+                debug!("Flush peer store");
             }
         });
     }
@@ -80,6 +80,8 @@ impl OutboundPeerService {
             .config
             .whitelist_peers()
             .expect("address must be correct")
+            .into_iter()
+            .chain(self.network_state.reserved_peers())
         {
             if self.network_state.query_session_id(&peer_id).is_none() {
                 self.network_state
@@ -121,7 +123,7 @@ impl Future for OutboundPeerService {
                                 self.dial_peers(true, FEELER_CONNECTION_COUNT);
                             }
                         }
-                        // keep whitelist peer on connected
+                        // keep whitelist and reserved peers on connected
                         self.try_dial_whitelist();
                         // try dial observed addrs
                         self.try_dial_observed();
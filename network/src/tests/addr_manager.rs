@@ -1,7 +1,8 @@
 use crate::{
     multiaddr::Multiaddr,
+    network_group::NetworkGroup,
     peer_store::{
-        addr_manager::AddrManager,
+        addr_manager::{AddrManager, Table, BUCKET_SIZE, TRIED_BUCKETS_PER_GROUP},
         types::{AddrInfo, MultiaddrExt},
     },
     PeerId,
@@ -10,12 +11,13 @@ use proptest::prelude::*;
 use std::net::Ipv4Addr;
 
 const MAX_FETCHED_ADDRS: usize = 1000;
-const RANDOM_REMOVE_ADDRS: usize = 500;
+const MIN_ADDRS: usize = 500;
 proptest! {
     #[test]
-    fn test_add_random_addrs(count in RANDOM_REMOVE_ADDRS..MAX_FETCHED_ADDRS) {
+    fn test_add_random_addrs(count in MIN_ADDRS..MAX_FETCHED_ADDRS) {
         fn new_addr(id: usize) -> AddrInfo{
-            let ip = Ipv4Addr::from(((225 << 24) + id) as u32);
+            // spread addrs into different network groups
+            let ip = Ipv4Addr::new(1 + (id / 256) as u8, (id % 256) as u8, 0, 1);
             let addr: Multiaddr = format!("/ip4/{}/tcp/42", ip).parse().unwrap();
             let ip_addr = addr.extract_ip_addr().unwrap();
             AddrInfo::new(
@@ -27,21 +29,68 @@ proptest! {
             )
         }
         let mut addr_manager: AddrManager = Default::default();
-        for i in 0..count {
-            addr_manager.add(new_addr(i + 1));
-        }
+        // some addrs are rejected because their bucket slots collide
+        let added = (0..count).filter(|i| addr_manager.add(new_addr(i + 1))).count();
+        assert_eq!(addr_manager.count(), added);
         // randomly remove addrs
-        let removed_addrs = addr_manager.fetch_random(RANDOM_REMOVE_ADDRS, |_| true);
-        assert_eq!(removed_addrs.len(), RANDOM_REMOVE_ADDRS);
+        let remove_count = added / 2;
+        let removed_addrs = addr_manager.fetch_random(remove_count, |_| true);
+        assert_eq!(removed_addrs.len(), remove_count);
         for addr in &removed_addrs {
             addr_manager.remove(&addr.ip_port());
         }
-        assert_eq!(addr_manager.count(), count - RANDOM_REMOVE_ADDRS);
-        // add back removed addrs
+        assert_eq!(addr_manager.count(), added - remove_count);
+        // add back removed addrs, they get their old slots back
         for addr in removed_addrs {
-            addr_manager.add(addr);
+            assert!(addr_manager.add(addr));
         }
         let addrs = addr_manager.fetch_random(count + 1, |_| true);
-        assert_eq!(addrs.len(), count);
+        assert_eq!(addrs.len(), added);
+    }
+}
+
+fn connected_addr(addr: &str, last_connected_at_ms: u64) -> AddrInfo {
+    let addr: Multiaddr = addr.parse().unwrap();
+    AddrInfo::new(
+        PeerId::random(),
+        addr.extract_ip_addr().unwrap(),
+        addr,
+        last_connected_at_ms,
+        0,
+    )
+}
+
+#[test]
+fn test_tried_table_bounded_by_group() {
+    let mut addr_manager = AddrManager::default();
+    // all addrs are in the same network group
+    for i in 0..(TRIED_BUCKETS_PER_GROUP as usize * BUCKET_SIZE * 2) {
+        let addr = format!("/ip4/225.0.{}.{}/tcp/42", i / 256, i % 256);
+        addr_manager.add(connected_addr(&addr, 100));
     }
+    let tried = addr_manager
+        .addrs_iter()
+        .filter(|addr| addr_manager.slot(&addr.ip_port()).unwrap().table == Table::Tried)
+        .count();
+    assert!(tried <= TRIED_BUCKETS_PER_GROUP as usize * BUCKET_SIZE);
+}
+
+#[test]
+fn test_slots_are_stable_with_same_key() {
+    let key = [42u8; 32];
+    let addr = connected_addr("/ip4/225.0.0.1/tcp/42", 0);
+    let source = addr.addr.network_group();
+    let slot = AddrManager::with_key(key).new_slot(&addr, &source);
+    assert_eq!(AddrManager::with_key(key).new_slot(&addr, &source), slot);
+    let mut addr_manager = AddrManager::with_key(key);
+    assert!(addr_manager.add(addr.clone()));
+    assert_eq!(addr_manager.slot(&addr.ip_port()), Some(slot));
+    // connected addr moves to tried table
+    let tried = connected_addr("/ip4/225.0.0.1/tcp/42", 100);
+    assert!(addr_manager.add(tried));
+    assert_eq!(
+        addr_manager.slot(&addr.ip_port()).map(|slot| slot.table),
+        Some(Table::Tried)
+    );
+    assert_eq!(addr_manager.count(), 1);
 }
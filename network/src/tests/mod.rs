@@ -2,3 +2,4 @@ mod addr_manager;
 mod peer_registry;
 mod peer_store;
 mod peer_store_db;
+mod proxy;
@@ -217,3 +217,32 @@ fn test_accept_inbound_peer_eviction() {
     // should evict from one of evict_targets
     assert_eq!(len_after_eviction, evict_targets.len() - 1);
 }
+
+#[test]
+fn test_add_whitelist_peer_at_runtime() {
+    let mut peer_store = PeerStore::default();
+    let peer_id = PeerId::random();
+    let addr = "/ip4/127.0.0.1/tcp/42".parse::<Multiaddr>().unwrap();
+    let session_id = 1.into();
+
+    let mut peers = PeerRegistry::new(3, 3, false, vec![]);
+    peers
+        .accept_peer(
+            peer_id.clone(),
+            addr.clone(),
+            session_id,
+            SessionType::Inbound,
+            &mut peer_store,
+        )
+        .expect("accept");
+    assert_eq!(peers.connection_status().non_whitelist_inbound, 1);
+
+    // connected peer is protected once whitelisted
+    peers.add_whitelist_peer(peer_id.clone());
+    assert!(peers.get_peer(session_id).unwrap().is_whitelist);
+    assert_eq!(peers.connection_status().non_whitelist_inbound, 0);
+
+    peers.remove_whitelist_peer(&peer_id);
+    assert!(!peers.get_peer(session_id).unwrap().is_whitelist);
+    assert_eq!(peers.connection_status().non_whitelist_inbound, 1);
+}
@@ -1,6 +1,11 @@
 use crate::{
     multiaddr::{self, Multiaddr},
-    peer_store::{types::MultiaddrExt, PeerStore, Status, ADDR_COUNT_LIMIT},
+    network_group::NetworkGroup,
+    peer_store::{
+        addr_manager::{BUCKET_SIZE, NEW_BUCKETS_PER_SOURCE_GROUP},
+        types::MultiaddrExt,
+        PeerStore, Status,
+    },
     Behaviour, PeerId, SessionType,
 };
 
@@ -200,61 +205,28 @@ fn test_trim_p2p_phase() {
 }
 
 #[test]
-fn test_eviction() {
+fn test_addrs_from_same_source_are_bounded() {
     let mut peer_store = PeerStore::default();
-    let now = faketime::unix_time_as_millis();
-    let tried_ms = now - 61_000;
-    // add addrs
-    for i in 0..(ADDR_COUNT_LIMIT - 2) {
-        let addr: Multiaddr = format!("/ip4/225.0.0.1/tcp/{}", i).parse().unwrap();
-        peer_store.add_addr(PeerId::random(), addr).unwrap();
-    }
-    // this peer will be evict from peer store
-    let evict_addr: Multiaddr = "/ip4/225.0.0.2/tcp/42".parse().unwrap();
-    peer_store
-        .add_addr(PeerId::random(), evict_addr.clone())
-        .unwrap();
-    // this peer will reserve in peer store
-    let reserved_addr: Multiaddr = "/ip4/192.163.1.1/tcp/42".parse().unwrap();
-    peer_store
-        .add_addr(PeerId::random(), reserved_addr.clone())
-        .unwrap();
-    // mark two peers as terrible peer
-    if let Some(paddr) = peer_store
-        .mut_addr_manager()
-        .get_mut(&evict_addr.extract_ip_addr().unwrap())
-    {
-        paddr.mark_tried(tried_ms);
-        paddr.mark_tried(tried_ms);
-        paddr.mark_tried(tried_ms);
-    }
-    if let Some(paddr) = peer_store
-        .mut_addr_manager()
-        .get_mut(&reserved_addr.extract_ip_addr().unwrap())
-    {
-        paddr.mark_tried(tried_ms);
-        paddr.mark_tried(tried_ms);
-        paddr.mark_tried(tried_ms);
-        assert!(paddr.is_terrible(now));
+    let limit = NEW_BUCKETS_PER_SOURCE_GROUP as usize * BUCKET_SIZE;
+    let source = "/ip4/225.0.0.1/tcp/42"
+        .parse::<Multiaddr>()
+        .unwrap()
+        .network_group();
+    // a single source tries to fill peer store with addrs in different groups
+    for i in 0..(limit * 2) {
+        let addr: Multiaddr = format!("/ip4/{}.{}.0.1/tcp/42", 1 + i / 256, i % 256)
+            .parse()
+            .unwrap();
+        peer_store
+            .add_addr_from(PeerId::random(), addr, &source)
+            .unwrap();
     }
-    // should evict evict_addr and accept new_peer
-    let new_peer_addr: Multiaddr = "/ip4/225.0.0.3/tcp/42".parse().unwrap();
-    peer_store
-        .add_addr(PeerId::random(), new_peer_addr.clone())
-        .unwrap();
-    // check addrs
-    // peer store will only evict peers from largest network group
-    // the evict_addr should be evict, other two addrs will remain in peer store
+    assert!(peer_store.addr_manager().count() <= limit);
+    // addrs from other sources are still accepted
+    let addr: Multiaddr = "/ip4/192.163.1.1/tcp/42".parse().unwrap();
+    peer_store.add_addr(PeerId::random(), addr.clone()).unwrap();
     assert!(peer_store
-        .mut_addr_manager()
-        .get(&new_peer_addr.extract_ip_addr().unwrap())
+        .addr_manager()
+        .get(&addr.extract_ip_addr().unwrap())
         .is_some());
-    assert!(peer_store
-        .mut_addr_manager()
-        .get(&reserved_addr.extract_ip_addr().unwrap())
-        .is_some());
-    assert!(peer_store
-        .mut_addr_manager()
-        .get(&evict_addr.extract_ip_addr().unwrap())
-        .is_none());
 }
@@ -1,5 +1,6 @@
 use crate::{
     multiaddr::Multiaddr,
+    network_group::NetworkGroup,
     peer_store::{
         types::{multiaddr_to_ip_network, AddrInfo, BannedAddr, MultiaddrExt},
         PeerStore,
@@ -12,7 +13,8 @@ use std::collections::HashSet;
 #[test]
 fn test_peer_store_persistent() {
     let now_ms = faketime::unix_time_as_millis();
-    let mut peer_store = PeerStore::default();
+    let dir = tempfile::tempdir().unwrap();
+    let mut peer_store = PeerStore::load_from_dir(&dir.path()).unwrap();
 
     // add addrs to addr manager
     let addr_manager = peer_store.mut_addr_manager();
@@ -68,9 +70,9 @@ fn test_peer_store_persistent() {
     ban_list.ban(ban2.clone());
     ban_list.ban(ban3.clone());
 
-    // dump and load
-    let dir = tempfile::tempdir().unwrap();
-    peer_store.dump_to_dir(&dir.path()).unwrap();
+    // flush and load
+    peer_store.flush().unwrap();
+    drop(peer_store);
     let peer_store2 = PeerStore::load_from_dir(&dir.path()).unwrap();
 
     // check addr manager
@@ -95,3 +97,55 @@ fn test_peer_store_persistent() {
         vec![ban1, ban2, ban3].into_iter().collect::<HashSet<_>>()
     );
 }
+
+#[test]
+fn test_peer_store_incremental_flush() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr1: Multiaddr = "/ip4/225.0.0.1/tcp/42".parse().unwrap();
+    let addr2: Multiaddr = "/ip4/226.0.0.1/tcp/42".parse().unwrap();
+    {
+        let mut peer_store = PeerStore::load_from_dir(&dir.path()).unwrap();
+        peer_store
+            .add_addr(PeerId::random(), addr1.clone())
+            .unwrap();
+        peer_store
+            .add_addr(PeerId::random(), addr2.clone())
+            .unwrap();
+        peer_store.flush().unwrap();
+        // only the removal is written in this flush
+        peer_store
+            .mut_addr_manager()
+            .remove(&addr1.extract_ip_addr().unwrap());
+        peer_store.flush().unwrap();
+    }
+    let peer_store = PeerStore::load_from_dir(&dir.path()).unwrap();
+    let addr_manager = peer_store.addr_manager();
+    assert_eq!(addr_manager.count(), 1);
+    assert!(addr_manager
+        .get(&addr2.extract_ip_addr().unwrap())
+        .is_some());
+}
+
+#[test]
+fn test_peer_store_keeps_source_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let addr: Multiaddr = "/ip4/225.0.0.1/tcp/42".parse().unwrap();
+    let source = "/ip4/226.0.0.1/tcp/42"
+        .parse::<Multiaddr>()
+        .unwrap()
+        .network_group();
+    let ip_port = addr.extract_ip_addr().unwrap();
+    let slot = {
+        let mut peer_store = PeerStore::load_from_dir(&dir.path()).unwrap();
+        peer_store
+            .add_addr_from(PeerId::random(), addr.clone(), &source)
+            .unwrap();
+        peer_store.flush().unwrap();
+        peer_store.addr_manager().slot(&ip_port).unwrap()
+    };
+    let peer_store = PeerStore::load_from_dir(&dir.path()).unwrap();
+    let addr_manager = peer_store.addr_manager();
+    assert_eq!(addr_manager.get(&ip_port).unwrap().source, Some(source));
+    // the address is in the same new bucket as before restart
+    assert_eq!(addr_manager.slot(&ip_port), Some(slot));
+}
@@ -0,0 +1,36 @@
+use crate::error::RPCError;
+use ckb_chain::chain::ChainController;
+use ckb_types::{packed, prelude::*, H256};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+#[rpc]
+pub trait AdminRpc {
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"invalidate_block","params": ["0xa5f5c85987a15de25661e5a214f2c1449cd803f071acc7999820f25246471f40"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "invalidate_block")]
+    fn invalidate_block(&self, hash: H256) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"reconsider_block","params": ["0xa5f5c85987a15de25661e5a214f2c1449cd803f071acc7999820f25246471f40"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "reconsider_block")]
+    fn reconsider_block(&self, hash: H256) -> Result<()>;
+}
+
+pub(crate) struct AdminRpcImpl {
+    pub chain: ChainController,
+}
+
+impl AdminRpc for AdminRpcImpl {
+    fn invalidate_block(&self, hash: H256) -> Result<()> {
+        let hash: packed::Byte32 = hash.pack();
+        self.chain
+            .invalidate_block(hash)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, err.to_string()))
+    }
+
+    fn reconsider_block(&self, hash: H256) -> Result<()> {
+        let hash: packed::Byte32 = hash.pack();
+        self.chain
+            .reconsider_block(hash)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, err.to_string()))
+    }
+}
@@ -1,15 +1,19 @@
 use crate::error::RPCError;
 use ckb_jsonrpc_types::{
-    BlockNumber, BlockReward, BlockView, CellOutputWithOutPoint, CellWithStatus, EpochNumber,
-    EpochView, HeaderView, OutPoint, TransactionWithStatus,
+    BlockNumber, BlockReward, BlockView, CellOutputWithOutPoint, CellWithStatus, ChainTip,
+    ChainTipStatus, EpochNumber, EpochView, HeaderView, OutPoint, TransactionWithStatus,
 };
 use ckb_logger::error;
 use ckb_reward_calculator::RewardCalculator;
 use ckb_shared::shared::Shared;
 use ckb_store::ChainStore;
+use ckb_sync::SyncSharedState;
 use ckb_types::{core::cell::CellProvider, packed, prelude::*, H256};
 use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
+use std::cmp;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 pub const PAGE_SIZE: u64 = 100;
 
@@ -58,10 +62,14 @@ pub trait ChainRpc {
 
     #[rpc(name = "get_cellbase_output_capacity_details")]
     fn get_cellbase_output_capacity_details(&self, _hash: H256) -> Result<Option<BlockReward>>;
+
+    #[rpc(name = "get_chain_tips")]
+    fn get_chain_tips(&self) -> Result<Vec<ChainTip>>;
 }
 
 pub(crate) struct ChainRpcImpl {
     pub shared: Shared,
+    pub sync_shared_state: Arc<SyncSharedState>,
 }
 
 impl ChainRpc for ChainRpcImpl {
@@ -244,4 +252,60 @@ impl ChainRpc for ChainRpcImpl {
                 })
         }))
     }
+
+    fn get_chain_tips(&self) -> Result<Vec<ChainTip>> {
+        let sync_snapshot = self.sync_shared_state.snapshot();
+        let snapshot = sync_snapshot.store();
+
+        // The headers received by the synchronizer go first, the stored blocks on their
+        // branches are not tips
+        let header_tips = sync_snapshot
+            .state()
+            .header_map_tips()
+            .into_iter()
+            .map(|view| (view.total_difficulty().to_owned(), view.into_inner()));
+        let stored_tips = snapshot.get_chain_tips().into_iter().filter_map(|hash| {
+            let header = snapshot.get_block_header(&hash)?;
+            let ext = snapshot.get_block_ext(&hash)?;
+            Some((ext.total_difficulty, header))
+        });
+
+        let mut on_branches = HashSet::new();
+        let mut tips = Vec::new();
+        for (total_difficulty, tip) in header_tips.chain(stored_tips) {
+            if snapshot.is_main_chain(&tip.hash()) || on_branches.contains(&tip.hash()) {
+                continue;
+            }
+            let mut branch_length = 0u64;
+            let mut status = ChainTipStatus::ValidFork;
+            let mut header = tip.clone();
+            while !snapshot.is_main_chain(&header.hash()) {
+                branch_length += 1;
+                let block_status = match snapshot.get_block_ext(&header.hash()) {
+                    None => ChainTipStatus::HeadersOnly,
+                    Some(ext) => match ext.verified {
+                        Some(true) => ChainTipStatus::ValidFork,
+                        None => ChainTipStatus::ValidHeaders,
+                        Some(false) => ChainTipStatus::Invalid,
+                    },
+                };
+                status = cmp::max(status, block_status);
+                on_branches.insert(header.hash());
+                match sync_snapshot.get_header(&header.parent_hash()) {
+                    Some(parent) => header = parent,
+                    None => break,
+                }
+            }
+            tips.push(ChainTip {
+                number: tip.number().into(),
+                hash: tip.hash().unpack(),
+                total_difficulty: Some(total_difficulty),
+                branch_length: branch_length.into(),
+                status,
+            });
+        }
+        // the highest tips first
+        tips.sort_by_key(|tip| cmp::Reverse(tip.number.value()));
+        Ok(tips)
+    }
 }
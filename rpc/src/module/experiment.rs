@@ -2,21 +2,27 @@ use crate::error::RPCError;
 use ckb_dao::DaoCalculator;
 use ckb_fee_estimator::MAX_CONFIRM_BLOCKS;
 use ckb_jsonrpc_types::{
-    Capacity, DryRunResult, EstimateResult, OutPoint, Script, Transaction, Uint64,
+    Capacity, DryRunResult, EstimateResult, OutPoint, ProfileResult, Script, Transaction, Uint64,
 };
 use ckb_logger::error;
-use ckb_shared::{shared::Shared, Snapshot};
+use ckb_shared::{shared::Shared, Snapshot, MAX_CELL_SET_AT_DEPTH};
 use ckb_store::ChainStore;
 use ckb_types::{
-    core::cell::{resolve_transaction, CellProvider, CellStatus, HeaderChecker},
+    core::{
+        cell::{resolve_transaction, CellMetaBuilder, CellProvider, CellStatus, HeaderChecker},
+        error::OutPointError,
+        HeaderView, TransactionMeta,
+    },
     packed,
     prelude::*,
     H256,
 };
-use ckb_verification::ScriptVerifier;
+use ckb_verification::{ScriptVerifier, TransactionVerifier};
+use im::hashmap::HashMap as HamtMap;
 use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 #[rpc]
 pub trait ExperimentRpc {
@@ -29,6 +35,16 @@ pub trait ExperimentRpc {
     #[rpc(name = "dry_run_transaction")]
     fn dry_run_transaction(&self, _tx: Transaction) -> Result<DryRunResult>;
 
+    // Run the scripts of given transaction like `dry_run_transaction`, and attribute
+    // the cycles to script functions and syscalls, in folded stacks format
+    #[rpc(name = "profile_transaction")]
+    fn profile_transaction(&self, _tx: Transaction) -> Result<ProfileResult>;
+
+    // Verify given transaction against the chain state as of a main chain block, as if
+    // it is committed in the next block, and return the execution cycles
+    #[rpc(name = "verify_transaction_at")]
+    fn verify_transaction_at(&self, _tx: Transaction, _block_hash: H256) -> Result<DryRunResult>;
+
     // Calculate the maximum withdraw one can get, given a referenced DAO cell,
     // and a withdraw block hash
     #[rpc(name = "calculate_dao_maximum_withdraw")]
@@ -60,6 +76,26 @@ impl ExperimentRpc for ExperimentRpcImpl {
         DryRunner::new(&self.shared).run(tx)
     }
 
+    fn profile_transaction(&self, tx: Transaction) -> Result<ProfileResult> {
+        let tx: packed::Transaction = tx.into();
+        DryRunner::new(&self.shared).profile(tx)
+    }
+
+    fn verify_transaction_at(&self, tx: Transaction, block_hash: H256) -> Result<DryRunResult> {
+        let tx: packed::Transaction = tx.into();
+        let snapshot = Arc::clone(&self.shared.snapshot());
+        let hash = block_hash.pack();
+        match snapshot.get_block_header(&hash) {
+            Some(header) if snapshot.is_main_chain(&hash) => {
+                HistoricalRunner::new(&self.shared, snapshot, header)?.verify(tx)
+            }
+            _ => Err(RPCError::custom(
+                RPCError::Invalid,
+                format!("block {:#x} is not in the main chain", block_hash),
+            )),
+        }
+    }
+
     fn calculate_dao_maximum_withdraw(&self, out_point: OutPoint, hash: H256) -> Result<Capacity> {
         let snapshot: &Snapshot = &self.shared.snapshot();
         let consensus = snapshot.consensus();
@@ -149,7 +185,11 @@ impl<'a> DryRunner<'a> {
             Ok(resolved) => {
                 let consensus = snapshot.consensus();
                 let max_cycles = consensus.max_block_cycles;
-                match ScriptVerifier::new(&resolved, snapshot).verify(max_cycles) {
+                let epoch = snapshot.tip_header().epoch().number();
+                let mut verifier = ScriptVerifier::new(&resolved, snapshot);
+                verifier.set_native_crypto(consensus.native_crypto_syscalls_enabled(epoch));
+                verifier.set_exec(consensus.exec_syscall_enabled(epoch));
+                match verifier.verify(max_cycles) {
                     Ok(cycles) => Ok(DryRunResult {
                         cycles: cycles.into(),
                     }),
@@ -159,4 +199,143 @@ impl<'a> DryRunner<'a> {
             Err(err) => Err(RPCError::custom(RPCError::Invalid, format!("{:?}", err))),
         }
     }
+
+    pub(crate) fn profile(&self, tx: packed::Transaction) -> Result<ProfileResult> {
+        let snapshot: &Snapshot = &self.shared.snapshot();
+        let resolved = resolve_transaction(tx.into_view(), &mut HashSet::new(), self, self)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, format!("{:?}", err)))?;
+        let consensus = snapshot.consensus();
+        let max_cycles = consensus.max_block_cycles;
+        let epoch = snapshot.tip_header().epoch().number();
+        let mut verifier = ScriptVerifier::new(&resolved, snapshot);
+        verifier.set_native_crypto(consensus.native_crypto_syscalls_enabled(epoch));
+        verifier.set_exec(consensus.exec_syscall_enabled(epoch));
+        let profiles = verifier
+            .profile(max_cycles)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, format!("{:?}", err)))?;
+        Ok(ProfileResult {
+            cycles: profiles
+                .iter()
+                .map(|profile| profile.total_cycles())
+                .sum::<u64>()
+                .into(),
+            folded_stacks: profiles
+                .iter()
+                .map(|profile| profile.folded_stacks())
+                .collect(),
+        })
+    }
+}
+
+// HistoricalRunner verifies given transaction against the chain state as of a main chain
+// block, as if the transaction is committed in the next block.
+//
+// The cell set as of the block is rebuilt from the undo data of the blocks after it, so cells
+// consumed at or before the block are rejected as dead.
+pub(crate) struct HistoricalRunner<'a> {
+    shared: &'a Shared,
+    snapshot: Arc<Snapshot>,
+    header: HeaderView,
+    cell_set: HamtMap<packed::Byte32, TransactionMeta>,
+}
+
+impl<'a> CellProvider for HistoricalRunner<'a> {
+    fn cell(&self, out_point: &packed::OutPoint, _with_data: bool) -> CellStatus {
+        let tx_hash = out_point.tx_hash();
+        let index: u32 = out_point.index().unpack();
+        match self
+            .cell_set
+            .get(&tx_hash)
+            .and_then(|meta| meta.is_dead(index as usize))
+        {
+            Some(false) => {}
+            Some(true) => return CellStatus::Dead,
+            None => return CellStatus::Unknown,
+        }
+        let tx_info = match self.snapshot.get_transaction_info(&tx_hash) {
+            Some(tx_info) => tx_info,
+            None => return CellStatus::Unknown,
+        };
+        self.snapshot
+            .get_transaction(&tx_hash)
+            .and_then(|(tx, _)| tx.output_with_data(index as usize))
+            .map(|(output, data)| {
+                CellStatus::live_cell(
+                    CellMetaBuilder::from_cell_output(output, data)
+                        .out_point(out_point.clone())
+                        .transaction_info(tx_info)
+                        .build(),
+                )
+            })
+            .unwrap_or(CellStatus::Unknown)
+    }
+}
+
+impl<'a> HeaderChecker for HistoricalRunner<'a> {
+    fn check_valid(
+        &self,
+        block_hash: &packed::Byte32,
+    ) -> std::result::Result<(), ckb_error::Error> {
+        match self.snapshot.get_block_header(block_hash) {
+            Some(header) if header.number() <= self.header.number() => {
+                let threshold = self.snapshot.consensus().cellbase_maturity().to_rational()
+                    + header.epoch().to_rational();
+                let current = self.header.epoch().to_rational();
+                if current < threshold {
+                    Err(OutPointError::ImmatureHeader(block_hash.clone()).into())
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(OutPointError::InvalidHeader(block_hash.clone()).into()),
+        }
+    }
+}
+
+impl<'a> HistoricalRunner<'a> {
+    pub(crate) fn new(
+        shared: &'a Shared,
+        snapshot: Arc<Snapshot>,
+        header: HeaderView,
+    ) -> Result<Self> {
+        let cell_set = snapshot.cell_set_at(&header.hash()).ok_or_else(|| {
+            RPCError::custom(
+                RPCError::Invalid,
+                format!(
+                    "the cell set as of block {:#x} is unavailable, it must be one of the recent \
+                     {} blocks whose undo data is kept",
+                    header.hash(),
+                    MAX_CELL_SET_AT_DEPTH
+                ),
+            )
+        })?;
+        Ok(Self {
+            shared,
+            snapshot,
+            header,
+            cell_set,
+        })
+    }
+
+    pub(crate) fn verify(&self, tx: packed::Transaction) -> Result<DryRunResult> {
+        let snapshot: &Snapshot = &self.snapshot;
+        let resolved = resolve_transaction(tx.into_view(), &mut HashSet::new(), self, self)
+            .map_err(|err| RPCError::custom(RPCError::Invalid, format!("{:?}", err)))?;
+        let consensus = snapshot.consensus();
+        let cache_entry = TransactionVerifier::new(
+            &resolved,
+            snapshot,
+            self.header.number() + 1,
+            self.header.epoch(),
+            self.header.hash(),
+            consensus,
+            snapshot,
+            self.shared.script_verify_cache(),
+        )
+        .verify(consensus.max_block_cycles())
+        .map_err(|err| RPCError::custom(RPCError::Invalid, format!("{:?}", err)))?;
+        Ok(DryRunResult {
+            cycles: cache_entry.cycles.into(),
+        })
+    }
 }
@@ -1,3 +1,4 @@
+mod admin;
 mod alert;
 mod chain;
 mod experiment;
@@ -8,6 +9,7 @@ mod pool;
 mod stats;
 mod test;
 
+pub(crate) use self::admin::{AdminRpc, AdminRpcImpl};
 pub(crate) use self::alert::{AlertRpc, AlertRpcImpl};
 pub(crate) use self::chain::{ChainRpc, ChainRpcImpl};
 pub(crate) use self::experiment::{ExperimentRpc, ExperimentRpcImpl};
@@ -1,6 +1,6 @@
 use crate::error::RPCError;
 use ckb_jsonrpc_types::{BannedAddr, Node, NodeAddress, Timestamp};
-use ckb_network::{MultiaddrExt, NetworkController};
+use ckb_network::{multiaddr::Multiaddr, MultiaddrExt, NetworkController, PeerId};
 use faketime::unix_time_as_millis;
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
@@ -33,6 +33,32 @@ pub trait NetworkRpc {
         absolute: Option<bool>,
         reason: Option<String>,
     ) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"clear_banned_addresses","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "clear_banned_addresses")]
+    fn clear_banned_addresses(&self) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"disconnect_peer","params": ["QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "disconnect_peer")]
+    fn disconnect_peer(&self, peer_id: String) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"add_reserved_peer","params": ["QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS", "/ip4/192.168.2.100/tcp/8115"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "add_reserved_peer")]
+    fn add_reserved_peer(&self, peer_id: String, address: String) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"remove_reserved_peer","params": ["QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "remove_reserved_peer")]
+    fn remove_reserved_peer(&self, peer_id: String) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"set_network_active","params": [false]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "set_network_active")]
+    fn set_network_active(&self, state: bool) -> Result<()>;
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId> {
+    peer_id
+        .parse()
+        .map_err(|_| RPCError::custom(RPCError::Invalid, "invalid peer_id".to_owned()))
 }
 
 pub(crate) struct NetworkRpcImpl {
@@ -161,4 +187,50 @@ impl NetworkRpc for NetworkRpcImpl {
         }
         Ok(())
     }
+
+    fn clear_banned_addresses(&self) -> Result<()> {
+        self.network_controller.clear_banned_addrs();
+        Ok(())
+    }
+
+    fn disconnect_peer(&self, peer_id: String) -> Result<()> {
+        let peer_id = parse_peer_id(&peer_id)?;
+        self.network_controller
+            .disconnect_peer(&peer_id)
+            .map_err(|err| {
+                RPCError::custom(RPCError::Invalid, format!("disconnect peer error {}", err))
+            })
+    }
+
+    fn add_reserved_peer(&self, peer_id: String, address: String) -> Result<()> {
+        let peer_id = parse_peer_id(&peer_id)?;
+        let address = address
+            .parse::<Multiaddr>()
+            .map_err(|_| RPCError::custom(RPCError::Invalid, "invalid address".to_owned()))?;
+        self.network_controller
+            .add_reserved_peer(peer_id, address)
+            .map_err(|err| {
+                RPCError::custom(
+                    RPCError::Invalid,
+                    format!("add reserved peer error {}", err),
+                )
+            })
+    }
+
+    fn remove_reserved_peer(&self, peer_id: String) -> Result<()> {
+        let peer_id = parse_peer_id(&peer_id)?;
+        self.network_controller
+            .remove_reserved_peer(&peer_id)
+            .map_err(|err| {
+                RPCError::custom(
+                    RPCError::Invalid,
+                    format!("remove reserved peer error {}", err),
+                )
+            })
+    }
+
+    fn set_network_active(&self, state: bool) -> Result<()> {
+        self.network_controller.set_network_active(state);
+        Ok(())
+    }
 }
@@ -1,7 +1,8 @@
 use crate::error::RPCError;
-use ckb_jsonrpc_types::{Transaction, TxPoolInfo};
+use ckb_jsonrpc_types::{ScriptGroupCycles, ScriptGroupType, Transaction, TxCycles, TxPoolInfo};
 use ckb_logger::error;
 use ckb_network::PeerIndex;
+use ckb_script::ScriptGroupType as CoreScriptGroupType;
 use ckb_shared::shared::Shared;
 use ckb_sync::SyncSharedState;
 use ckb_tx_pool::{error::SubmitTxError, FeeRate};
@@ -19,6 +20,10 @@ pub trait PoolRpc {
     // curl -d '{"params": [], "method": "tx_pool_info", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
     #[rpc(name = "tx_pool_info")]
     fn tx_pool_info(&self) -> Result<TxPoolInfo>;
+
+    // curl -d '{"params": ["0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3"], "method": "get_transaction_cycles", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "get_transaction_cycles")]
+    fn get_transaction_cycles(&self, _hash: H256) -> Result<Option<TxCycles>>;
 }
 
 pub(crate) struct PoolRpcImpl {
@@ -103,4 +108,33 @@ impl PoolRpc for PoolRpcImpl {
             last_txs_updated_at: tx_pool_info.last_txs_updated_at.into(),
         })
     }
+
+    fn get_transaction_cycles(&self, hash: H256) -> Result<Option<TxCycles>> {
+        let hash = hash.pack();
+        let id = packed::ProposalShortId::from_tx_hash(&hash);
+
+        let tx_pool = self.shared.tx_pool_controller();
+        let fetch_tx_cycles = tx_pool.fetch_tx_cycles(id);
+        if let Err(e) = fetch_tx_cycles {
+            error!("send fetch_tx_cycles request error {}", e);
+            return Err(Error::internal_error());
+        };
+
+        Ok(fetch_tx_cycles.unwrap().map(|cache_entry| TxCycles {
+            cycles: cache_entry.cycles.into(),
+            fee: cache_entry.fee.into(),
+            script_groups: cache_entry
+                .group_cycles
+                .into_iter()
+                .map(|group| ScriptGroupCycles {
+                    script_hash: group.script_hash.unpack(),
+                    group_type: match group.group_type {
+                        CoreScriptGroupType::Lock => ScriptGroupType::Lock,
+                        CoreScriptGroupType::Type => ScriptGroupType::Type,
+                    },
+                    cycles: group.cycles.into(),
+                })
+                .collect(),
+        }))
+    }
 }
@@ -1,8 +1,9 @@
-use ckb_jsonrpc_types::{AlertMessage, ChainInfo, PeerState};
+use ckb_jsonrpc_types::{AlertMessage, ChainInfo, PeerState, ReorgRecord};
 use ckb_network_alert::notifier::Notifier as AlertNotifier;
 use ckb_shared::shared::Shared;
 use ckb_sync::Synchronizer;
 use ckb_traits::BlockMedianTimeContext;
+use ckb_types::prelude::*;
 use ckb_util::Mutex;
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
@@ -15,6 +16,9 @@ pub trait StatsRpc {
 
     #[rpc(name = "get_peers_state")]
     fn get_peers_state(&self) -> Result<Vec<PeerState>>;
+
+    #[rpc(name = "get_reorg_history")]
+    fn get_reorg_history(&self) -> Result<Vec<ReorgRecord>>;
 }
 
 pub(crate) struct StatsRpcImpl {
@@ -71,4 +75,19 @@ impl StatsRpc for StatsRpcImpl {
             .map(|(peer, blocks)| PeerState::new(peer.value(), 0, blocks.len()))
             .collect())
     }
+
+    fn get_reorg_history(&self) -> Result<Vec<ReorgRecord>> {
+        Ok(self
+            .shared
+            .reorg_history()
+            .into_iter()
+            .map(|record| ReorgRecord {
+                timestamp: record.timestamp.into(),
+                depth: record.depth.into(),
+                detached_blocks: record.detached_blocks.iter().map(Unpack::unpack).collect(),
+                attached_blocks: record.attached_blocks.iter().map(Unpack::unpack).collect(),
+                refused: record.refused,
+            })
+            .collect())
+    }
 }
@@ -1,8 +1,9 @@
 use crate::config::Config;
 use crate::module::{
-    AlertRpc, AlertRpcImpl, ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc,
-    IndexerRpcImpl, IntegrationTestRpc, IntegrationTestRpcImpl, MinerRpc, MinerRpcImpl, NetworkRpc,
-    NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
+    AdminRpc, AdminRpcImpl, AlertRpc, AlertRpcImpl, ChainRpc, ChainRpcImpl, ExperimentRpc,
+    ExperimentRpcImpl, IndexerRpc, IndexerRpcImpl, IntegrationTestRpc, IntegrationTestRpcImpl,
+    MinerRpc, MinerRpcImpl, NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc,
+    StatsRpcImpl,
 };
 use ckb_chain::chain::ChainController;
 use ckb_indexer::{DefaultIndexerStore, IndexerConfig};
@@ -28,10 +29,15 @@ impl<'a> ServiceBuilder<'a> {
             io_handler: IoHandler::new(),
         }
     }
-    pub fn enable_chain(mut self, shared: Shared) -> Self {
+    pub fn enable_chain(mut self, shared: Shared, sync_shared_state: Arc<SyncSharedState>) -> Self {
         if self.config.chain_enable() {
-            self.io_handler
-                .extend_with(ChainRpcImpl { shared }.to_delegate());
+            self.io_handler.extend_with(
+                ChainRpcImpl {
+                    shared,
+                    sync_shared_state,
+                }
+                .to_delegate(),
+            );
         }
         self
     }
@@ -138,6 +144,14 @@ impl<'a> ServiceBuilder<'a> {
         self
     }
 
+    pub fn enable_admin(mut self, chain: ChainController) -> Self {
+        if self.config.admin_enable() {
+            self.io_handler
+                .extend_with(AdminRpcImpl { chain }.to_delegate());
+        }
+        self
+    }
+
     pub fn enable_indexer(mut self, indexer_config: &IndexerConfig, shared: Shared) -> Self {
         if self.config.indexer_enable() {
             let store = DefaultIndexerStore::new(indexer_config, shared);
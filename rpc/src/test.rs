@@ -1,6 +1,7 @@
 use crate::module::{
-    ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc, IndexerRpcImpl, MinerRpc,
-    MinerRpcImpl, NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
+    AdminRpc, AdminRpcImpl, ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc,
+    IndexerRpcImpl, MinerRpc, MinerRpcImpl, NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl,
+    StatsRpc, StatsRpcImpl,
 };
 use crate::RpcServer;
 use ckb_chain::chain::{ChainController, ChainService};
@@ -34,6 +35,7 @@ use ckb_types::{
     H256,
 };
 use ckb_util::{Condvar, Mutex};
+use ckb_verification::Switch;
 use jsonrpc_core::IoHandler;
 use jsonrpc_http_server::ServerBuilder;
 use jsonrpc_server_utils::cors::AccessControlAllowOrigin;
@@ -252,6 +254,7 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
     io.extend_with(
         ChainRpcImpl {
             shared: shared.clone(),
+            sync_shared_state: Arc::clone(&sync_shared_state),
         }
         .to_delegate(),
     );
@@ -292,6 +295,12 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
         }
         .to_delegate(),
     );
+    io.extend_with(
+        AdminRpcImpl {
+            chain: chain_controller.clone(),
+        }
+        .to_delegate(),
+    );
     let server = ServerBuilder::new(io)
         .cors(DomainsValidation::AllowOnly(vec![
             AccessControlAllowOrigin::Null,
@@ -398,9 +407,12 @@ fn params_of(shared: &Shared, method: &str) -> Value {
         | "local_node_info"
         | "get_peers"
         | "get_banned_addresses"
+        | "clear_banned_addresses"
         | "get_blockchain_info"
         | "tx_pool_info"
         | "get_peers_state"
+        | "get_reorg_history"
+        | "get_chain_tips"
         | "get_lock_hash_index_states" => vec![],
         "get_epoch_by_number" => vec![json!("0x0")],
         "get_block_hash" | "get_block_by_number" | "get_header_by_number" => {
@@ -420,10 +432,23 @@ fn params_of(shared: &Shared, method: &str) -> Value {
             json!(true),
             json!("set_ban example"),
         ],
-        "send_transaction" | "dry_run_transaction" | "_compute_transaction_hash" => {
+        "disconnect_peer" | "remove_reserved_peer" => {
+            vec![json!("QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS")]
+        }
+        "add_reserved_peer" => vec![
+            json!("QmUsZHPbjjzU627UZFt4k8j6ycEcNvXRnVGxCPKqwbAfQS"),
+            json!("/ip4/192.168.2.100/tcp/8115"),
+        ],
+        "set_network_active" => vec![json!(false)],
+        "send_transaction"
+        | "dry_run_transaction"
+        | "profile_transaction"
+        | "_compute_transaction_hash" => {
             vec![transaction]
         }
-        "get_transaction" => vec![transaction_hash],
+        "verify_transaction_at" => vec![transaction, tip_hash],
+        "invalidate_block" | "reconsider_block" => vec![tip_hash],
+        "get_transaction" | "get_transaction_cycles" => vec![transaction_hash],
         "index_lock_hash" => vec![json!(always_success_script_hash), json!("0x400")],
         "deindex_lock_hash" => vec![json!(always_success_script_hash)],
         "_compute_code_hash" => vec![json!("0x123456")],
@@ -533,7 +558,12 @@ fn test_rpc() {
                 .to_string();
             let params = case.get("params").expect("get params").clone();
             let result = case.get("result").expect("get result").clone();
-            if case.get("skip").unwrap_or(&json!(false)).as_bool().unwrap() {
+            // `skip` is either `true` or the reason why the case can not run
+            let skip = case
+                .get("skip")
+                .map(|skip| skip.is_string() || skip.as_bool().unwrap())
+                .unwrap_or(false);
+            if skip {
                 expected.push((method.clone(), result.clone()));
             } else {
                 expected.push((method.clone(), result_of(&client, &uri, &method, params)));
@@ -548,3 +578,42 @@ fn test_rpc() {
 
     server.close();
 }
+
+#[test]
+fn test_verify_transaction_at_spent_cell() {
+    let (shared, table) = SharedBuilder::default()
+        .consensus(always_success_consensus())
+        .build()
+        .unwrap();
+    let chain_controller = ChainService::new(shared.clone(), table).start::<&str>(None);
+    let mut parent = always_success_consensus().genesis_block;
+    for _ in 0..20 {
+        let block = next_block(&shared, &parent.header());
+        chain_controller
+            .process_block(Arc::new(block.clone()))
+            .expect("processing new block should be ok");
+        parent = block;
+    }
+
+    // Commit a transaction which consumes the tip cellbase
+    let transaction = construct_transaction();
+    let spending = next_block(&shared, &parent.header())
+        .as_advanced_builder()
+        .transaction(transaction.clone())
+        .build();
+    chain_controller
+        .internal_process_block(Arc::new(spending.clone()), Switch::DISABLE_ALL)
+        .expect("processing new block should be ok");
+
+    let rpc = ExperimentRpcImpl {
+        shared: shared.clone(),
+    };
+    let json_transaction: ckb_jsonrpc_types::Transaction = transaction.data().into();
+    assert!(rpc
+        .verify_transaction_at(json_transaction.clone(), parent.hash().unpack())
+        .is_ok());
+    let err = rpc
+        .verify_transaction_at(json_transaction, spending.hash().unpack())
+        .unwrap_err();
+    assert!(err.message.contains("Dead"), "{}", err.message);
+}
@@ -0,0 +1,28 @@
+use ckb_types::{core::Cycle, packed::Byte32};
+use ckb_util::Mutex;
+use lru_cache::LruCache;
+
+/// Cycles of successfully verified script groups.
+///
+/// The key is a hash of everything a script group can observe through syscalls, so a
+/// cached result stays valid across transaction re-resolution, e.g. from tx-pool
+/// admission to block verification.
+pub struct ScriptVerifyCache {
+    inner: Mutex<LruCache<Byte32, Cycle>>,
+}
+
+impl ScriptVerifyCache {
+    pub fn new(capacity: usize) -> Self {
+        ScriptVerifyCache {
+            inner: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    pub fn get(&self, key: &Byte32) -> Option<Cycle> {
+        self.inner.lock().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: Byte32, cycles: Cycle) {
+        self.inner.lock().insert(key, cycles);
+    }
+}
@@ -11,6 +11,17 @@ pub fn transferred_byte_cycles(bytes: u64) -> u64 {
     (bytes + BYTES_PER_CYCLE - 1) / BYTES_PER_CYCLE
 }
 
+// Native crypto syscalls, the costs are fixed so they are deterministic across machines
+pub const BLAKE2B_BASE_CYCLES: u64 = 1_000;
+// Cycles per 128 bytes block compressed by blake2b
+pub const BLAKE2B_BLOCK_CYCLES: u64 = 100;
+pub const SECP256K1_RECOVER_CYCLES: u64 = 100_000;
+
+pub fn blake2b_cycles(bytes: u64) -> u64 {
+    let blocks = bytes / 128 + if bytes % 128 == 0 { 0 } else { 1 };
+    BLAKE2B_BASE_CYCLES.saturating_add(blocks.saturating_mul(BLAKE2B_BLOCK_CYCLES))
+}
+
 pub fn instruction_cycles(i: Instruction) -> u64 {
     match extract_opcode(i) {
         insts::OP_JALR => 3,
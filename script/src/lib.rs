@@ -1,11 +1,20 @@
+mod cache;
 pub mod cost_model;
 mod error;
+mod profiler;
+mod snapshot;
 mod syscalls;
 mod type_id;
 mod verify;
 
+pub use crate::cache::ScriptVerifyCache;
 pub use crate::error::ScriptError;
-pub use crate::verify::{ScriptGroup, ScriptGroupType, TransactionScriptsVerifier};
+pub use crate::profiler::ScriptProfile;
+pub use crate::snapshot::{MachineSnapshot, TransactionSnapshot, VerifyResult};
+pub use crate::syscalls::ExecRequest;
+pub use crate::verify::{
+    ScriptGroup, ScriptGroupCycles, ScriptGroupType, ScriptVersion, TransactionScriptsVerifier,
+};
 
 /// re-export DataLoader
 pub use ckb_script_data_loader::DataLoader;
@@ -0,0 +1,118 @@
+use ckb_types::{bytes::Bytes, core::Cycle};
+use ckb_vm::{registers::A7, Error as VMError, Register, SupportMachine, Syscalls};
+use goblin::elf::{sym::STT_FUNC, Elf};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::rc::Rc;
+
+// Cycles of code without symbols are grouped by aligned ranges of this size
+const PC_RANGE_SIZE: u64 = 256;
+
+/// Cycles consumed by a script group, attributed to functions and syscalls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScriptProfile {
+    // Root frame of the folded stacks, usually the script hash
+    pub name: String,
+    // Function name (or pc range when symbols are stripped) => cycles
+    pub functions: HashMap<String, Cycle>,
+    // (function name, syscall number) => cycles, includes the transferred bytes cost
+    pub syscalls: HashMap<(String, u64), Cycle>,
+}
+
+impl ScriptProfile {
+    pub fn new(name: String) -> Self {
+        ScriptProfile {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn total_cycles(&self) -> Cycle {
+        self.functions.values().sum::<Cycle>() + self.syscalls.values().sum::<Cycle>()
+    }
+
+    pub(crate) fn add_function_cycles(&mut self, function: String, cycles: Cycle) {
+        *self.functions.entry(function).or_default() += cycles;
+    }
+
+    pub(crate) fn add_syscall_cycles(&mut self, function: String, number: u64, cycles: Cycle) {
+        *self.syscalls.entry((function, number)).or_default() += cycles;
+    }
+
+    /// Folded stacks which can be rendered by flamegraph.pl or inferno, one frame per line:
+    /// `<name>;<function> <cycles>` and `<name>;<function>;syscall_<number> <cycles>`
+    pub fn folded_stacks(&self) -> String {
+        // sorted for stable output
+        let mut lines = BTreeMap::new();
+        for (function, cycles) in &self.functions {
+            lines.insert(format!("{};{}", self.name, function), *cycles);
+        }
+        for ((function, number), cycles) in &self.syscalls {
+            lines.insert(
+                format!("{};{};syscall_{}", self.name, function, number),
+                *cycles,
+            );
+        }
+        let mut folded = String::new();
+        for (stack, cycles) in lines {
+            let _ = writeln!(folded, "{} {}", stack, cycles);
+        }
+        folded
+    }
+}
+
+/// Function symbols of an ELF program, used to resolve program counters.
+pub(crate) struct Symbols {
+    // start address => (end address, name)
+    functions: BTreeMap<u64, (u64, String)>,
+}
+
+impl Symbols {
+    pub(crate) fn parse(program: &Bytes) -> Symbols {
+        let mut functions = BTreeMap::new();
+        if let Ok(elf) = Elf::parse(program) {
+            for sym in elf.syms.iter() {
+                if sym.st_type() != STT_FUNC || sym.st_size == 0 {
+                    continue;
+                }
+                if let Some(Ok(name)) = elf.strtab.get(sym.st_name) {
+                    functions.insert(sym.st_value, (sym.st_value + sym.st_size, name.to_string()));
+                }
+            }
+        }
+        Symbols { functions }
+    }
+
+    pub(crate) fn resolve(&self, pc: u64) -> String {
+        match self.functions.range(..=pc).next_back() {
+            Some((_, (end, name))) if pc < *end => name.to_owned(),
+            _ => {
+                let start = pc - pc % PC_RANGE_SIZE;
+                format!("0x{:x}-0x{:x}", start, start + PC_RANGE_SIZE)
+            }
+        }
+    }
+}
+
+/// Records the number of the syscall being invoked, it never handles the syscall.
+pub(crate) struct SyscallRecorder {
+    number: Rc<Cell<Option<u64>>>,
+}
+
+impl SyscallRecorder {
+    pub(crate) fn new(number: Rc<Cell<Option<u64>>>) -> Self {
+        SyscallRecorder { number }
+    }
+}
+
+impl<Mac: SupportMachine> Syscalls<Mac> for SyscallRecorder {
+    fn initialize(&mut self, _machine: &mut Mac) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        self.number.set(Some(machine.registers()[A7].to_u64()));
+        Ok(false)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::syscalls::ExecRequest;
+use crate::verify::ScriptGroupCycles;
+use ckb_types::{bytes::Bytes, core::Cycle};
+use ckb_vm::{
+    memory::{FLAG_FREEZED, FLAG_WRITABLE},
+    CoreMachine, Error as VMError, Memory, SupportMachine, RISCV_PAGES, RISCV_PAGESIZE,
+};
+
+/// VM state of a suspended script group.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MachineSnapshot {
+    pub pc: u64,
+    pub registers: Vec<u64>,
+    pub cycles: Cycle,
+    // Pages which have non-zero content or flags, as (page, flags, content). The code loaded by
+    // `load_cell_data_as_code` is only known from the snapshot, so executable pages are kept too
+    pub pages: Vec<(u64, u8, Vec<u8>)>,
+    // Program loaded by the exec syscall, None if the script binary is still running
+    pub exec: Option<ExecRequest>,
+}
+
+impl MachineSnapshot {
+    pub(crate) fn capture<Mac: SupportMachine<REG = u64>>(
+        machine: &mut Mac,
+    ) -> Result<MachineSnapshot, VMError> {
+        let mut pages = Vec::new();
+        for page in 0..RISCV_PAGES as u64 {
+            let flags = machine.memory_mut().fetch_flag(page)?;
+            let start = page * RISCV_PAGESIZE as u64;
+            let mut content = Vec::with_capacity(RISCV_PAGESIZE);
+            for offset in (0..RISCV_PAGESIZE as u64).step_by(8) {
+                let value = machine.memory_mut().load64(&(start + offset))?;
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            if flags != 0 || content.iter().any(|byte| *byte != 0) {
+                pages.push((page, flags, content));
+            }
+        }
+        Ok(MachineSnapshot {
+            pc: *machine.pc(),
+            registers: machine.registers().to_vec(),
+            cycles: machine.cycles(),
+            pages,
+            exec: None,
+        })
+    }
+
+    /// Restore a machine which has loaded the same program.
+    pub(crate) fn restore<Mac: SupportMachine<REG = u64>>(
+        &self,
+        machine: &mut Mac,
+    ) -> Result<(), VMError> {
+        let mut pages = self.pages.iter().peekable();
+        for page in 0..RISCV_PAGES as u64 {
+            let snapshot_page = match pages.peek() {
+                Some((index, flags, content)) if *index == page => {
+                    pages.next();
+                    Some((*flags, content))
+                }
+                _ => None,
+            };
+            // Frozen pages are loaded with the program, they are the same as when captured
+            if machine.memory_mut().fetch_flag(page)? & FLAG_FREEZED != 0 {
+                continue;
+            }
+            let start = page * RISCV_PAGESIZE as u64;
+            match snapshot_page {
+                Some((flags, content)) => machine.memory_mut().init_pages(
+                    start,
+                    RISCV_PAGESIZE as u64,
+                    flags,
+                    Some(Bytes::from(content.clone())),
+                    0,
+                )?,
+                None => machine.memory_mut().init_pages(
+                    start,
+                    RISCV_PAGESIZE as u64,
+                    FLAG_WRITABLE,
+                    None,
+                    0,
+                )?,
+            }
+        }
+        for (index, value) in self.registers.iter().enumerate() {
+            machine.set_register(index, *value);
+        }
+        machine.set_pc(self.pc);
+        machine.set_cycles(self.cycles);
+        Ok(())
+    }
+}
+
+/// Verification state of a suspended transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionSnapshot {
+    // Index of the current script group
+    pub current: usize,
+    // Cycles consumed by the completed script groups
+    pub current_cycles: Cycle,
+    // Cycles of each completed script group
+    pub group_cycles: Vec<ScriptGroupCycles>,
+    // VM state of the current script group, None if it has not started yet
+    pub machine: Option<MachineSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyResult {
+    // Total cycles and the cycles of each script group
+    Completed(Cycle, Vec<ScriptGroupCycles>),
+    Suspended(TransactionSnapshot),
+}
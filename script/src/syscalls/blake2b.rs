@@ -0,0 +1,46 @@
+use crate::{
+    cost_model::blake2b_cycles,
+    syscalls::{utils::load_bytes, BLAKE2B_SYSCALL_NUMBER, SUCCESS},
+};
+use ckb_hash::blake2b_256;
+use ckb_vm::{
+    registers::{A0, A1, A2, A7},
+    Error as VMError, Memory, Register, SupportMachine, Syscalls,
+};
+
+/// Hashes a memory range natively with the ckb blake2b personalization.
+///
+/// a0: address of the 32 bytes output, a1: address of the input, a2: length of the input
+#[derive(Debug, Default)]
+pub struct Blake2b {}
+
+impl Blake2b {
+    pub fn new() -> Blake2b {
+        Blake2b {}
+    }
+}
+
+impl<Mac: SupportMachine> Syscalls<Mac> for Blake2b {
+    fn initialize(&mut self, _machine: &mut Mac) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        if machine.registers()[A7].to_u64() != BLAKE2B_SYSCALL_NUMBER {
+            return Ok(false);
+        }
+
+        let output_addr = machine.registers()[A0].to_u64();
+        let input_addr = machine.registers()[A1].to_u64();
+        let input_len = machine.registers()[A2].to_u64();
+        // Charge before hashing, so a huge length fails on the cycles limit first
+        machine.add_cycles(blake2b_cycles(input_len))?;
+
+        let input = load_bytes(machine, input_addr, input_len)?;
+        machine
+            .memory_mut()
+            .store_bytes(output_addr, &blake2b_256(&input))?;
+        machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
+        Ok(true)
+    }
+}
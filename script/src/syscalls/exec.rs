@@ -0,0 +1,165 @@
+use crate::{
+    cost_model::transferred_byte_cycles,
+    syscalls::{Source, SourceEntry, EXEC_SYSCALL_NUMBER, INDEX_OUT_OF_BOUND, ITEM_MISSING},
+    DataLoader,
+};
+use ckb_types::{bytes::Bytes, core::cell::CellMeta};
+use ckb_vm::{
+    registers::{A0, A1, A2, A3, A7},
+    Error as VMError, Memory, Register, SupportMachine, Syscalls,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Program and argv requested by the exec syscall, the verifier replaces the
+/// current program with it after the machine stops.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecRequest {
+    pub program: Bytes,
+    pub argv: Vec<Bytes>,
+}
+
+pub struct Exec<'a, DL> {
+    data_loader: &'a DL,
+    outputs: &'a [CellMeta],
+    resolved_inputs: &'a [CellMeta],
+    resolved_cell_deps: &'a [CellMeta],
+    group_inputs: &'a [usize],
+    group_outputs: &'a [usize],
+    request: Rc<RefCell<Option<ExecRequest>>>,
+}
+
+impl<'a, DL: DataLoader + 'a> Exec<'a, DL> {
+    pub fn new(
+        data_loader: &'a DL,
+        outputs: &'a [CellMeta],
+        resolved_inputs: &'a [CellMeta],
+        resolved_cell_deps: &'a [CellMeta],
+        group_inputs: &'a [usize],
+        group_outputs: &'a [usize],
+        request: Rc<RefCell<Option<ExecRequest>>>,
+    ) -> Exec<'a, DL> {
+        Exec {
+            data_loader,
+            outputs,
+            resolved_inputs,
+            resolved_cell_deps,
+            group_inputs,
+            group_outputs,
+            request,
+        }
+    }
+
+    fn fetch_cell(&self, source: Source, index: usize) -> Result<&'a CellMeta, u8> {
+        match source {
+            Source::Transaction(SourceEntry::Input) => {
+                self.resolved_inputs.get(index).ok_or(INDEX_OUT_OF_BOUND)
+            }
+            Source::Transaction(SourceEntry::Output) => {
+                self.outputs.get(index).ok_or(INDEX_OUT_OF_BOUND)
+            }
+            Source::Transaction(SourceEntry::CellDep) => {
+                self.resolved_cell_deps.get(index).ok_or(INDEX_OUT_OF_BOUND)
+            }
+            Source::Transaction(SourceEntry::HeaderDep) => Err(INDEX_OUT_OF_BOUND),
+            Source::Group(SourceEntry::Input) => self
+                .group_inputs
+                .get(index)
+                .ok_or(INDEX_OUT_OF_BOUND)
+                .and_then(|actual_index| {
+                    self.resolved_inputs
+                        .get(*actual_index)
+                        .ok_or(INDEX_OUT_OF_BOUND)
+                }),
+            Source::Group(SourceEntry::Output) => self
+                .group_outputs
+                .get(index)
+                .ok_or(INDEX_OUT_OF_BOUND)
+                .and_then(|actual_index| self.outputs.get(*actual_index).ok_or(INDEX_OUT_OF_BOUND)),
+            Source::Group(SourceEntry::CellDep) => Err(INDEX_OUT_OF_BOUND),
+            Source::Group(SourceEntry::HeaderDep) => Err(INDEX_OUT_OF_BOUND),
+        }
+    }
+}
+
+/// Maximum number of arguments passed to a program by exec
+pub const EXEC_MAX_ARGC: u64 = 256;
+/// Maximum length of an argument passed by exec, not including the terminating null byte
+pub const EXEC_MAX_ARG_LENGTH: usize = 64 * 1024;
+
+// The string is charged once it is read, its length is capped so a missing null byte can't
+// make the syscall scan the whole memory for free.
+fn load_c_string<Mac: SupportMachine>(machine: &mut Mac, mut addr: u64) -> Result<Bytes, VMError> {
+    let mut buffer = Vec::new();
+    loop {
+        let byte = machine
+            .memory_mut()
+            .load8(&Mac::REG::from_u64(addr))?
+            .to_u8();
+        if byte == 0 {
+            break;
+        }
+        if buffer.len() >= EXEC_MAX_ARG_LENGTH {
+            return Err(VMError::OutOfBound);
+        }
+        buffer.push(byte);
+        addr = addr.checked_add(1).ok_or(VMError::OutOfBound)?;
+    }
+    machine.add_cycles(transferred_byte_cycles(buffer.len() as u64 + 1))?;
+    Ok(Bytes::from(buffer))
+}
+
+impl<'a, Mac: SupportMachine, DL: DataLoader> Syscalls<Mac> for Exec<'a, DL> {
+    fn initialize(&mut self, _machine: &mut Mac) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        if machine.registers()[A7].to_u64() != EXEC_SYSCALL_NUMBER {
+            return Ok(false);
+        }
+
+        let index = machine.registers()[A0].to_u64();
+        let source = Source::parse_from_u64(machine.registers()[A1].to_u64())?;
+        let argc = machine.registers()[A2].to_u64();
+        let argv_addr = machine.registers()[A3].to_u64();
+
+        let cell = match self.fetch_cell(source, index as usize) {
+            Ok(cell) => cell,
+            Err(err) => {
+                machine.set_register(A0, Mac::REG::from_u8(err));
+                return Ok(true);
+            }
+        };
+        let program = match self.data_loader.load_cell_data(cell) {
+            Some((data, _)) => data,
+            None => {
+                machine.set_register(A0, Mac::REG::from_u8(ITEM_MISSING));
+                return Ok(true);
+            }
+        };
+
+        if argc > EXEC_MAX_ARGC {
+            return Err(VMError::OutOfBound);
+        }
+        let mut argv = Vec::new();
+        for i in 0..argc {
+            let pointer_addr = i
+                .checked_mul(8)
+                .and_then(|offset| argv_addr.checked_add(offset))
+                .ok_or(VMError::OutOfBound)?;
+            let pointer = machine
+                .memory_mut()
+                .load64(&Mac::REG::from_u64(pointer_addr))?
+                .to_u64();
+            machine.add_cycles(transferred_byte_cycles(8))?;
+            argv.push(load_c_string(machine, pointer)?);
+        }
+
+        // Stop the current program, the verifier loads the new one on a fresh
+        // machine and carries over the consumed cycles.
+        *self.request.borrow_mut() = Some(ExecRequest { program, argv });
+        machine.set_running(false);
+        Ok(true)
+    }
+}
@@ -1,4 +1,6 @@
+mod blake2b;
 mod debugger;
+mod exec;
 mod load_cell;
 mod load_cell_data;
 mod load_header;
@@ -7,9 +9,13 @@ mod load_script;
 mod load_script_hash;
 mod load_tx;
 mod load_witness;
+mod read_recorder;
+mod secp256k1;
 mod utils;
 
+pub use self::blake2b::Blake2b;
 pub use self::debugger::Debugger;
+pub use self::exec::{Exec, ExecRequest, EXEC_MAX_ARGC, EXEC_MAX_ARG_LENGTH};
 pub use self::load_cell::LoadCell;
 pub use self::load_cell_data::LoadCellData;
 pub use self::load_header::LoadHeader;
@@ -18,6 +24,8 @@ pub use self::load_script::LoadScript;
 pub use self::load_script_hash::LoadScriptHash;
 pub use self::load_tx::LoadTx;
 pub use self::load_witness::LoadWitness;
+pub(crate) use self::read_recorder::OutsideGroupReadRecorder;
+pub use self::secp256k1::Secp256k1Recover;
 
 use ckb_vm::Error;
 
@@ -29,7 +37,11 @@ pub const SUCCESS: u8 = 0;
 pub const INDEX_OUT_OF_BOUND: u8 = 1;
 pub const ITEM_MISSING: u8 = 2;
 pub const SLICE_OUT_OF_BOUND: u8 = 3;
+// INVALID_SIGNATURE is returned when secp256k1 recovery fails on the given message
+// and signature.
+pub const INVALID_SIGNATURE: u8 = 4;
 
+pub const EXEC_SYSCALL_NUMBER: u64 = 2043;
 pub const LOAD_TRANSACTION_SYSCALL_NUMBER: u64 = 2051;
 pub const LOAD_SCRIPT_SYSCALL_NUMBER: u64 = 2052;
 pub const LOAD_TX_HASH_SYSCALL_NUMBER: u64 = 2061;
@@ -43,6 +55,8 @@ pub const LOAD_HEADER_BY_FIELD_SYSCALL_NUMBER: u64 = 2082;
 pub const LOAD_INPUT_BY_FIELD_SYSCALL_NUMBER: u64 = 2083;
 pub const LOAD_CELL_DATA_AS_CODE_SYSCALL_NUMBER: u64 = 2091;
 pub const LOAD_CELL_DATA_SYSCALL_NUMBER: u64 = 2092;
+pub const BLAKE2B_SYSCALL_NUMBER: u64 = 2101;
+pub const SECP256K1_RECOVER_SYSCALL_NUMBER: u64 = 2102;
 pub const DEBUG_PRINT_SYSCALL_NUMBER: u64 = 2177;
 
 #[derive(Debug, PartialEq, Clone, Copy, Eq)]
@@ -175,8 +189,10 @@ impl Source {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cost_model::{blake2b_cycles, SECP256K1_RECOVER_CYCLES};
     use crate::DataLoader;
     use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+    use ckb_crypto::secp::{Generator, Message};
     use ckb_db::RocksDB;
     use ckb_hash::blake2b_256;
     use ckb_store::{data_loader_wrapper::DataLoaderWrapper, ChainDB, COLUMNS};
@@ -195,10 +211,13 @@ mod tests {
     use ckb_vm::{
         memory::{FLAG_EXECUTABLE, FLAG_FREEZED, FLAG_WRITABLE},
         registers::{A0, A1, A2, A3, A4, A5, A7},
-        CoreMachine, Error as VMError, Memory, SparseMemory, Syscalls, WXorXMemory, RISCV_PAGESIZE,
+        CoreMachine, Error as VMError, Memory, SparseMemory, SupportMachine, Syscalls, WXorXMemory,
+        RISCV_PAGESIZE,
     };
     use proptest::{collection::size_range, prelude::*};
+    use std::cell::{Cell, RefCell};
     use std::collections::HashMap;
+    use std::rc::Rc;
 
     fn new_store() -> ChainDB {
         ChainDB::new(RocksDB::open_tmp(COLUMNS), Default::default())
@@ -1247,6 +1266,207 @@ mod tests {
         assert_eq!(result.unwrap_err(), VMError::OutOfBound);
     }
 
+    #[test]
+    fn test_exec() {
+        let data = vec![0, 1, 2, 3, 4, 5];
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory<u64>>::default();
+        let argv_addr = 4096;
+        let arg_addr = 8192;
+
+        machine.set_register(A0, 0); //index
+        machine.set_register(A1, u64::from(Source::Transaction(SourceEntry::CellDep))); //source
+        machine.set_register(A2, 1); // argc
+        machine.set_register(A3, argv_addr); // argv
+        machine.set_register(A7, EXEC_SYSCALL_NUMBER); // syscall number
+
+        assert!(machine.memory_mut().store64(&argv_addr, &arg_addr).is_ok());
+        assert!(machine.memory_mut().store_bytes(arg_addr, b"foo\0").is_ok());
+
+        let dep_cell_data = Bytes::from(data.clone());
+        let dep_cell = build_cell_meta(10000, dep_cell_data);
+
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let outputs = vec![];
+        let resolved_inputs = vec![];
+        let resolved_cell_deps = vec![dep_cell];
+        let group_inputs = vec![];
+        let group_outputs = vec![];
+        let request = Rc::new(RefCell::new(None));
+        let mut exec = Exec::new(
+            &data_loader,
+            &outputs,
+            &resolved_inputs,
+            &resolved_cell_deps,
+            &group_inputs,
+            &group_outputs,
+            Rc::clone(&request),
+        );
+
+        machine.set_running(true);
+        assert!(exec.ecall(&mut machine).is_ok());
+        assert!(!machine.running());
+        assert_eq!(
+            request.borrow_mut().take(),
+            Some(ExecRequest {
+                program: Bytes::from(data),
+                argv: vec![Bytes::from(&b"foo"[..])],
+            })
+        );
+
+        // Missing cell dep
+        machine.set_running(true);
+        machine.set_register(A0, 1); //index
+        assert!(exec.ecall(&mut machine).is_ok());
+        assert!(machine.running());
+        assert_eq!(machine.registers()[A0], u64::from(INDEX_OUT_OF_BOUND));
+        assert_eq!(*request.borrow(), None);
+    }
+
+    #[test]
+    fn test_exec_limits() {
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory<u64>>::new_with_max_cycles(100);
+        let argv_addr = 4096;
+        let arg_addr = 8192;
+
+        machine.set_register(A0, 0); //index
+        machine.set_register(A1, u64::from(Source::Transaction(SourceEntry::CellDep))); //source
+        machine.set_register(A2, EXEC_MAX_ARGC + 1); // argc
+        machine.set_register(A3, argv_addr); // argv
+        machine.set_register(A7, EXEC_SYSCALL_NUMBER); // syscall number
+
+        let dep_cell = build_cell_meta(10000, Bytes::from(vec![0, 1, 2, 3]));
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let outputs = vec![];
+        let resolved_inputs = vec![];
+        let resolved_cell_deps = vec![dep_cell];
+        let group_inputs = vec![];
+        let group_outputs = vec![];
+        let request = Rc::new(RefCell::new(None));
+        let mut exec = Exec::new(
+            &data_loader,
+            &outputs,
+            &resolved_inputs,
+            &resolved_cell_deps,
+            &group_inputs,
+            &group_outputs,
+            Rc::clone(&request),
+        );
+
+        // Too many arguments
+        assert_eq!(exec.ecall(&mut machine), Err(VMError::OutOfBound));
+
+        // Argument without the terminating null byte within the length limit
+        let arg = Bytes::from(vec![b'a'; EXEC_MAX_ARG_LENGTH + 1]);
+        machine.set_register(A2, 1); // argc
+        assert!(machine.memory_mut().store64(&argv_addr, &arg_addr).is_ok());
+        assert!(machine.memory_mut().store_bytes(arg_addr, &arg).is_ok());
+        assert_eq!(exec.ecall(&mut machine), Err(VMError::OutOfBound));
+
+        // The arguments are charged while they are read
+        assert!(machine
+            .memory_mut()
+            .store_byte(arg_addr + 1024, 1, 0)
+            .is_ok());
+        assert_eq!(exec.ecall(&mut machine), Err(VMError::InvalidCycles));
+        assert_eq!(*request.borrow(), None);
+    }
+
+    #[test]
+    fn test_outside_group_read_recorder() {
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory<u64>>::default();
+        let read = Rc::new(Cell::new(false));
+        let mut recorder = OutsideGroupReadRecorder::new(Rc::clone(&read));
+
+        machine.set_register(A7, LOAD_WITNESS_SYSCALL_NUMBER); // syscall number
+        machine.set_register(A4, u64::from(Source::Group(SourceEntry::Input))); //source
+        assert_eq!(recorder.ecall(&mut machine), Ok(false));
+        assert!(!read.get());
+
+        machine.set_register(A7, LOAD_TX_HASH_SYSCALL_NUMBER); // syscall number
+        assert_eq!(recorder.ecall(&mut machine), Ok(false));
+        assert!(!read.get());
+
+        machine.set_register(A7, LOAD_WITNESS_SYSCALL_NUMBER); // syscall number
+        machine.set_register(A4, u64::from(Source::Transaction(SourceEntry::Input))); //source
+        assert_eq!(recorder.ecall(&mut machine), Ok(false));
+        assert!(read.get());
+
+        read.set(false);
+        machine.set_register(A7, LOAD_TRANSACTION_SYSCALL_NUMBER); // syscall number
+        assert_eq!(recorder.ecall(&mut machine), Ok(false));
+        assert!(read.get());
+    }
+
+    #[test]
+    fn test_blake2b() {
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory<u64>>::default();
+        let output_addr = 4096;
+        let input_addr = 8192;
+        let input = b"hello, ckb";
+
+        machine.set_register(A0, output_addr); // output
+        machine.set_register(A1, input_addr); // input
+        machine.set_register(A2, input.len() as u64); // input length
+        machine.set_register(A7, BLAKE2B_SYSCALL_NUMBER); // syscall number
+
+        assert!(machine.memory_mut().store_bytes(input_addr, input).is_ok());
+
+        let mut blake2b = Blake2b::new();
+        assert!(blake2b.ecall(&mut machine).is_ok());
+        assert_eq!(machine.registers()[A0], u64::from(SUCCESS));
+        assert_eq!(machine.cycles(), blake2b_cycles(input.len() as u64));
+        assert_eq!(
+            utils::load_bytes(&mut machine, output_addr, 32).unwrap(),
+            blake2b_256(&input[..]).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_recover() {
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory<u64>>::default();
+        let output_addr = 4096;
+        let message_addr = 8192;
+        let signature_addr = 12288;
+
+        let (privkey, pubkey) = Generator::random_keypair();
+        let message = Message::from(blake2b_256(b"hello, ckb"));
+        let signature = privkey.sign_recoverable(&message).unwrap();
+
+        machine.set_register(A0, output_addr); // output
+        machine.set_register(A1, message_addr); // message
+        machine.set_register(A2, signature_addr); // signature
+        machine.set_register(A7, SECP256K1_RECOVER_SYSCALL_NUMBER); // syscall number
+
+        assert!(machine
+            .memory_mut()
+            .store_bytes(message_addr, message.as_bytes())
+            .is_ok());
+        assert!(machine
+            .memory_mut()
+            .store_bytes(signature_addr, &signature.serialize())
+            .is_ok());
+
+        let mut secp256k1_recover = Secp256k1Recover::new();
+        assert!(secp256k1_recover.ecall(&mut machine).is_ok());
+        assert_eq!(machine.registers()[A0], u64::from(SUCCESS));
+        assert_eq!(machine.cycles(), SECP256K1_RECOVER_CYCLES);
+        assert_eq!(
+            utils::load_bytes(&mut machine, output_addr, 33).unwrap(),
+            pubkey.serialize()
+        );
+
+        // Invalid recovery id
+        assert!(machine
+            .memory_mut()
+            .store_bytes(signature_addr + 64, &[4])
+            .is_ok());
+        machine.set_register(A0, output_addr);
+        assert!(secp256k1_recover.ecall(&mut machine).is_ok());
+        assert_eq!(machine.registers()[A0], u64::from(INVALID_SIGNATURE));
+    }
+
     fn _test_load_cell_data_on_freezed_memory(
         as_code: bool,
         data: &[u8],
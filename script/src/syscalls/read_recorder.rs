@@ -0,0 +1,43 @@
+use crate::syscalls::{Source, LOAD_TRANSACTION_SYSCALL_NUMBER, LOAD_WITNESS_SYSCALL_NUMBER};
+use ckb_vm::{
+    registers::{A4, A7},
+    Error as VMError, Register, SupportMachine, Syscalls,
+};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Records whether a script reads the whole transaction or the witnesses by their
+/// transaction index, which may be outside of its group. It must be the first syscall
+/// to see every ecall, it never handles one.
+pub(crate) struct OutsideGroupReadRecorder {
+    read: Rc<Cell<bool>>,
+}
+
+impl OutsideGroupReadRecorder {
+    pub(crate) fn new(read: Rc<Cell<bool>>) -> Self {
+        OutsideGroupReadRecorder { read }
+    }
+}
+
+impl<Mac: SupportMachine> Syscalls<Mac> for OutsideGroupReadRecorder {
+    fn initialize(&mut self, _machine: &mut Mac) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        let outside_group = match machine.registers()[A7].to_u64() {
+            LOAD_TRANSACTION_SYSCALL_NUMBER => true,
+            LOAD_WITNESS_SYSCALL_NUMBER => {
+                match Source::parse_from_u64(machine.registers()[A4].to_u64()) {
+                    Ok(Source::Group(_)) => false,
+                    _ => true,
+                }
+            }
+            _ => false,
+        };
+        if outside_group {
+            self.read.set(true);
+        }
+        Ok(false)
+    }
+}
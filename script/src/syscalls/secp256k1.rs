@@ -0,0 +1,61 @@
+use crate::{
+    cost_model::SECP256K1_RECOVER_CYCLES,
+    syscalls::{utils::load_bytes, INVALID_SIGNATURE, SECP256K1_RECOVER_SYSCALL_NUMBER, SUCCESS},
+};
+use ckb_crypto::secp::{Message, Signature};
+use ckb_vm::{
+    registers::{A0, A1, A2, A7},
+    Error as VMError, Memory, Register, SupportMachine, Syscalls,
+};
+
+const MESSAGE_SIZE: u64 = 32;
+const SIGNATURE_SIZE: u64 = 65;
+
+/// Recovers the compressed public key from a recoverable signature natively.
+///
+/// a0: address of the 33 bytes output, a1: address of the 32 bytes message,
+/// a2: address of the 65 bytes signature. Returns `INVALID_SIGNATURE` if the
+/// public key can't be recovered.
+#[derive(Debug, Default)]
+pub struct Secp256k1Recover {}
+
+impl Secp256k1Recover {
+    pub fn new() -> Secp256k1Recover {
+        Secp256k1Recover {}
+    }
+}
+
+impl<Mac: SupportMachine> Syscalls<Mac> for Secp256k1Recover {
+    fn initialize(&mut self, _machine: &mut Mac) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
+        if machine.registers()[A7].to_u64() != SECP256K1_RECOVER_SYSCALL_NUMBER {
+            return Ok(false);
+        }
+
+        let output_addr = machine.registers()[A0].to_u64();
+        let message_addr = machine.registers()[A1].to_u64();
+        let signature_addr = machine.registers()[A2].to_u64();
+        machine.add_cycles(SECP256K1_RECOVER_CYCLES)?;
+
+        let message = load_bytes(machine, message_addr, MESSAGE_SIZE)?;
+        let signature = load_bytes(machine, signature_addr, SIGNATURE_SIZE)?;
+        let pubkey = Message::from_slice(&message).ok().and_then(|message| {
+            Signature::from_slice(&signature)
+                .and_then(|signature| signature.recover(&message))
+                .ok()
+        });
+        match pubkey {
+            Some(pubkey) => {
+                machine
+                    .memory_mut()
+                    .store_bytes(output_addr, &pubkey.serialize())?;
+                machine.set_register(A0, Mac::REG::from_u8(SUCCESS));
+            }
+            None => machine.set_register(A0, Mac::REG::from_u8(INVALID_SIGNATURE)),
+        }
+        Ok(true)
+    }
+}
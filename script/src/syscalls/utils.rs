@@ -23,6 +23,23 @@ pub fn store_data<Mac: SupportMachine>(machine: &mut Mac, data: &[u8]) -> Result
     Ok(real_size)
 }
 
+pub fn load_bytes<Mac: SupportMachine>(
+    machine: &mut Mac,
+    addr: u64,
+    size: u64,
+) -> Result<Vec<u8>, VMError> {
+    let end = addr.checked_add(size).ok_or(VMError::OutOfBound)?;
+    let mut buffer = Vec::new();
+    for addr in addr..end {
+        let byte = machine
+            .memory_mut()
+            .load8(&Mac::REG::from_u64(addr))?
+            .to_u8();
+        buffer.push(byte);
+    }
+    Ok(buffer)
+}
+
 pub fn store_u64<Mac: SupportMachine>(machine: &mut Mac, v: u64) -> Result<u64, VMError> {
     let mut buffer = [0u8; std::mem::size_of::<u64>()];
     LittleEndian::write_u64(&mut buffer, v);
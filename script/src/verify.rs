@@ -1,14 +1,19 @@
 use crate::{
+    cache::ScriptVerifyCache,
     cost_model::{instruction_cycles, transferred_byte_cycles},
+    profiler::{ScriptProfile, Symbols, SyscallRecorder},
+    snapshot::{MachineSnapshot, TransactionSnapshot, VerifyResult},
     syscalls::{
-        Debugger, LoadCell, LoadCellData, LoadHeader, LoadInput, LoadScript, LoadScriptHash,
-        LoadTx, LoadWitness,
+        Blake2b, Debugger, Exec, ExecRequest, LoadCell, LoadCellData, LoadHeader, LoadInput,
+        LoadScript, LoadScriptHash, LoadTx, LoadWitness, OutsideGroupReadRecorder,
+        Secp256k1Recover,
     },
     type_id::TypeIdSystemScript,
     DataLoader, ScriptError,
 };
 use ckb_chain_spec::consensus::TYPE_ID_CODE_HASH;
 use ckb_error::{Error, InternalErrorKind};
+use ckb_hash::new_blake2b;
 #[cfg(feature = "logging")]
 use ckb_logger::{debug, info};
 use ckb_types::{
@@ -19,25 +24,50 @@ use ckb_types::{
     },
     packed::{Byte32, Byte32Vec, BytesVec, CellInputVec, CellOutput, OutPoint, Script},
     prelude::*,
+    H256,
 };
 #[cfg(has_asm)]
-use ckb_vm::{
-    machine::asm::{AsmCoreMachine, AsmMachine},
-    DefaultMachineBuilder, InstructionCycleFunc, SupportMachine, Syscalls,
-};
+use ckb_vm::machine::asm::{AsmCoreMachine, AsmMachine};
 #[cfg(not(has_asm))]
+use ckb_vm::TraceMachine;
 use ckb_vm::{
-    DefaultCoreMachine, DefaultMachineBuilder, InstructionCycleFunc, SparseMemory, SupportMachine,
-    Syscalls, TraceMachine, WXorXMemory,
+    decoder::build_imac_decoder, CoreMachine, DefaultCoreMachine, DefaultMachineBuilder,
+    InstructionCycleFunc, SparseMemory, SupportMachine, Syscalls, WXorXMemory,
 };
+use lazy_static::lazy_static;
+use rayon::prelude::*;
 use serde_derive::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// The script groups of a transaction are verified on their own pool, so a large transaction
+// can not occupy the global rayon pool
+const MAX_VERIFY_THREADS: usize = 8;
+
+lazy_static! {
+    static ref VERIFY_POOL: rayon::ThreadPool = rayon::ThreadPoolBuilder::new()
+        .num_threads(rayon::current_num_threads().min(MAX_VERIFY_THREADS))
+        .thread_name(|index| format!("ScriptVerify{}", index))
+        .build()
+        .expect("build script verification pool");
+}
 
 #[cfg(has_asm)]
 type CoreMachineType = Box<AsmCoreMachine>;
 #[cfg(not(has_asm))]
 type CoreMachineType = DefaultCoreMachine<u64, WXorXMemory<u64, SparseMemory<u64>>>;
+// Suspendable execution always uses the interpreter, its state can be captured between
+// any two instructions.
+type ResumableCoreMachineType = DefaultCoreMachine<u64, WXorXMemory<u64, SparseMemory<u64>>>;
+
+enum ScriptGroupResult {
+    Completed(Cycle),
+    Suspended(MachineSnapshot),
+}
 
 // A script group is defined as scripts that share the same hash.
 // A script group will only be executed once per transaction, the
@@ -45,14 +75,16 @@ type CoreMachineType = DefaultCoreMachine<u64, WXorXMemory<u64, SparseMemory<u64
 // if needed.
 pub struct ScriptGroup {
     pub script: Script,
+    pub group_type: ScriptGroupType,
     pub input_indices: Vec<usize>,
     pub output_indices: Vec<usize>,
 }
 
 impl ScriptGroup {
-    pub fn new(script: &Script) -> Self {
+    pub fn new(script: &Script, group_type: ScriptGroupType) -> Self {
         Self {
             script: script.to_owned(),
+            group_type,
             input_indices: vec![],
             output_indices: vec![],
         }
@@ -66,12 +98,56 @@ pub enum ScriptGroupType {
     Type,
 }
 
+/// Cycles consumed by a single script group of a transaction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScriptGroupCycles {
+    pub script_hash: Byte32,
+    pub group_type: ScriptGroupType,
+    pub cycles: Cycle,
+}
+
+impl ScriptGroupCycles {
+    pub fn new(group: &ScriptGroup, cycles: Cycle) -> Self {
+        ScriptGroupCycles {
+            script_hash: group.script.calc_script_hash(),
+            group_type: group.group_type.clone(),
+            cycles,
+        }
+    }
+}
+
+// The VM version decides the machine, cost model and syscalls a script runs with.
+// A new version is introduced whenever a change would break existing scripts, old
+// scripts keep running on the version selected by their hash type.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ScriptVersion {
+    V0 = 0,
+    // Adds the exec syscall
+    V1 = 1,
+}
+
+impl From<ScriptHashType> for ScriptVersion {
+    fn from(hash_type: ScriptHashType) -> ScriptVersion {
+        match hash_type {
+            ScriptHashType::Data | ScriptHashType::Type => ScriptVersion::V0,
+            ScriptHashType::Data1 => ScriptVersion::V1,
+        }
+    }
+}
+
 // This struct leverages CKB VM to verify transaction inputs.
 // FlatBufferBuilder owned Vec<u8> that grows as needed, in the
 // future, we might refactor this to share buffer to achive zero-copy
 pub struct TransactionScriptsVerifier<'a, DL> {
     data_loader: &'a DL,
-    debug_printer: Box<dyn Fn(&Byte32, &str)>,
+    debug_printer: Box<dyn Fn(&Byte32, &str) + Sync>,
+    // argv passed to the script binaries
+    argv: Vec<Bytes>,
+    script_cache: Option<Arc<ScriptVerifyCache>>,
+    // Whether the native blake2b and secp256k1 syscalls are activated
+    native_crypto: bool,
+    // Whether the exec syscall is activated for the scripts running on VM version 1
+    exec: bool,
 
     outputs: Vec<CellMeta>,
     rtx: &'a ResolvedTransaction,
@@ -131,12 +207,12 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             let output = &cell_meta.cell_output;
             let lock_group_entry = lock_groups
                 .entry(output.calc_lock_hash())
-                .or_insert_with(|| ScriptGroup::new(&output.lock()));
+                .or_insert_with(|| ScriptGroup::new(&output.lock(), ScriptGroupType::Lock));
             lock_group_entry.input_indices.push(i);
             if let Some(t) = &output.type_().to_opt() {
                 let type_group_entry = type_groups
                     .entry(t.calc_script_hash())
-                    .or_insert_with(|| ScriptGroup::new(&t));
+                    .or_insert_with(|| ScriptGroup::new(&t, ScriptGroupType::Type));
                 type_group_entry.input_indices.push(i);
             }
         }
@@ -144,7 +220,7 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             if let Some(t) = &output.type_().to_opt() {
                 let type_group_entry = type_groups
                     .entry(t.calc_script_hash())
-                    .or_insert_with(|| ScriptGroup::new(&t));
+                    .or_insert_with(|| ScriptGroup::new(&t, ScriptGroupType::Type));
                 type_group_entry.output_indices.push(i);
             }
         }
@@ -157,6 +233,10 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             rtx,
             lock_groups,
             type_groups,
+            argv: Vec::new(),
+            script_cache: None,
+            native_crypto: false,
+            exec: false,
             debug_printer: Box::new(
                 #[allow(unused_variables)]
                 |hash: &Byte32, message: &str| {
@@ -167,10 +247,26 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         }
     }
 
-    pub fn set_debug_printer<F: Fn(&Byte32, &str) + 'static>(&mut self, func: F) {
+    pub fn set_debug_printer<F: Fn(&Byte32, &str) + Sync + 'static>(&mut self, func: F) {
         self.debug_printer = Box::new(func);
     }
 
+    pub fn set_argv(&mut self, argv: Vec<Bytes>) {
+        self.argv = argv;
+    }
+
+    pub fn set_script_cache(&mut self, script_cache: Arc<ScriptVerifyCache>) {
+        self.script_cache = Some(script_cache);
+    }
+
+    pub fn set_native_crypto(&mut self, enabled: bool) {
+        self.native_crypto = enabled;
+    }
+
+    pub fn set_exec(&mut self, enabled: bool) {
+        self.exec = enabled;
+    }
+
     #[inline]
     fn inputs(&self) -> CellInputVec {
         self.rtx.transaction.inputs()
@@ -265,10 +361,26 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         LoadScript::new(script)
     }
 
+    fn build_exec(
+        &'a self,
+        script_group: &'a ScriptGroup,
+        request: Rc<RefCell<Option<ExecRequest>>>,
+    ) -> Exec<'a, DL> {
+        Exec::new(
+            &self.data_loader,
+            &self.outputs,
+            self.resolved_inputs(),
+            self.resolved_cell_deps(),
+            &script_group.input_indices,
+            &script_group.output_indices,
+            request,
+        )
+    }
+
     // Extracts actual script binary either in dep cells.
     pub fn extract_script(&self, script: &'a Script) -> Result<Bytes, Error> {
         match ScriptHashType::try_from(script.hash_type()).expect("checked data") {
-            ScriptHashType::Data => {
+            ScriptHashType::Data | ScriptHashType::Data1 => {
                 if let Some(data) = self.binaries_by_data_hash.get(&script.code_hash()) {
                     Ok(data.to_owned())
                 } else {
@@ -290,12 +402,59 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         }
     }
 
-    pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, Error> {
-        let mut cycles: Cycle = 0;
+    pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, Error>
+    where
+        DL: Sync,
+    {
+        // The total is checked against `max_cycles` in `verify_groups`, it can't overflow
+        Ok(self
+            .verify_groups(max_cycles)?
+            .iter()
+            .map(|group| group.cycles)
+            .sum())
+    }
 
-        // Now run each script group
-        for group in self.lock_groups.values().chain(self.type_groups.values()) {
-            let cycle = self.verify_script_group(group, max_cycles).map_err(|e| {
+    /// Like `verify`, but returns the cycles consumed by each script group.
+    pub fn verify_groups(&self, max_cycles: Cycle) -> Result<Vec<ScriptGroupCycles>, Error>
+    where
+        DL: Sync,
+    {
+        let mut cycles: Cycle = 0;
+        let script_groups = self.script_groups();
+
+        // Script groups are independent, run them on the script verification pool. They share
+        // the `max_cycles` budget: a group starts with what the finished groups left, so the
+        // transaction never runs much more than `max_cycles` in total. A transaction within
+        // the budget passes whatever the schedule is.
+        let consumed_cycles = AtomicU64::new(0);
+        let results = VERIFY_POOL.install(|| {
+            script_groups
+                .par_iter()
+                .map(|group| {
+                    let remaining_cycles =
+                        max_cycles.saturating_sub(consumed_cycles.load(Ordering::Acquire));
+                    self.verify_script_group(group, remaining_cycles)
+                        .map(|cycles| {
+                            consumed_cycles.fetch_add(cycles, Ordering::AcqRel);
+                            cycles
+                        })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The results are accumulated in the order of `script_groups`. Which group runs out of
+        // the shared budget depends on the schedule, so such a group is verified again with
+        // what the previous groups left. Any other result does not depend on the budget, the
+        // first error is the same as a serial verification would report.
+        let mut group_cycles = Vec::with_capacity(results.len());
+        for (group, result) in script_groups.into_iter().zip(results) {
+            let result = match result {
+                Err(ref e) if is_exceeded_maximum_cycles(e) => {
+                    self.verify_script_group(group, max_cycles - cycles)
+                }
+                result => result,
+            };
+            let cycle = result.map_err(|e| {
                 #[cfg(feature = "logging")]
                 info!(
                     "Error validating script group {} of transaction {}: {}",
@@ -312,8 +471,105 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
                 return Err(ScriptError::ExceededMaximumCycles.into());
             }
             cycles = current_cycles;
+            group_cycles.push(ScriptGroupCycles::new(group, cycle));
         }
-        Ok(cycles)
+        Ok(group_cycles)
+    }
+
+    /// Like `verify`, but suspends after consuming about `step_cycles` cycles, the returned
+    /// snapshot can be passed to `resume_from_snapshot` to continue the verification.
+    pub fn resumable_verify(
+        &self,
+        max_cycles: Cycle,
+        step_cycles: Cycle,
+    ) -> Result<VerifyResult, Error> {
+        self.resume_from_snapshot(&TransactionSnapshot::default(), max_cycles, step_cycles)
+    }
+
+    pub fn resume_from_snapshot(
+        &self,
+        snapshot: &TransactionSnapshot,
+        max_cycles: Cycle,
+        step_cycles: Cycle,
+    ) -> Result<VerifyResult, Error> {
+        let mut cycles = snapshot.current_cycles;
+        let mut group_cycles = snapshot.group_cycles.clone();
+        let mut budget = step_cycles;
+
+        for (index, group) in self
+            .script_groups()
+            .into_iter()
+            .enumerate()
+            .skip(snapshot.current)
+        {
+            let machine = if index == snapshot.current {
+                snapshot.machine.as_ref()
+            } else {
+                None
+            };
+            if budget == 0 {
+                return Ok(VerifyResult::Suspended(TransactionSnapshot {
+                    current: index,
+                    current_cycles: cycles,
+                    group_cycles,
+                    machine: machine.cloned(),
+                }));
+            }
+            let resumed_cycles = machine.map(|machine| machine.cycles).unwrap_or(0);
+            let result = self
+                .resume_script_group(group, max_cycles, machine, budget)
+                .map_err(|e| {
+                    #[cfg(feature = "logging")]
+                    info!(
+                        "Error validating script group {} of transaction {}: {}",
+                        group.script.calc_script_hash(),
+                        self.hash(),
+                        e
+                    );
+                    e
+                })?;
+            match result {
+                ScriptGroupResult::Completed(cycle) => {
+                    budget = budget.saturating_sub(cycle.saturating_sub(resumed_cycles));
+                    let current_cycles = cycles
+                        .checked_add(cycle)
+                        .ok_or(ScriptError::ExceededMaximumCycles)?;
+                    if current_cycles > max_cycles {
+                        return Err(ScriptError::ExceededMaximumCycles.into());
+                    }
+                    cycles = current_cycles;
+                    group_cycles.push(ScriptGroupCycles::new(group, cycle));
+                }
+                ScriptGroupResult::Suspended(machine) => {
+                    return Ok(VerifyResult::Suspended(TransactionSnapshot {
+                        current: index,
+                        current_cycles: cycles,
+                        group_cycles,
+                        machine: Some(machine),
+                    }));
+                }
+            }
+        }
+        Ok(VerifyResult::Completed(cycles, group_cycles))
+    }
+
+    /// Run all script groups and attribute the consumed cycles to functions and syscalls.
+    /// It is much slower than `verify`, only used to help script authors to optimize.
+    pub fn profile(&self, max_cycles: Cycle) -> Result<Vec<ScriptProfile>, Error> {
+        let mut cycles: Cycle = 0;
+        let mut profiles = Vec::new();
+
+        for group in self.script_groups() {
+            let profile = self.profile_script_group(group, max_cycles)?;
+            cycles = cycles
+                .checked_add(profile.total_cycles())
+                .ok_or(ScriptError::ExceededMaximumCycles)?;
+            if cycles > max_cycles {
+                return Err(ScriptError::ExceededMaximumCycles.into());
+            }
+            profiles.push(profile);
+        }
+        Ok(profiles)
     }
 
     // Run a single script in current transaction, while this is not useful for
@@ -331,6 +587,22 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
     }
 
     fn verify_script_group(&self, group: &ScriptGroup, max_cycles: Cycle) -> Result<Cycle, Error> {
+        if let Some(cycles) = self.cached_cycles(group, max_cycles) {
+            return Ok(cycles);
+        }
+        let outside_group_read = Rc::new(Cell::new(false));
+        let cycles =
+            self.verify_script_group_without_cache(group, max_cycles, &outside_group_read)?;
+        self.cache_cycles(group, outside_group_read.get(), cycles);
+        Ok(cycles)
+    }
+
+    fn verify_script_group_without_cache(
+        &self,
+        group: &ScriptGroup,
+        max_cycles: Cycle,
+        outside_group_read: &Rc<Cell<bool>>,
+    ) -> Result<Cycle, Error> {
         if group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
             && Into::<u8>::into(group.script.hash_type()) == Into::<u8>::into(ScriptHashType::Type)
         {
@@ -341,10 +613,91 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             };
             verifier.verify()
         } else {
-            self.run(&group, max_cycles)
+            self.run(&group, max_cycles, outside_group_read)
+        }
+    }
+
+    // Everything a script group can observe through syscalls: the script, the raw
+    // transaction, the witnesses of the group, and the blocks of the resolved cells, which
+    // are loaded by `load_header`, plus the argv and the optional syscalls. The contents of
+    // the resolved cells are determined by their out points in the transaction.
+    //
+    // A script reading the whole transaction or the witnesses by their transaction index
+    // may observe the witnesses outside of its group, its result is keyed on all the
+    // witnesses instead, by setting `outside_group_read`.
+    fn cache_key(&self, group: &ScriptGroup, outside_group_read: bool) -> Byte32 {
+        let mut hasher = new_blake2b();
+        hasher.update(group.script.calc_script_hash().as_slice());
+        if outside_group_read {
+            hasher.update(&[1]);
+            hasher.update(self.rtx.transaction.witness_hash().as_slice());
+        } else {
+            hasher.update(&[0]);
+            hasher.update(self.rtx.transaction.hash().as_slice());
+            let witnesses = self.witnesses();
+            hasher.update(&(group.input_indices.len() as u64).to_le_bytes());
+            for index in group.input_indices.iter().chain(&group.output_indices) {
+                match witnesses.get(*index) {
+                    Some(witness) => {
+                        let witness = witness.raw_data();
+                        hasher.update(&[1]);
+                        hasher.update(&(witness.len() as u64).to_le_bytes());
+                        hasher.update(&witness);
+                    }
+                    None => hasher.update(&[0]),
+                }
+            }
+        }
+        for cell_meta in self
+            .resolved_inputs()
+            .iter()
+            .chain(self.resolved_cell_deps())
+        {
+            match &cell_meta.transaction_info {
+                Some(info) => hasher.update(info.block_hash.as_slice()),
+                None => hasher.update(&[0u8; 32]),
+            }
+        }
+        for arg in &self.argv {
+            hasher.update(&(arg.len() as u64).to_le_bytes());
+            hasher.update(arg);
+        }
+        hasher.update(&[self.native_crypto as u8, self.exec as u8]);
+        let mut hash = [0u8; 32];
+        hasher.finalize(&mut hash);
+        hash.pack()
+    }
+
+    // Results which exceeded `max_cycles` are verified again to report the same error
+    fn cached_cycles(&self, group: &ScriptGroup, max_cycles: Cycle) -> Option<Cycle> {
+        let script_cache = self.script_cache.as_ref()?;
+        [false, true]
+            .iter()
+            .filter_map(|outside_group_read| {
+                script_cache.get(&self.cache_key(group, *outside_group_read))
+            })
+            .find(|cycles| *cycles <= max_cycles)
+    }
+
+    fn cache_cycles(&self, group: &ScriptGroup, outside_group_read: bool, cycles: Cycle) {
+        if let Some(script_cache) = &self.script_cache {
+            script_cache.insert(self.cache_key(group, outside_group_read), cycles);
         }
     }
 
+    // Lock groups then type groups, each sorted by script hash
+    fn script_groups(&self) -> Vec<&ScriptGroup> {
+        let mut lock_groups = self.lock_groups.iter().collect::<Vec<_>>();
+        let mut type_groups = self.type_groups.iter().collect::<Vec<_>>();
+        lock_groups.sort_by(|(a, _), (b, _)| a.as_slice().cmp(b.as_slice()));
+        type_groups.sort_by(|(a, _), (b, _)| a.as_slice().cmp(b.as_slice()));
+        lock_groups
+            .into_iter()
+            .chain(type_groups)
+            .map(|(_, group)| group)
+            .collect()
+    }
+
     pub fn find_script_group(
         &self,
         script_group_type: &ScriptGroupType,
@@ -356,16 +709,28 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         }
     }
 
-    pub fn cost_model(&self) -> Box<InstructionCycleFunc> {
-        Box::new(instruction_cycles)
+    pub fn select_version(&self, script: &Script) -> ScriptVersion {
+        ScriptHashType::try_from(script.hash_type())
+            .expect("checked data")
+            .into()
     }
 
-    pub fn generate_syscalls(
+    // All versions share the same instruction set for now, a new cost model is
+    // needed once a version adds instructions.
+    pub fn cost_model(&self, version: ScriptVersion) -> Box<InstructionCycleFunc> {
+        match version {
+            ScriptVersion::V0 | ScriptVersion::V1 => Box::new(instruction_cycles),
+        }
+    }
+
+    pub fn generate_syscalls<Mac: SupportMachine>(
         &'a self,
+        version: ScriptVersion,
         script_group: &'a ScriptGroup,
-    ) -> Vec<Box<(dyn Syscalls<CoreMachineType> + 'a)>> {
+        exec_request: &Rc<RefCell<Option<ExecRequest>>>,
+    ) -> Vec<Box<(dyn Syscalls<Mac> + 'a)>> {
         let current_script_hash = script_group.script.calc_script_hash();
-        vec![
+        let mut syscalls: Vec<Box<(dyn Syscalls<Mac> + 'a)>> = vec![
             Box::new(self.build_load_script_hash(current_script_hash.clone())),
             Box::new(self.build_load_tx()),
             Box::new(
@@ -384,41 +749,233 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
                 ),
             ),
             Box::new(Debugger::new(current_script_hash, &self.debug_printer)),
-        ]
+        ];
+        if version >= ScriptVersion::V1 && self.exec {
+            syscalls.push(Box::new(
+                self.build_exec(script_group, Rc::clone(exec_request)),
+            ));
+        }
+        if self.native_crypto {
+            syscalls.push(Box::new(Blake2b::new()));
+            syscalls.push(Box::new(Secp256k1Recover::new()));
+        }
+        syscalls
     }
 
-    fn run(&self, script_group: &ScriptGroup, max_cycles: Cycle) -> Result<Cycle, Error> {
-        let program = self.extract_script(&script_group.script)?;
-        #[cfg(has_asm)]
-        let core_machine = AsmCoreMachine::new_with_max_cycles(max_cycles);
-        #[cfg(not(has_asm))]
-        let core_machine =
-            DefaultCoreMachine::<u64, WXorXMemory<u64, SparseMemory<u64>>>::new_with_max_cycles(
-                max_cycles,
-            );
-        let machine_builder = DefaultMachineBuilder::<CoreMachineType>::new(core_machine)
-            .instruction_cycle_func(self.cost_model());
-        let machine_builder = self
-            .generate_syscalls(script_group)
-            .into_iter()
-            .fold(machine_builder, |builder, syscall| builder.syscall(syscall));
-        let default_machine = machine_builder.build();
-        #[cfg(has_asm)]
-        let mut machine = AsmMachine::new(default_machine, None);
-        #[cfg(not(has_asm))]
-        let mut machine = TraceMachine::new(default_machine);
-        let bytes = machine
-            .load_program(&program, &[])
-            .map_err(internal_error)?;
-        machine
-            .machine
-            .add_cycles(transferred_byte_cycles(bytes))
-            .map_err(internal_error)?;
-        let code = machine.run().map_err(internal_error)?;
-        if code == 0 {
-            Ok(machine.machine.cycles())
-        } else {
-            Err(ScriptError::ValidationFailure(code).into())
+    fn run(
+        &self,
+        script_group: &ScriptGroup,
+        max_cycles: Cycle,
+        outside_group_read: &Rc<Cell<bool>>,
+    ) -> Result<Cycle, Error> {
+        let version = self.select_version(&script_group.script);
+        let mut program = self.extract_script(&script_group.script)?;
+        let mut argv = self.argv.clone();
+        let mut cycles = 0;
+        // Every exec replaces the program on a fresh machine, until a program exits
+        loop {
+            #[cfg(has_asm)]
+            let core_machine = AsmCoreMachine::new_with_max_cycles(max_cycles);
+            #[cfg(not(has_asm))]
+            let core_machine =
+                DefaultCoreMachine::<u64, WXorXMemory<u64, SparseMemory<u64>>>::new_with_max_cycles(
+                    max_cycles,
+                );
+            let exec_request = Rc::new(RefCell::new(None));
+            // The recorder must be the first syscall to see every ecall
+            let machine_builder = DefaultMachineBuilder::<CoreMachineType>::new(core_machine)
+                .instruction_cycle_func(self.cost_model(version))
+                .syscall(Box::new(OutsideGroupReadRecorder::new(Rc::clone(
+                    outside_group_read,
+                ))));
+            let machine_builder = self
+                .generate_syscalls(version, script_group, &exec_request)
+                .into_iter()
+                .fold(machine_builder, |builder, syscall| builder.syscall(syscall));
+            let default_machine = machine_builder.build();
+            #[cfg(has_asm)]
+            let mut machine = AsmMachine::new(default_machine, None);
+            #[cfg(not(has_asm))]
+            let mut machine = TraceMachine::new(default_machine);
+            let bytes = machine
+                .load_program(&program, &argv)
+                .map_err(internal_error)?;
+            machine
+                .machine
+                .add_cycles(cycles.saturating_add(transferred_byte_cycles(bytes)))
+                .map_err(internal_error)?;
+            let code = machine.run().map_err(internal_error)?;
+            cycles = machine.machine.cycles();
+            let request = exec_request.borrow_mut().take();
+            match request {
+                Some(request) => {
+                    program = request.program;
+                    argv = request.argv;
+                }
+                None if code == 0 => return Ok(cycles),
+                None => return Err(ScriptError::ValidationFailure(code).into()),
+            }
+        }
+    }
+
+    fn profile_script_group(
+        &self,
+        script_group: &ScriptGroup,
+        max_cycles: Cycle,
+    ) -> Result<ScriptProfile, Error> {
+        let script_hash: H256 = script_group.script.calc_script_hash().unpack();
+        let mut profile = ScriptProfile::new(format!("{:#x}", script_hash));
+        if script_group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
+            && Into::<u8>::into(script_group.script.hash_type())
+                == Into::<u8>::into(ScriptHashType::Type)
+        {
+            let cycles = self.verify_script_group(script_group, max_cycles)?;
+            profile.add_function_cycles("type_id".to_string(), cycles);
+            return Ok(profile);
+        }
+
+        let version = self.select_version(&script_group.script);
+        let mut program = self.extract_script(&script_group.script)?;
+        let mut argv = self.argv.clone();
+        let mut cycles = 0;
+        loop {
+            let symbols = Symbols::parse(&program);
+            let syscall_number = Rc::new(Cell::new(None));
+            let exec_request = Rc::new(RefCell::new(None));
+            let core_machine = ResumableCoreMachineType::new_with_max_cycles(max_cycles);
+            // The recorder must be the first syscall to see every ecall
+            let machine_builder =
+                DefaultMachineBuilder::<ResumableCoreMachineType>::new(core_machine)
+                    .instruction_cycle_func(self.cost_model(version))
+                    .syscall(Box::new(SyscallRecorder::new(Rc::clone(&syscall_number))));
+            let machine_builder = self
+                .generate_syscalls(version, script_group, &exec_request)
+                .into_iter()
+                .fold(machine_builder, |builder, syscall| builder.syscall(syscall));
+            let mut machine = machine_builder.build();
+            let bytes = machine
+                .load_program(&program, &argv)
+                .map_err(internal_error)?;
+            let load_cycles = transferred_byte_cycles(bytes);
+            machine
+                .add_cycles(cycles.saturating_add(load_cycles))
+                .map_err(internal_error)?;
+            profile.add_function_cycles("load_program".to_string(), load_cycles);
+
+            let decoder = build_imac_decoder::<u64>();
+            machine.set_running(true);
+            while machine.running() {
+                let pc = *machine.pc();
+                let cycles = machine.cycles();
+                machine.step(&decoder).map_err(internal_error)?;
+                let consumed = machine.cycles() - cycles;
+                let function = symbols.resolve(pc);
+                match syscall_number.take() {
+                    Some(number) => profile.add_syscall_cycles(function, number, consumed),
+                    None => profile.add_function_cycles(function, consumed),
+                }
+            }
+            cycles = machine.cycles();
+            let request = exec_request.borrow_mut().take();
+            match request {
+                Some(request) => {
+                    program = request.program;
+                    argv = request.argv;
+                }
+                None if machine.exit_code() == 0 => return Ok(profile),
+                None => return Err(ScriptError::ValidationFailure(machine.exit_code()).into()),
+            }
+        }
+    }
+
+    fn resume_script_group(
+        &self,
+        script_group: &ScriptGroup,
+        max_cycles: Cycle,
+        snapshot: Option<&MachineSnapshot>,
+        budget: Cycle,
+    ) -> Result<ScriptGroupResult, Error> {
+        // Type ID script is cheap, it is always completed in one step
+        if script_group.script.code_hash() == TYPE_ID_CODE_HASH.pack()
+            && Into::<u8>::into(script_group.script.hash_type())
+                == Into::<u8>::into(ScriptHashType::Type)
+        {
+            return self
+                .verify_script_group(script_group, max_cycles)
+                .map(ScriptGroupResult::Completed);
+        }
+        if snapshot.is_none() {
+            if let Some(cycles) = self.cached_cycles(script_group, max_cycles) {
+                return Ok(ScriptGroupResult::Completed(cycles));
+            }
+        }
+        // Reads before the snapshot are not recorded, a resumed result is keyed on the
+        // whole transaction
+        let outside_group_read = Rc::new(Cell::new(snapshot.is_some()));
+
+        let version = self.select_version(&script_group.script);
+        let mut exec = snapshot.and_then(|snapshot| snapshot.exec.clone());
+        let (mut program, mut argv) = match &exec {
+            Some(request) => (request.program.clone(), request.argv.clone()),
+            None => (
+                self.extract_script(&script_group.script)?,
+                self.argv.clone(),
+            ),
+        };
+        let mut snapshot = snapshot;
+        let mut suspend_at = None;
+        let mut cycles = 0;
+        loop {
+            let exec_request = Rc::new(RefCell::new(None));
+            let core_machine = ResumableCoreMachineType::new_with_max_cycles(max_cycles);
+            // The recorder must be the first syscall to see every ecall
+            let machine_builder =
+                DefaultMachineBuilder::<ResumableCoreMachineType>::new(core_machine)
+                    .instruction_cycle_func(self.cost_model(version))
+                    .syscall(Box::new(OutsideGroupReadRecorder::new(Rc::clone(
+                        &outside_group_read,
+                    ))));
+            let machine_builder = self
+                .generate_syscalls(version, script_group, &exec_request)
+                .into_iter()
+                .fold(machine_builder, |builder, syscall| builder.syscall(syscall));
+            let mut machine = machine_builder.build();
+            let bytes = machine
+                .load_program(&program, &argv)
+                .map_err(internal_error)?;
+            match snapshot.take() {
+                Some(snapshot) => snapshot.restore(&mut machine).map_err(internal_error)?,
+                None => machine
+                    .add_cycles(cycles.saturating_add(transferred_byte_cycles(bytes)))
+                    .map_err(internal_error)?,
+            }
+
+            let suspend_at = *suspend_at.get_or_insert(machine.cycles().saturating_add(budget));
+            let decoder = build_imac_decoder::<u64>();
+            machine.set_running(true);
+            while machine.running() {
+                if machine.cycles() >= suspend_at {
+                    let mut snapshot =
+                        MachineSnapshot::capture(&mut machine).map_err(internal_error)?;
+                    snapshot.exec = exec;
+                    return Ok(ScriptGroupResult::Suspended(snapshot));
+                }
+                machine.step(&decoder).map_err(internal_error)?;
+            }
+            cycles = machine.cycles();
+            let request = exec_request.borrow_mut().take();
+            match request {
+                Some(request) => {
+                    program = request.program.clone();
+                    argv = request.argv.clone();
+                    exec = Some(request);
+                }
+                None if machine.exit_code() == 0 => {
+                    self.cache_cycles(script_group, outside_group_read.get(), cycles);
+                    return Ok(ScriptGroupResult::Completed(cycles));
+                }
+                None => return Err(ScriptError::ValidationFailure(machine.exit_code()).into()),
+            }
         }
     }
 }
@@ -427,6 +984,10 @@ fn internal_error(error: ckb_vm::Error) -> Error {
     InternalErrorKind::VM.reason(format!("{:?}", error)).into()
 }
 
+fn is_exceeded_maximum_cycles(error: &Error) -> bool {
+    error.downcast_ref::<ScriptError>() == Some(&ScriptError::ExceededMaximumCycles)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -456,6 +1017,10 @@ mod tests {
         secp256k1_data_cell, type_lock_script_code_hash,
     };
     use ckb_vm::Error as VMInternalError;
+    use ckb_vm::{
+        memory::{FLAG_EXECUTABLE, FLAG_FREEZED},
+        RISCV_PAGESIZE,
+    };
     use std::fs::File;
     use std::io::Read;
     use std::path::Path;
@@ -483,6 +1048,11 @@ mod tests {
             .unwrap()
     }
 
+    fn open_cell_load_code() -> File {
+        File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("../script/testdata/load_code"))
+            .unwrap()
+    }
+
     fn new_store() -> ChainDB {
         ChainDB::new(RocksDB::open_tmp(COLUMNS), Default::default())
     }
@@ -1139,6 +1709,493 @@ mod tests {
             verifier.verify(100_000_000).ok(),
             Some(ALWAYS_SUCCESS_SCRIPT_CYCLE * 2)
         );
+
+        // Each group is under the limit, but the total cycles are not
+        assert_error_eq!(
+            verifier
+                .verify(ALWAYS_SUCCESS_SCRIPT_CYCLE * 2 - 1)
+                .unwrap_err(),
+            ScriptError::ExceededMaximumCycles,
+        );
+    }
+
+    #[test]
+    fn check_script_group_cycles() {
+        let mut file = open_cell_always_success();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let cell_dep = CellDep::new_builder()
+            .out_point(dep_out_point.clone())
+            .build();
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(cell_dep)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script.clone())
+            .type_(Some(script.clone()).pack())
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+
+        let group_cycles = verifier.verify_groups(100_000_000).unwrap();
+        assert_eq!(
+            group_cycles
+                .iter()
+                .map(|group| (group.group_type.clone(), group.cycles))
+                .collect::<Vec<_>>(),
+            vec![
+                (ScriptGroupType::Lock, ALWAYS_SUCCESS_SCRIPT_CYCLE),
+                (ScriptGroupType::Type, ALWAYS_SUCCESS_SCRIPT_CYCLE),
+            ]
+        );
+        assert!(group_cycles
+            .iter()
+            .all(|group| group.script_hash == script.calc_script_hash()));
+
+        // The groups share the budget, the total can take all of it
+        let group_cycles = verifier
+            .verify_groups(ALWAYS_SUCCESS_SCRIPT_CYCLE * 2)
+            .unwrap();
+        assert_eq!(group_cycles.len(), 2);
+    }
+
+    #[test]
+    fn check_resumable_verify() {
+        let mut file = open_cell_always_success();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let cell_dep = CellDep::new_builder()
+            .out_point(dep_out_point.clone())
+            .build();
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(cell_dep)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script.clone())
+            .type_(Some(script).pack())
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+
+        let mut suspended = 0;
+        let mut result = verifier.resumable_verify(100_000_000, 100).unwrap();
+        while let VerifyResult::Suspended(snapshot) = result {
+            suspended += 1;
+            result = verifier
+                .resume_from_snapshot(&snapshot, 100_000_000, 100)
+                .unwrap();
+        }
+        assert!(suspended > 1);
+        // Same cycles as verifying in one go
+        assert_eq!(
+            result,
+            VerifyResult::Completed(
+                verifier.verify(100_000_000).unwrap(),
+                verifier.verify_groups(100_000_000).unwrap()
+            )
+        );
+        match result {
+            VerifyResult::Completed(cycles, _) => {
+                assert_eq!(cycles, ALWAYS_SUCCESS_SCRIPT_CYCLE * 2);
+            }
+            VerifyResult::Suspended(_) => panic!("verification should be completed"),
+        }
+    }
+
+    #[test]
+    fn check_resumable_verify_after_load_code() {
+        let mut file = open_cell_load_code();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        // li a0, 0; li a7, 93; ecall
+        let code_out_point = OutPoint::new(h256!("0x124").pack(), 0);
+        let code = Bytes::from(vec![
+            0x13, 0x05, 0x00, 0x00, 0x93, 0x08, 0xd0, 0x05, 0x73, 0x00, 0x00, 0x00,
+        ]);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(code.len()).unwrap().pack())
+            .build();
+        let code_cell = CellMetaBuilder::from_cell_output(output, code)
+            .transaction_info(default_transaction_info())
+            .out_point(code_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(CellDep::new_builder().out_point(dep_out_point).build())
+            .cell_dep(CellDep::new_builder().out_point(code_out_point).build())
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script)
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell, code_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+
+        // The loaded code page must survive the snapshots taken while the script spins
+        let code_page = 0x20000 / RISCV_PAGESIZE as u64;
+        let mut suspended_after_load = 0;
+        let mut result = verifier.resumable_verify(100_000_000, 100).unwrap();
+        while let VerifyResult::Suspended(snapshot) = result {
+            let loaded = snapshot.machine.as_ref().map_or(false, |machine| {
+                machine.pages.iter().any(|(page, flags, _)| {
+                    *page == code_page && *flags == FLAG_EXECUTABLE | FLAG_FREEZED
+                })
+            });
+            if loaded {
+                suspended_after_load += 1;
+            }
+            result = verifier
+                .resume_from_snapshot(&snapshot, 100_000_000, 100)
+                .unwrap();
+        }
+        assert!(suspended_after_load > 1);
+        assert_eq!(
+            result,
+            VerifyResult::Completed(
+                verifier.verify(100_000_000).unwrap(),
+                verifier.verify_groups(100_000_000).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn check_script_verify_cache() {
+        let mut file = open_cell_always_success();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let cell_dep = CellDep::new_builder()
+            .out_point(dep_out_point.clone())
+            .build();
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(cell_dep)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script)
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let script_cache = Arc::new(ScriptVerifyCache::new(10));
+        let mut verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+        verifier.set_script_cache(Arc::clone(&script_cache));
+
+        assert_eq!(
+            verifier.verify(100_000_000).unwrap(),
+            ALWAYS_SUCCESS_SCRIPT_CYCLE
+        );
+        let cache_key = verifier.cache_key(verifier.script_groups()[0], false);
+        assert_eq!(
+            script_cache.get(&cache_key),
+            Some(ALWAYS_SUCCESS_SCRIPT_CYCLE)
+        );
+
+        // Cached cycles are returned without running the script
+        script_cache.insert(cache_key.clone(), 1);
+        assert_eq!(verifier.verify(100_000_000).unwrap(), 1);
+        match verifier.resumable_verify(100_000_000, 100).unwrap() {
+            VerifyResult::Completed(cycles, _) => assert_eq!(cycles, 1),
+            VerifyResult::Suspended(_) => panic!("cached group should not be suspended"),
+        }
+
+        // Cached cycles exceeding the limit are verified again
+        script_cache.insert(cache_key.clone(), 200_000_000);
+        assert_eq!(
+            verifier.verify(100_000_000).unwrap(),
+            ALWAYS_SUCCESS_SCRIPT_CYCLE
+        );
+
+        // The argv passed to the script is part of the key
+        verifier.set_argv(vec![Bytes::from("arg")]);
+        assert_ne!(
+            verifier.cache_key(verifier.script_groups()[0], false),
+            cache_key
+        );
+        verifier.set_argv(vec![]);
+
+        // The witnesses of the group are part of the key, the others are not unless the
+        // script may read them
+        let with_witnesses = |witnesses: Vec<&'static str>| ResolvedTransaction {
+            transaction: rtx
+                .transaction
+                .as_advanced_builder()
+                .set_witnesses(
+                    witnesses
+                        .into_iter()
+                        .map(|w| Bytes::from(w).pack())
+                        .collect(),
+                )
+                .build(),
+            resolved_cell_deps: rtx.resolved_cell_deps.clone(),
+            resolved_inputs: rtx.resolved_inputs.clone(),
+            resolved_dep_groups: vec![],
+        };
+        let group_key = |rtx: &ResolvedTransaction, outside_group_read: bool| {
+            let verifier = TransactionScriptsVerifier::new(rtx, &data_loader);
+            verifier.cache_key(verifier.script_groups()[0], outside_group_read)
+        };
+        let rtx1 = with_witnesses(vec!["group"]);
+        let rtx2 = with_witnesses(vec!["group", "other"]);
+        let rtx3 = with_witnesses(vec!["changed", "other"]);
+        assert_ne!(group_key(&rtx1, false), cache_key);
+        assert_eq!(group_key(&rtx1, false), group_key(&rtx2, false));
+        assert_ne!(group_key(&rtx2, false), group_key(&rtx3, false));
+        assert_ne!(group_key(&rtx1, true), group_key(&rtx2, true));
+    }
+
+    #[test]
+    fn check_script_version() {
+        let mut file = open_cell_always_success();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data1.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let cell_dep = CellDep::new_builder()
+            .out_point(dep_out_point.clone())
+            .build();
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(cell_dep)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script.clone())
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+
+        assert_eq!(verifier.select_version(&script), ScriptVersion::V1);
+        assert_eq!(
+            verifier.verify(100_000_000).unwrap(),
+            ALWAYS_SUCCESS_SCRIPT_CYCLE
+        );
+    }
+
+    #[test]
+    fn check_exec_activation() {
+        let rtx = ResolvedTransaction {
+            transaction: TransactionBuilder::default().build(),
+            resolved_cell_deps: vec![],
+            resolved_inputs: vec![],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let group = ScriptGroup::new(&Script::default(), ScriptGroupType::Lock);
+        let exec_request = Rc::new(RefCell::new(None));
+        let syscalls_count = |exec: bool, version: ScriptVersion| {
+            let mut verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+            verifier.set_exec(exec);
+            let syscalls =
+                verifier.generate_syscalls::<CoreMachineType>(version, &group, &exec_request);
+            syscalls.len()
+        };
+
+        let v0 = syscalls_count(false, ScriptVersion::V0);
+        assert_eq!(syscalls_count(false, ScriptVersion::V1), v0);
+        assert_eq!(syscalls_count(true, ScriptVersion::V0), v0);
+        assert_eq!(syscalls_count(true, ScriptVersion::V1), v0 + 1);
+    }
+
+    #[test]
+    fn check_profile() {
+        let mut file = open_cell_always_success();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let script = Script::new_builder()
+            .code_hash(blake2b_256(&buffer).pack())
+            .hash_type(ScriptHashType::Data.into())
+            .build();
+
+        let dep_out_point = OutPoint::new(h256!("0x123").pack(), 8);
+        let cell_dep = CellDep::new_builder()
+            .out_point(dep_out_point.clone())
+            .build();
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::default()
+            .capacity(Capacity::bytes(data.len()).unwrap().pack())
+            .build();
+        let dep_cell = CellMetaBuilder::from_cell_output(output, data)
+            .transaction_info(default_transaction_info())
+            .out_point(dep_out_point.clone())
+            .build();
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .cell_dep(cell_dep)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(script.clone())
+            .build();
+        let dummy_cell = CellMetaBuilder::from_cell_output(output, Bytes::new())
+            .transaction_info(default_transaction_info())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            resolved_cell_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+            resolved_dep_groups: vec![],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader);
+
+        let profiles = verifier.profile(100_000_000).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].total_cycles(), ALWAYS_SUCCESS_SCRIPT_CYCLE);
+        // always_success only invokes the exit syscall
+        assert!(profiles[0].syscalls.keys().all(|(_, number)| *number == 93));
+        let script_hash: H256 = script.calc_script_hash().unpack();
+        assert!(profiles[0]
+            .folded_stacks()
+            .lines()
+            .all(|line| line.starts_with(&format!("{:#x};", script_hash))));
     }
 
     #[test]
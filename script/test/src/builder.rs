@@ -0,0 +1,61 @@
+use ckb_types::{
+    bytes::Bytes,
+    core::{DepType, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint},
+    prelude::*,
+};
+
+/// Fluent transaction builder, which pairs outputs with their data and wraps out points
+/// into inputs and cell deps.
+#[derive(Default)]
+pub struct TransactionContextBuilder {
+    inner: TransactionBuilder,
+}
+
+impl TransactionContextBuilder {
+    pub fn input(self, out_point: OutPoint) -> Self {
+        self.input_with_since(out_point, 0)
+    }
+
+    pub fn input_with_since(self, out_point: OutPoint, since: u64) -> Self {
+        TransactionContextBuilder {
+            inner: self.inner.input(CellInput::new(out_point, since)),
+        }
+    }
+
+    pub fn output(self, output: CellOutput, data: Bytes) -> Self {
+        TransactionContextBuilder {
+            inner: self.inner.output(output).output_data(data.pack()),
+        }
+    }
+
+    pub fn cell_dep(self, out_point: OutPoint) -> Self {
+        self.cell_dep_with_type(out_point, DepType::Code)
+    }
+
+    pub fn cell_dep_with_type(self, out_point: OutPoint, dep_type: DepType) -> Self {
+        let cell_dep = CellDep::new_builder()
+            .out_point(out_point)
+            .dep_type(dep_type.into())
+            .build();
+        TransactionContextBuilder {
+            inner: self.inner.cell_dep(cell_dep),
+        }
+    }
+
+    pub fn header_dep(self, block_hash: Byte32) -> Self {
+        TransactionContextBuilder {
+            inner: self.inner.header_dep(block_hash),
+        }
+    }
+
+    pub fn witness(self, witness: Bytes) -> Self {
+        TransactionContextBuilder {
+            inner: self.inner.witness(witness.pack()),
+        }
+    }
+
+    pub fn build(self) -> TransactionView {
+        self.inner.build()
+    }
+}
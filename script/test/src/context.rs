@@ -0,0 +1,255 @@
+use crate::builder::TransactionContextBuilder;
+use ckb_error::Error;
+use ckb_hash::blake2b_256;
+use ckb_script::TransactionScriptsVerifier;
+use ckb_script_data_loader::DataLoader;
+use ckb_store::data_loader_wrapper::DataLoaderWrapper;
+use ckb_test_chain_utils::MockStore;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{
+            resolve_transaction, CellMeta, CellMetaBuilder, CellProvider, CellStatus,
+            HeaderChecker, ResolvedTransaction,
+        },
+        error::OutPointError,
+        BlockBuilder, BlockExt, Capacity, Cycle, EpochExt, HeaderView, ScriptHashType,
+        TransactionInfo, TransactionView,
+    },
+    packed::{Byte32, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Result of verifying a transaction in a [`Context`].
+#[derive(Debug)]
+pub struct VerifyOutput {
+    // Total cycles of all the script groups, or the error of the first failed group
+    pub result: Result<Cycle, Error>,
+    // Messages printed by `ckb_debug`, with the hash of the printing script
+    pub debug_messages: Vec<(Byte32, String)>,
+}
+
+/// In-memory chain state for script tests, it implements `DataLoader` for the verifier.
+#[derive(Default)]
+pub struct Context {
+    cells: HashMap<OutPoint, CellMeta>,
+    store: MockStore,
+    native_crypto: bool,
+    exec: bool,
+    // Used to derive unique out points for the created cells
+    nonce: u64,
+}
+
+impl Context {
+    /// Deploy a binary as a cell, returns its out point to be used as a cell dep.
+    pub fn deploy_cell(&mut self, binary: Bytes) -> OutPoint {
+        let output = CellOutput::new_builder()
+            .capacity(
+                Capacity::bytes(binary.len())
+                    .expect("binary capacity")
+                    .pack(),
+            )
+            .build();
+        self.create_cell(output, binary)
+    }
+
+    /// Create a live cell, returns its out point to be used as an input or a cell dep.
+    pub fn create_cell(&mut self, output: CellOutput, data: Bytes) -> OutPoint {
+        let out_point = self.next_out_point();
+        let cell_meta = CellMetaBuilder::from_cell_output(output, data)
+            .out_point(out_point.clone())
+            .build();
+        self.cells.insert(out_point.clone(), cell_meta);
+        out_point
+    }
+
+    /// Create a live cell committed in the block of `header`, so scripts can load the
+    /// header of the cell. The header must be inserted by `insert_header`.
+    pub fn create_cell_in_block(
+        &mut self,
+        output: CellOutput,
+        data: Bytes,
+        header: &HeaderView,
+    ) -> OutPoint {
+        let out_point = self.next_out_point();
+        let cell_meta = CellMetaBuilder::from_cell_output(output, data)
+            .out_point(out_point.clone())
+            .transaction_info(TransactionInfo {
+                block_hash: header.hash(),
+                block_number: header.number(),
+                block_epoch: header.epoch(),
+                // not a cellbase output
+                index: 1,
+            })
+            .build();
+        self.cells.insert(out_point.clone(), cell_meta);
+        out_point
+    }
+
+    pub fn get_cell(&self, out_point: &OutPoint) -> Option<&CellMeta> {
+        self.cells.get(out_point)
+    }
+
+    /// Build a script referencing the binary in the deployed cell by its data hash.
+    pub fn build_script(&self, out_point: &OutPoint, args: Bytes) -> Option<Script> {
+        self.cells.get(out_point).map(|cell_meta| {
+            let data = cell_meta.mem_cell_data.as_ref().expect("cell data");
+            Script::new_builder()
+                .code_hash(CellOutput::calc_data_hash(&data.0))
+                .hash_type(ScriptHashType::Data.into())
+                .args(args.pack())
+                .build()
+        })
+    }
+
+    /// Insert a header which can be used as a header dep, with the epoch it belongs to.
+    pub fn insert_header(&mut self, header: HeaderView, epoch: EpochExt) {
+        let block = BlockBuilder::default().header(header).build_unchecked();
+        self.store.insert_block(&block, &epoch);
+    }
+
+    /// Enable the native blake2b and secp256k1 syscalls
+    pub fn set_native_crypto(&mut self, enabled: bool) {
+        self.native_crypto = enabled;
+    }
+
+    /// Enable the exec syscall for the scripts running on VM version 1
+    pub fn set_exec(&mut self, enabled: bool) {
+        self.exec = enabled;
+    }
+
+    pub fn transaction(&self) -> TransactionContextBuilder {
+        TransactionContextBuilder::default()
+    }
+
+    pub fn resolve(&self, tx: &TransactionView) -> Result<ResolvedTransaction, Error> {
+        resolve_transaction(tx.clone(), &mut HashSet::new(), self, self)
+    }
+
+    /// Resolve and run the scripts of the transaction, `ckb_debug` messages are captured
+    /// instead of being logged.
+    pub fn verify_tx(&self, tx: &TransactionView, max_cycles: Cycle) -> VerifyOutput {
+        let resolved = match self.resolve(tx) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                return VerifyOutput {
+                    result: Err(err),
+                    debug_messages: Vec::new(),
+                }
+            }
+        };
+        let debug_messages = Arc::new(Mutex::new(Vec::new()));
+        let result = {
+            let mut verifier = TransactionScriptsVerifier::new(&resolved, self);
+            let messages = Arc::clone(&debug_messages);
+            verifier.set_debug_printer(move |hash: &Byte32, message: &str| {
+                messages
+                    .lock()
+                    .expect("debug messages lock")
+                    .push((hash.clone(), message.to_owned()));
+            });
+            verifier.set_native_crypto(self.native_crypto);
+            verifier.set_exec(self.exec);
+            verifier.verify(max_cycles)
+        };
+        let debug_messages = debug_messages
+            .lock()
+            .expect("debug messages lock")
+            .drain(..)
+            .collect();
+        VerifyOutput {
+            result,
+            debug_messages,
+        }
+    }
+
+    fn next_out_point(&mut self) -> OutPoint {
+        self.nonce += 1;
+        let tx_hash = blake2b_256(self.nonce.to_le_bytes());
+        OutPoint::new(tx_hash.pack(), 0)
+    }
+}
+
+impl CellProvider for Context {
+    fn cell(&self, out_point: &OutPoint, _with_data: bool) -> CellStatus {
+        self.cells
+            .get(out_point)
+            .cloned()
+            .map(CellStatus::live_cell)
+            .unwrap_or(CellStatus::Unknown)
+    }
+}
+
+impl HeaderChecker for Context {
+    fn check_valid(&self, block_hash: &Byte32) -> Result<(), Error> {
+        if self.get_header(block_hash).is_some() {
+            Ok(())
+        } else {
+            Err(OutPointError::InvalidHeader(block_hash.clone()).into())
+        }
+    }
+}
+
+impl DataLoader for Context {
+    fn load_cell_data(&self, cell: &CellMeta) -> Option<(Bytes, Byte32)> {
+        cell.mem_cell_data.clone()
+    }
+
+    fn get_block_ext(&self, block_hash: &Byte32) -> Option<BlockExt> {
+        DataLoaderWrapper::new(self.store.store()).get_block_ext(block_hash)
+    }
+
+    fn get_block_epoch(&self, block_hash: &Byte32) -> Option<EpochExt> {
+        DataLoaderWrapper::new(self.store.store()).get_block_epoch(block_hash)
+    }
+
+    fn get_header(&self, block_hash: &Byte32) -> Option<HeaderView> {
+        DataLoaderWrapper::new(self.store.store()).get_header(block_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_test_chain_utils::always_success_cell;
+    use ckb_types::core::{capacity_bytes, HeaderBuilder};
+
+    #[test]
+    fn verify_always_success() {
+        let mut context = Context::default();
+        let (_, always_success_data, _) = always_success_cell();
+        let lock_out_point = context.deploy_cell(always_success_data.clone());
+        let lock_script = context.build_script(&lock_out_point, Bytes::new()).unwrap();
+
+        let output = CellOutput::new_builder()
+            .capacity(capacity_bytes!(100).pack())
+            .lock(lock_script)
+            .build();
+        let input_out_point = context.create_cell(output.clone(), Bytes::new());
+        let header = HeaderBuilder::default().number(10u64.pack()).build();
+        context.insert_header(header.clone(), EpochExt::default());
+
+        let tx = context
+            .transaction()
+            .input(input_out_point)
+            .output(output, Bytes::new())
+            .cell_dep(lock_out_point)
+            .header_dep(header.hash())
+            .build();
+        let output = context.verify_tx(&tx, 10_000_000);
+        assert!(output.result.unwrap() > 0);
+        assert!(output.debug_messages.is_empty());
+    }
+
+    #[test]
+    fn verify_unknown_input() {
+        let context = Context::default();
+        let tx = context
+            .transaction()
+            .input(OutPoint::new(Byte32::zero(), 0))
+            .build();
+        assert!(context.verify_tx(&tx, 10_000_000).result.is_err());
+    }
+}
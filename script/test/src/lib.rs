@@ -0,0 +1,31 @@
+//! Helpers to unit test scripts without a running chain.
+//!
+//! A [`Context`] keeps the deployed binaries and other cells in memory, and headers in a
+//! [`MockStore`](ckb_test_chain_utils::MockStore). Transactions built with
+//! [`TransactionContextBuilder`] are resolved against the context and verified by
+//! `TransactionScriptsVerifier`.
+//!
+//! ```ignore
+//! let mut context = Context::default();
+//! let lock_out_point = context.deploy_cell(lock_binary);
+//! let lock_script = context.build_script(&lock_out_point, args).unwrap();
+//! let input_out_point = context.create_cell(
+//!     CellOutput::new_builder().capacity(capacity.pack()).lock(lock_script.clone()).build(),
+//!     Bytes::new(),
+//! );
+//! let tx = context
+//!     .transaction()
+//!     .input(input_out_point)
+//!     .output(output, Bytes::new())
+//!     .cell_dep(lock_out_point)
+//!     .witness(signature)
+//!     .build();
+//! let output = context.verify_tx(&tx, MAX_CYCLES);
+//! assert!(output.result.is_ok());
+//! ```
+
+mod builder;
+mod context;
+
+pub use crate::builder::TransactionContextBuilder;
+pub use crate::context::{Context, VerifyOutput};
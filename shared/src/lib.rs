@@ -1,5 +1,6 @@
+pub mod reorg;
 pub mod shared;
 
-pub use ckb_snapshot::{Snapshot, SnapshotMgr};
+pub use ckb_snapshot::{Snapshot, SnapshotMgr, MAX_CELL_SET_AT_DEPTH};
 
 pub(crate) const LOG_TARGET_CHAIN: &str = "ckb-chain";
@@ -0,0 +1,36 @@
+use ckb_types::{core::BlockNumber, packed::Byte32};
+use std::collections::VecDeque;
+
+// Only the latest reorgs are kept in memory
+pub const MAX_REORG_RECORDS: usize = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReorgRecord {
+    // unix time in milliseconds
+    pub timestamp: u64,
+    // number of blocks detached from the main chain
+    pub depth: BlockNumber,
+    pub detached_blocks: Vec<Byte32>,
+    pub attached_blocks: Vec<Byte32>,
+    // the fork exceeded the max reorg depth and was kept as a fork
+    pub refused: bool,
+}
+
+#[derive(Default, Debug)]
+pub struct ReorgHistory {
+    records: VecDeque<ReorgRecord>,
+}
+
+impl ReorgHistory {
+    pub fn push(&mut self, record: ReorgRecord) {
+        if self.records.len() >= MAX_REORG_RECORDS {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    // oldest first
+    pub fn records(&self) -> Vec<ReorgRecord> {
+        self.records.iter().cloned().collect()
+    }
+}
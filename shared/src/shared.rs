@@ -1,3 +1,4 @@
+use crate::reorg::{ReorgHistory, ReorgRecord};
 use crate::{Snapshot, SnapshotMgr};
 use arc_swap::Guard;
 use ckb_chain_spec::consensus::Consensus;
@@ -6,8 +7,9 @@ use ckb_db::{DBConfig, RocksDB};
 use ckb_error::{Error, InternalErrorKind};
 use ckb_logger::info_target;
 use ckb_proposal_table::{ProposalTable, ProposalView};
+use ckb_script::ScriptVerifyCache;
 use ckb_store::ChainDB;
-use ckb_store::{ChainStore, StoreConfig, COLUMNS};
+use ckb_store::{chain_db_config, migrations, ChainStore, StoreConfig, COLUMNS};
 use ckb_tx_pool::{
     BlockAssemblerConfig, PollLock, TxPoolConfig, TxPoolController, TxPoolServiceBuilder,
 };
@@ -17,6 +19,7 @@ use ckb_types::{
     prelude::*,
     U256,
 };
+use ckb_util::RwLock;
 use ckb_verification::cache::TxVerifyCache;
 use im::hashmap::HashMap as HamtMap;
 use std::collections::HashSet;
@@ -27,8 +30,10 @@ pub struct Shared {
     pub(crate) store: Arc<ChainDB>,
     pub(crate) tx_pool_controller: TxPoolController,
     pub(crate) txs_verify_cache: PollLock<TxVerifyCache>,
+    pub(crate) script_verify_cache: Arc<ScriptVerifyCache>,
     pub(crate) consensus: Arc<Consensus>,
     pub(crate) snapshot_mgr: Arc<SnapshotMgr>,
+    pub(crate) reorg_history: Arc<RwLock<ReorgHistory>>,
 }
 
 impl Shared {
@@ -51,6 +56,9 @@ impl Shared {
 
         let txs_verify_cache =
             PollLock::new(TxVerifyCache::new(tx_pool_config.max_verify_cache_size));
+        let script_verify_cache = Arc::new(ScriptVerifyCache::new(
+            tx_pool_config.max_script_verify_cache_size,
+        ));
         let snapshot = Arc::new(Snapshot::new(
             tip_header,
             total_difficulty,
@@ -67,6 +75,7 @@ impl Shared {
             Arc::clone(&snapshot),
             block_assembler_config,
             txs_verify_cache.clone(),
+            Arc::clone(&script_verify_cache),
             Arc::clone(&snapshot_mgr),
         );
 
@@ -76,8 +85,10 @@ impl Shared {
             store,
             consensus,
             txs_verify_cache,
+            script_verify_cache,
             snapshot_mgr,
             tx_pool_controller,
+            reorg_history: Default::default(),
         };
 
         Ok((shared, proposal_table))
@@ -185,6 +196,10 @@ impl Shared {
         self.txs_verify_cache.clone()
     }
 
+    pub fn script_verify_cache(&self) -> Arc<ScriptVerifyCache> {
+        Arc::clone(&self.script_verify_cache)
+    }
+
     pub fn snapshot(&self) -> Guard<Arc<Snapshot>> {
         self.snapshot_mgr.load()
     }
@@ -216,6 +231,14 @@ impl Shared {
         &self.consensus
     }
 
+    pub fn record_reorg(&self, record: ReorgRecord) {
+        self.reorg_history.write().push(record);
+    }
+
+    pub fn reorg_history(&self) -> Vec<ReorgRecord> {
+        self.reorg_history.read().records()
+    }
+
     pub fn genesis_hash(&self) -> Byte32 {
         self.consensus.genesis_hash()
     }
@@ -247,7 +270,8 @@ impl Default for SharedBuilder {
 
 impl SharedBuilder {
     pub fn with_db_config(config: &DBConfig) -> Self {
-        let db = RocksDB::open(config, COLUMNS);
+        let db = RocksDB::open_with_migrations(&chain_db_config(config), COLUMNS, &migrations())
+            .unwrap_or_else(|err| panic!("{}", err));
         SharedBuilder {
             db,
             consensus: None,
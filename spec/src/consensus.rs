@@ -1,5 +1,6 @@
 #![allow(clippy::inconsistent_digit_grouping)]
 
+use crate::hardfork::{HardForkConfig, HardForkFeature};
 use crate::{
     calculate_block_reward, OUTPUT_INDEX_DAO, OUTPUT_INDEX_SECP256K1_BLAKE160_SIGHASH_ALL,
 };
@@ -243,6 +244,8 @@ impl ConsensusBuilder {
                 primary_epoch_reward_halving_interval:
                     DEFAULT_PRIMARY_EPOCH_REWARD_HALVING_INTERVAL,
                 permanent_difficulty_in_dummy: false,
+                assume_valid_target: None,
+                hardfork: HardForkConfig::default(),
             },
         }
     }
@@ -374,6 +377,16 @@ impl ConsensusBuilder {
         self.inner.permanent_difficulty_in_dummy = permanent;
         self
     }
+
+    pub fn assume_valid_target(mut self, target: Option<H256>) -> Self {
+        self.inner.assume_valid_target = target;
+        self
+    }
+
+    pub fn hardfork(mut self, hardfork: HardForkConfig) -> Self {
+        self.inner.hardfork = hardfork;
+        self
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -420,6 +433,11 @@ pub struct Consensus {
     pub primary_epoch_reward_halving_interval: EpochNumber,
     // Keep difficulty be permanent if the pow is dummy
     pub permanent_difficulty_in_dummy: bool,
+    // The scripts of this block and its ancestors are assumed valid and not executed
+    // during the initial block download
+    pub assume_valid_target: Option<H256>,
+    // Activation epochs of the hard fork features
+    pub hardfork: HardForkConfig,
 }
 
 // genesis difficulty should not be zero
@@ -516,6 +534,30 @@ impl Consensus {
         self.pow.is_dummy() && self.permanent_difficulty_in_dummy
     }
 
+    pub fn assume_valid_target(&self) -> Option<&H256> {
+        self.assume_valid_target.as_ref()
+    }
+
+    pub fn hardfork(&self) -> &HardForkConfig {
+        &self.hardfork
+    }
+
+    pub fn is_active(&self, feature: HardForkFeature, epoch: EpochNumber) -> bool {
+        self.hardfork.is_active(feature, epoch)
+    }
+
+    pub fn native_crypto_syscalls_enabled(&self, epoch: EpochNumber) -> bool {
+        self.is_active(HardForkFeature::NativeCryptoSyscalls, epoch)
+    }
+
+    pub fn script_hash_type_data1_enabled(&self, epoch: EpochNumber) -> bool {
+        self.is_active(HardForkFeature::ScriptHashTypeData1, epoch)
+    }
+
+    pub fn exec_syscall_enabled(&self, epoch: EpochNumber) -> bool {
+        self.is_active(HardForkFeature::ExecSyscall, epoch)
+    }
+
     pub fn cellbase_maturity(&self) -> EpochNumberWithFraction {
         self.cellbase_maturity
     }
@@ -544,6 +586,24 @@ impl Consensus {
         self.tx_version
     }
 
+    // The highest block version accepted in `epoch`
+    pub fn max_block_version(&self, epoch: EpochNumber) -> Version {
+        if self.is_active(HardForkFeature::BlockVersion1, epoch) {
+            cmp::max(self.block_version, 1)
+        } else {
+            self.block_version
+        }
+    }
+
+    // The highest transaction version accepted in `epoch`
+    pub fn max_tx_version(&self, epoch: EpochNumber) -> Version {
+        if self.is_active(HardForkFeature::TxVersion1, epoch) {
+            cmp::max(self.tx_version, 1)
+        } else {
+            self.tx_version
+        }
+    }
+
     pub fn type_id_code_hash(&self) -> &H256 {
         &self.type_id_code_hash
     }
@@ -777,6 +837,21 @@ pub mod test {
     use ckb_types::core::{capacity_bytes, BlockBuilder, HeaderBuilder, TransactionBuilder};
     use ckb_types::packed::Bytes;
 
+    #[test]
+    fn test_hardfork_versions() {
+        let consensus = ConsensusBuilder::default().build();
+        assert_eq!(consensus.max_block_version(100), BLOCK_VERSION);
+        assert_eq!(consensus.max_tx_version(100), TX_VERSION);
+
+        let mut hardfork = HardForkConfig::default();
+        hardfork.set_activation_epoch(HardForkFeature::BlockVersion1, Some(10));
+        let consensus = ConsensusBuilder::default().hardfork(hardfork).build();
+        assert_eq!(consensus.max_block_version(9), BLOCK_VERSION);
+        assert_eq!(consensus.max_block_version(10), 1);
+        assert_eq!(consensus.max_tx_version(10), TX_VERSION);
+        assert!(!consensus.is_active(HardForkFeature::NativeCryptoSyscalls, 10));
+    }
+
     #[test]
     fn test_init_epoch_reward() {
         let cellbase = TransactionBuilder::default()
@@ -0,0 +1,146 @@
+//! Hard fork activation
+//!
+//! Every consensus rule change is a named feature which is activated from an epoch configured
+//! in the chain spec under `[params.hardfork]`. A feature without an activation epoch is never
+//! activated.
+
+use ckb_types::core::EpochNumber;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+/// The consensus rule changes which are gated by the hard fork framework
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HardForkFeature {
+    /// Accept blocks whose header version is 1
+    BlockVersion1,
+    /// Accept transactions whose version is 1
+    TxVersion1,
+    /// Expose the native blake2b and secp256k1 syscalls to scripts
+    NativeCryptoSyscalls,
+    /// Accept scripts whose hash type is `data1`
+    ScriptHashTypeData1,
+    /// Expose the exec syscall to the scripts running on VM version 1
+    ExecSyscall,
+}
+
+impl HardForkFeature {
+    pub const ALL: [HardForkFeature; 5] = [
+        HardForkFeature::BlockVersion1,
+        HardForkFeature::TxVersion1,
+        HardForkFeature::NativeCryptoSyscalls,
+        HardForkFeature::ScriptHashTypeData1,
+        HardForkFeature::ExecSyscall,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HardForkFeature::BlockVersion1 => "block_version_1",
+            HardForkFeature::TxVersion1 => "tx_version_1",
+            HardForkFeature::NativeCryptoSyscalls => "native_crypto_syscalls",
+            HardForkFeature::ScriptHashTypeData1 => "script_hash_type_data1",
+            HardForkFeature::ExecSyscall => "exec_syscall",
+        }
+    }
+}
+
+impl fmt::Display for HardForkFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Activation epochs of the hard fork features, `None` means never activated
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HardForkConfig {
+    #[serde(default)]
+    pub block_version_1: Option<EpochNumber>,
+    #[serde(default)]
+    pub tx_version_1: Option<EpochNumber>,
+    #[serde(default)]
+    pub native_crypto_syscalls: Option<EpochNumber>,
+    #[serde(default)]
+    pub script_hash_type_data1: Option<EpochNumber>,
+    #[serde(default)]
+    pub exec_syscall: Option<EpochNumber>,
+}
+
+impl HardForkConfig {
+    /// Activates all the features from `epoch`, mostly for dev chains and tests.
+    pub fn all_activated_at(epoch: EpochNumber) -> Self {
+        HardForkConfig {
+            block_version_1: Some(epoch),
+            tx_version_1: Some(epoch),
+            native_crypto_syscalls: Some(epoch),
+            script_hash_type_data1: Some(epoch),
+            exec_syscall: Some(epoch),
+        }
+    }
+
+    pub fn activation_epoch(&self, feature: HardForkFeature) -> Option<EpochNumber> {
+        match feature {
+            HardForkFeature::BlockVersion1 => self.block_version_1,
+            HardForkFeature::TxVersion1 => self.tx_version_1,
+            HardForkFeature::NativeCryptoSyscalls => self.native_crypto_syscalls,
+            HardForkFeature::ScriptHashTypeData1 => self.script_hash_type_data1,
+            HardForkFeature::ExecSyscall => self.exec_syscall,
+        }
+    }
+
+    pub fn set_activation_epoch(&mut self, feature: HardForkFeature, epoch: Option<EpochNumber>) {
+        let field = match feature {
+            HardForkFeature::BlockVersion1 => &mut self.block_version_1,
+            HardForkFeature::TxVersion1 => &mut self.tx_version_1,
+            HardForkFeature::NativeCryptoSyscalls => &mut self.native_crypto_syscalls,
+            HardForkFeature::ScriptHashTypeData1 => &mut self.script_hash_type_data1,
+            HardForkFeature::ExecSyscall => &mut self.exec_syscall,
+        };
+        *field = epoch;
+    }
+
+    pub fn is_active(&self, feature: HardForkFeature, epoch: EpochNumber) -> bool {
+        self.activation_epoch(feature)
+            .map(|activation| epoch >= activation)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_active() {
+        let mut config = HardForkConfig::default();
+        for feature in HardForkFeature::ALL.iter() {
+            assert!(!config.is_active(*feature, 0));
+            assert!(!config.is_active(*feature, EpochNumber::max_value()));
+        }
+
+        config.set_activation_epoch(HardForkFeature::TxVersion1, Some(10));
+        assert!(!config.is_active(HardForkFeature::TxVersion1, 9));
+        assert!(config.is_active(HardForkFeature::TxVersion1, 10));
+        assert!(config.is_active(HardForkFeature::TxVersion1, 11));
+        assert!(!config.is_active(HardForkFeature::BlockVersion1, 10));
+
+        let config = HardForkConfig::all_activated_at(0);
+        for feature in HardForkFeature::ALL.iter() {
+            assert!(config.is_active(*feature, 0));
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let config: HardForkConfig = toml::from_str("tx_version_1 = 5").unwrap();
+        assert_eq!(
+            config.activation_epoch(HardForkFeature::TxVersion1),
+            Some(5)
+        );
+        assert_eq!(
+            config.activation_epoch(HardForkFeature::BlockVersion1),
+            None
+        );
+
+        assert!(toml::from_str::<HardForkConfig>("unknown_feature = 0").is_err());
+    }
+}
@@ -13,6 +13,7 @@ use crate::consensus::{
     build_genesis_dao_data, build_genesis_epoch_ext, Consensus, ConsensusBuilder,
     SATOSHI_CELL_OCCUPIED_RATIO, SATOSHI_PUBKEY_HASH, TYPE_ID_CODE_HASH,
 };
+use crate::hardfork::HardForkConfig;
 use ckb_crypto::secp::Privkey;
 use ckb_hash::{blake2b_256, new_blake2b};
 use ckb_jsonrpc_types::Script;
@@ -42,6 +43,7 @@ pub use error::SpecError;
 
 pub mod consensus;
 mod error;
+pub mod hardfork;
 
 // Just a random secp256k1 secret key for dep group input cell's lock
 const SPECIAL_CELL_PRIVKEY: H256 =
@@ -73,6 +75,12 @@ pub struct Params {
     pub genesis_epoch_length: BlockNumber,
     #[serde(default)]
     pub permanent_difficulty_in_dummy: bool,
+    // Skip script execution for this block and its ancestors, can be overridden in ckb.toml
+    #[serde(default)]
+    pub assume_valid_target: Option<H256>,
+    // Activation epochs of the hard fork features, must be the last field since it's a table
+    #[serde(default)]
+    pub hardfork: HardForkConfig,
 }
 
 impl Default for Params {
@@ -91,6 +99,8 @@ impl Default for Params {
             epoch_duration_target: DEFAULT_EPOCH_DURATION_TARGET,
             genesis_epoch_length: GENESIS_EPOCH_LENGTH,
             permanent_difficulty_in_dummy: false,
+            assume_valid_target: None,
+            hardfork: HardForkConfig::default(),
         }
     }
 }
@@ -242,6 +252,8 @@ impl ChainSpec {
             .initial_primary_epoch_reward(self.params.initial_primary_epoch_reward)
             .epoch_duration_target(self.params.epoch_duration_target)
             .permanent_difficulty_in_dummy(self.params.permanent_difficulty_in_dummy)
+            .assume_valid_target(self.params.assume_valid_target.clone())
+            .hardfork(self.params.hardfork.clone())
             .build();
 
         Ok(consensus)
@@ -327,11 +339,12 @@ impl ChainSpec {
             })
         {
             match ScriptHashType::try_from(lock_script.hash_type()).expect("checked data") {
-                ScriptHashType::Data => {
+                ScriptHashType::Data | ScriptHashType::Data1 => {
                     if !data_hashes.contains_key(&lock_script.code_hash()) {
                         return Err(format!(
-                            "Invalid lock script: code_hash={}, hash_type=data",
+                            "Invalid lock script: code_hash={}, hash_type={}",
                             lock_script.code_hash(),
+                            lock_script.hash_type(),
                         )
                         .into());
                     }
@@ -1,4 +1,12 @@
+use crate::{
+    COLUMN_BLOCK_BODY, COLUMN_BLOCK_EXT, COLUMN_BLOCK_HEADER, COLUMN_CELL_SET, COLUMN_INDEX,
+    COLUMN_TRANSACTION_INFO,
+};
+use ckb_db::config::{ColumnConfig, CompressionType};
+use ckb_db::DBConfig;
+use ckb_types::core::BlockNumber;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash, Debug)]
 pub struct StoreConfig {
@@ -8,6 +16,14 @@ pub struct StoreConfig {
     pub block_tx_hashes_cache_size: usize,
     pub block_uncles_cache_size: usize,
     pub cellbase_cache_size: usize,
+    // The cell set undo data is kept for this many blocks below the tip, the older records are
+    // pruned and detaching those blocks falls back to rebuilding the spent metas
+    #[serde(default = "default_cell_undo_depth")]
+    pub cell_undo_depth: BlockNumber,
+}
+
+fn default_cell_undo_depth() -> BlockNumber {
+    10_000
 }
 
 impl Default for StoreConfig {
@@ -19,6 +35,45 @@ impl Default for StoreConfig {
             block_tx_hashes_cache_size: 30,
             block_uncles_cache_size: 30,
             cellbase_cache_size: 30,
+            cell_undo_depth: default_cell_undo_depth(),
         }
     }
 }
+
+/// Applies the per column family defaults of the chain database to the `[db]` settings which
+/// are not configured
+pub fn chain_db_config(config: &DBConfig) -> DBConfig {
+    config.clone().with_column_defaults(default_columns())
+}
+
+fn default_columns() -> HashMap<String, ColumnConfig> {
+    let column = |block_cache_share, bloom_filter_bits, compression, prefix_len| ColumnConfig {
+        block_cache_share: Some(block_cache_share),
+        bloom_filter_bits,
+        compression,
+        prefix_len,
+    };
+    vec![
+        // headers and the main chain index are hot, read by every verification and sync request
+        (COLUMN_INDEX, column(0.1, Some(10), None, None)),
+        (
+            COLUMN_BLOCK_HEADER,
+            column(0.2, Some(10), Some(CompressionType::Lz4), None),
+        ),
+        // block bodies are large and cold, scanned by the block hash prefix of their keys
+        (
+            COLUMN_BLOCK_BODY,
+            column(0.1, None, Some(CompressionType::Zstd), Some(32)),
+        ),
+        (COLUMN_TRANSACTION_INFO, column(0.1, Some(10), None, None)),
+        (COLUMN_BLOCK_EXT, column(0.05, Some(10), None, None)),
+        // the cell set churns with every block
+        (
+            COLUMN_CELL_SET,
+            column(0.3, Some(10), Some(CompressionType::Lz4), None),
+        ),
+    ]
+    .into_iter()
+    .map(|(col, column)| (col.to_owned(), column))
+    .collect()
+}
@@ -11,7 +11,7 @@ use ckb_db::{
 };
 use ckb_error::Error;
 use ckb_types::{
-    core::{BlockExt, TransactionMeta},
+    core::{BlockExt, BlockNumber, TransactionMeta},
     packed,
     prelude::*,
 };
@@ -20,6 +20,7 @@ use std::sync::Arc;
 pub struct ChainDB {
     db: RocksDB,
     cache: Arc<StoreCache>,
+    cell_undo_depth: BlockNumber,
 }
 
 impl<'a> ChainStore<'a> for ChainDB {
@@ -53,6 +54,7 @@ impl ChainDB {
         ChainDB {
             db,
             cache: Arc::new(cache),
+            cell_undo_depth: config.cell_undo_depth,
         }
     }
 
@@ -73,6 +75,7 @@ impl ChainDB {
         StoreTransaction {
             inner: self.db.transaction(),
             cache: Arc::clone(&self.cache),
+            cell_undo_depth: self.cell_undo_depth,
         }
     }
 
@@ -140,8 +143,11 @@ impl ChainDB {
 mod tests {
     use super::super::COLUMNS;
     use super::*;
+    use crate::COLUMN_CELL_UNDO;
     use ckb_chain_spec::consensus::ConsensusBuilder;
     use ckb_db::RocksDB;
+    use ckb_types::core::{BlockBuilder, BlockView, TransactionBuilder};
+    use ckb_types::packed::{CellInput, OutPoint};
 
     fn setup_db(columns: u32) -> RocksDB {
         RocksDB::open_tmp(columns)
@@ -182,6 +188,53 @@ mod tests {
         assert_eq!(block, store.get_block(&hash).unwrap());
     }
 
+    #[test]
+    fn save_header_without_block() {
+        let db = setup_db(COLUMNS);
+        let store = ChainDB::new(db, Default::default());
+        let consensus = ConsensusBuilder::default().build();
+        let header = consensus.genesis_block().header();
+
+        let hash = header.hash();
+        let txn = store.begin_transaction();
+        txn.insert_header(&header).unwrap();
+        txn.insert_block_index(&hash, header.number()).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(header, store.get_block_header(&hash).unwrap());
+        assert_eq!(hash, store.get_block_hash(header.number()).unwrap());
+        assert!(store.get_block(&hash).is_none());
+    }
+
+    #[test]
+    fn index_chain_tips() {
+        let db = setup_db(COLUMNS);
+        let store = ChainDB::new(db, Default::default());
+        let consensus = ConsensusBuilder::default().build();
+        let genesis = consensus.genesis_block().clone();
+        let child = |parent: &BlockView, timestamp: u64| {
+            BlockBuilder::default()
+                .parent_hash(parent.hash())
+                .number((parent.number() + 1).pack())
+                .timestamp(timestamp.pack())
+                .build()
+        };
+        let block1 = child(&genesis, 1);
+        let block2 = child(&block1, 2);
+        let fork1 = child(&genesis, 3);
+
+        let txn = store.begin_transaction();
+        for block in &[genesis, block1, block2.clone(), fork1.clone()] {
+            txn.insert_block(block).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let mut tips = store.get_chain_tips();
+        tips.sort();
+        let mut expected = vec![block2.hash(), fork1.hash()];
+        expected.sort();
+        assert_eq!(tips, expected);
+    }
+
     #[test]
     fn save_and_get_block_ext() {
         let db = setup_db(COLUMNS);
@@ -223,4 +276,58 @@ mod tests {
 
         assert_eq!(block.header(), store.get_tip_header().unwrap());
     }
+
+    #[test]
+    fn prune_block_undo() {
+        let db = RocksDB::open_tmp(COLUMNS);
+        let config = StoreConfig {
+            cell_undo_depth: 2,
+            ..Default::default()
+        };
+        let store = ChainDB::new(db, config);
+        let consensus = ConsensusBuilder::default().build();
+        store.init(&consensus).unwrap();
+
+        let genesis_tx_hash = consensus.genesis_block().transactions()[0].hash();
+        let genesis_tx_meta = store.get_tx_meta(&genesis_tx_hash).unwrap();
+        let blocks: Vec<BlockView> = (1..=3)
+            .map(|number| {
+                let cellbase = TransactionBuilder::default()
+                    .input(CellInput::new_cellbase_input(number))
+                    .build();
+                let mut builder = BlockBuilder::default()
+                    .number(number.pack())
+                    .transaction(cellbase);
+                if number == 1 {
+                    builder = builder.transaction(
+                        TransactionBuilder::default()
+                            .input(CellInput::new(OutPoint::new(genesis_tx_hash.clone(), 0), 0))
+                            .build(),
+                    );
+                }
+                builder.build()
+            })
+            .collect();
+        let attach = |block: &BlockView| {
+            let txn = store.begin_transaction();
+            txn.insert_block(block).unwrap();
+            txn.attach_block(block).unwrap();
+            txn.commit().unwrap();
+        };
+
+        attach(&blocks[0]);
+        attach(&blocks[1]);
+        assert_eq!(
+            store.get_block_undo(&blocks[0].hash()),
+            Some(vec![(genesis_tx_hash, genesis_tx_meta)])
+        );
+        // the blocks which spend no earlier cell have no record
+        assert!(store
+            .get(COLUMN_CELL_UNDO, blocks[1].hash().as_slice())
+            .is_none());
+        assert_eq!(store.get_block_undo(&blocks[1].hash()), Some(Vec::new()));
+
+        attach(&blocks[2]);
+        assert!(store.get_block_undo(&blocks[0].hash()).is_none());
+    }
 }
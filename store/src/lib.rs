@@ -2,20 +2,22 @@ mod cache;
 mod config;
 pub mod data_loader_wrapper;
 mod db;
+mod migrations;
 mod snapshot;
 mod store;
 mod transaction;
 
 pub use cache::StoreCache;
-pub use config::StoreConfig;
+pub use config::{chain_db_config, StoreConfig};
 pub use db::ChainDB;
+pub use migrations::migrations;
 pub use snapshot::StoreSnapshot;
 pub use store::ChainStore;
 pub use transaction::StoreTransaction;
 
 use ckb_db::Col;
 
-pub const COLUMNS: u32 = 12;
+pub const COLUMNS: u32 = 15;
 pub const COLUMN_INDEX: Col = "0";
 pub const COLUMN_BLOCK_HEADER: Col = "1";
 pub const COLUMN_BLOCK_BODY: Col = "2";
@@ -28,6 +30,9 @@ pub const COLUMN_BLOCK_EPOCH: Col = "8";
 pub const COLUMN_EPOCH: Col = "9";
 pub const COLUMN_CELL_SET: Col = "10";
 pub const COLUMN_UNCLES: Col = "11";
+pub const COLUMN_CELL_UNDO: Col = "12";
+pub const COLUMN_BLOCK_CHILDREN: Col = "13";
+pub const COLUMN_CHAIN_TIPS: Col = "14";
 
 const META_TIP_HEADER_KEY: &[u8] = b"TIP_HEADER";
 const META_CURRENT_EPOCH_KEY: &[u8] = b"CURRENT_EPOCH";
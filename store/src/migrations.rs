@@ -0,0 +1,104 @@
+//! The migrations of the chain database
+//!
+//! When the format of a column changes, register a `Migration` which upgrades the existing data
+//! in place here, its version becomes the version required by the chain database.
+
+use crate::{COLUMN_BLOCK_CHILDREN, COLUMN_BLOCK_HEADER, COLUMN_CHAIN_TIPS};
+use ckb_db::{Migration, Migrations, Result, RocksDB};
+use ckb_types::{packed, prelude::*};
+
+// The index is written in batches of this many children
+const BATCH_SIZE: usize = 10_000;
+
+/// The ordered migrations of the chain database.
+pub fn migrations() -> Migrations {
+    let mut migrations = Migrations::new();
+    migrations.add_migration(Box::new(AddBlockChildrenIndex));
+    migrations.add_migration(Box::new(AddChainTipsIndex));
+    migrations
+}
+
+// Indexes the children of the stored blocks, which are used to find the descendants of a block
+struct AddBlockChildrenIndex;
+
+impl Migration for AddBlockChildrenIndex {
+    fn version(&self) -> &str {
+        "0.2200.1"
+    }
+
+    fn migrate(&self, db: &RocksDB, progress: &dyn Fn(u64, u64)) -> Result<()> {
+        let mut total = 0;
+        db.traverse(COLUMN_BLOCK_HEADER, |_, _| {
+            total += 1;
+            Ok(())
+        })?;
+
+        // the children are written while traversing, only one batch is kept in memory
+        let mut txn = db.transaction();
+        let mut pending = 0;
+        let mut done = 0;
+        db.traverse(COLUMN_BLOCK_HEADER, |hash, value| {
+            let header = packed::HeaderViewReader::from_slice_should_be_ok(value);
+            let key = [header.data().raw().parent_hash().as_slice(), hash].concat();
+            txn.put(COLUMN_BLOCK_CHILDREN, &key, &[])?;
+            pending += 1;
+            if pending == BATCH_SIZE {
+                txn.commit()?;
+                txn = db.transaction();
+                done += pending as u64;
+                pending = 0;
+                progress(done, total);
+            }
+            Ok(())
+        })?;
+        txn.commit()?;
+        progress(done + pending as u64, total);
+        Ok(())
+    }
+}
+
+// Indexes the stored blocks without a stored child, which are the tips of the main chain and the
+// forks. Every block is added first, then the parents are removed.
+struct AddChainTipsIndex;
+
+impl Migration for AddChainTipsIndex {
+    fn version(&self) -> &str {
+        "0.2200.2"
+    }
+
+    fn migrate(&self, db: &RocksDB, progress: &dyn Fn(u64, u64)) -> Result<()> {
+        let mut total = 0;
+        db.traverse(COLUMN_BLOCK_HEADER, |_, _| {
+            total += 2;
+            Ok(())
+        })?;
+
+        let mut txn = db.transaction();
+        let mut pending = 0;
+        let mut done = 0;
+        let mut write = |key: &[u8], is_tip: bool| -> Result<()> {
+            if is_tip {
+                txn.put(COLUMN_CHAIN_TIPS, key, &[])?;
+            } else {
+                txn.delete(COLUMN_CHAIN_TIPS, key)?;
+            }
+            pending += 1;
+            if pending == BATCH_SIZE {
+                txn.commit()?;
+                txn = db.transaction();
+                done += pending as u64;
+                pending = 0;
+                progress(done, total);
+            }
+            Ok(())
+        };
+        db.traverse(COLUMN_BLOCK_HEADER, |hash, _| write(hash, true))?;
+        db.traverse(COLUMN_BLOCK_HEADER, |_, value| {
+            let header = packed::HeaderViewReader::from_slice_should_be_ok(value);
+            write(header.data().raw().parent_hash().as_slice(), false)
+        })?;
+        txn.commit()?;
+        progress(done + pending as u64, total);
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 use crate::cache::StoreCache;
 use crate::{
-    COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_HEADER,
-    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET, COLUMN_EPOCH, COLUMN_INDEX,
-    COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
-    META_TIP_HEADER_KEY,
+    COLUMN_BLOCK_BODY, COLUMN_BLOCK_CHILDREN, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT,
+    COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET,
+    COLUMN_CELL_UNDO, COLUMN_CHAIN_TIPS, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META,
+    COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY, META_TIP_HEADER_KEY,
 };
 use ckb_chain_spec::consensus::Consensus;
 use ckb_db::{iter::DBIteratorItem, Col, Direction};
@@ -16,6 +16,7 @@ use ckb_types::{
     packed,
     prelude::*,
 };
+use std::collections::HashSet;
 
 pub trait ChainStore<'a>: Send + Sync {
     type Vector: AsRef<[u8]>;
@@ -29,17 +30,16 @@ pub trait ChainStore<'a>: Send + Sync {
     ) -> Box<dyn Iterator<Item = DBIteratorItem> + 'i>;
 
     /// Get block by block header hash
+    ///
+    /// Returns `None` for the blocks below a snapshot checkpoint, which only keep their headers.
     fn get_block(&'a self, h: &packed::Byte32) -> Option<BlockView> {
-        self.get_block_header(h).map(|header| {
-            let body = self.get_block_body(h);
-            let uncles = self
-                .get_block_uncles(h)
-                .expect("block uncles must be stored");
-            let proposals = self
-                .get_block_proposal_txs_ids(h)
-                .expect("block proposal_ids must be stored");
-            BlockView::new_unchecked(header, uncles, body, proposals)
-        })
+        let header = self.get_block_header(h)?;
+        let uncles = self.get_block_uncles(h)?;
+        let proposals = self
+            .get_block_proposal_txs_ids(h)
+            .expect("block proposal_ids must be stored");
+        let body = self.get_block_body(h);
+        Some(BlockView::new_unchecked(header, uncles, body, proposals))
     }
 
     /// Get header by block header hash
@@ -64,6 +64,20 @@ pub trait ChainStore<'a>: Send + Sync {
         }
     }
 
+    /// Get the headers of all the stored blocks which are not on the main chain
+    fn get_fork_block_headers(&'a self) -> Vec<HeaderView> {
+        self.get_iter(COLUMN_BLOCK_HEADER, &[], Direction::Forward)
+            .filter(|(key, _)| {
+                let hash = packed::Byte32Reader::from_slice_should_be_ok(&key[..]).to_entity();
+                !self.is_main_chain(&hash)
+            })
+            .map(|(_, value)| {
+                let reader = packed::HeaderViewReader::from_slice_should_be_ok(&value[..]);
+                Unpack::<HeaderView>::unpack(&reader)
+            })
+            .collect()
+    }
+
     /// Get block body by block header hash
     fn get_block_body(&'a self, hash: &packed::Byte32) -> Vec<TransactionView> {
         let prefix = hash.as_slice();
@@ -171,6 +185,24 @@ pub trait ChainStore<'a>: Send + Sync {
             })
     }
 
+    /// Get the hashes of the stored children of a block, fork blocks included
+    fn get_block_children(&'a self, hash: &packed::Byte32) -> Vec<packed::Byte32> {
+        let prefix = hash.as_slice();
+        self.get_iter(COLUMN_BLOCK_CHILDREN, prefix, Direction::Forward)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| {
+                packed::Byte32Reader::from_slice_should_be_ok(&key[prefix.len()..]).to_entity()
+            })
+            .collect()
+    }
+
+    /// Get the hashes of the stored blocks without a stored child, the main chain tip included
+    fn get_chain_tips(&'a self) -> Vec<packed::Byte32> {
+        self.get_iter(COLUMN_CHAIN_TIPS, &[], Direction::Forward)
+            .map(|(key, _)| packed::Byte32Reader::from_slice_should_be_ok(&key[..]).to_entity())
+            .collect()
+    }
+
     /// Get block header hash by block number
     fn get_block_hash(&'a self, number: BlockNumber) -> Option<packed::Byte32> {
         let block_number: packed::Uint64 = number.pack();
@@ -242,6 +274,48 @@ pub trait ChainStore<'a>: Send + Sync {
         })
     }
 
+    /// Get the cell set undo data of a main chain block: the metas spent or removed by the
+    /// block, as they were before the block was attached. `None` if the record is pruned or the
+    /// block was attached before the undo data was introduced.
+    fn get_block_undo(
+        &'a self,
+        block_hash: &packed::Byte32,
+    ) -> Option<Vec<(packed::Byte32, TransactionMeta)>> {
+        match self.get(COLUMN_CELL_UNDO, block_hash.as_slice()) {
+            Some(slice) => Some(
+                packed::BytesVecReader::from_slice_should_be_ok(&slice.as_ref())
+                    .iter()
+                    .map(|item| {
+                        // tx hash followed by the packed meta
+                        let (tx_hash, meta) = item.raw_data().split_at(32);
+                        (
+                            packed::Byte32Reader::from_slice_should_be_ok(tx_hash).to_entity(),
+                            packed::TransactionMetaReader::from_slice_should_be_ok(meta).unpack(),
+                        )
+                    })
+                    .collect(),
+            ),
+            // empty records are not written, the undo data is empty if the block spends no cell
+            // from before it
+            None => {
+                let block = self.get_block(block_hash)?;
+                let tx_hashes: HashSet<packed::Byte32> =
+                    block.tx_hashes().iter().cloned().collect();
+                let spends_earlier_cells = block
+                    .transactions()
+                    .iter()
+                    .skip(1)
+                    .flat_map(|tx| tx.input_pts_iter())
+                    .any(|out_point| !tx_hashes.contains(&out_point.tx_hash()));
+                if spends_earlier_cells {
+                    None
+                } else {
+                    Some(Vec::new())
+                }
+            }
+        }
+    }
+
     fn get_cell_meta(&'a self, tx_hash: &packed::Byte32, index: u32) -> Option<CellMeta> {
         self.get_transaction_info_packed(&tx_hash)
             .and_then(|tx_info| {
@@ -1,10 +1,10 @@
 use crate::cache::StoreCache;
 use crate::store::ChainStore;
 use crate::{
-    COLUMN_BLOCK_BODY, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT, COLUMN_BLOCK_HEADER,
-    COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET, COLUMN_EPOCH, COLUMN_INDEX,
-    COLUMN_META, COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY,
-    META_TIP_HEADER_KEY,
+    COLUMN_BLOCK_BODY, COLUMN_BLOCK_CHILDREN, COLUMN_BLOCK_EPOCH, COLUMN_BLOCK_EXT,
+    COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS, COLUMN_BLOCK_UNCLE, COLUMN_CELL_SET,
+    COLUMN_CELL_UNDO, COLUMN_CHAIN_TIPS, COLUMN_EPOCH, COLUMN_INDEX, COLUMN_META,
+    COLUMN_TRANSACTION_INFO, COLUMN_UNCLES, META_CURRENT_EPOCH_KEY, META_TIP_HEADER_KEY,
 };
 use ckb_db::{
     iter::{DBIterator, DBIteratorItem},
@@ -12,15 +12,18 @@ use ckb_db::{
 };
 use ckb_error::Error;
 use ckb_types::{
-    core::{BlockExt, BlockView, EpochExt, HeaderView},
+    bytes::Bytes,
+    core::{BlockExt, BlockNumber, BlockView, EpochExt, HeaderView, TransactionView},
     packed,
     prelude::*,
 };
+use std::collections::HashSet;
 use std::sync::Arc;
 
 pub struct StoreTransaction {
     pub(crate) inner: RocksDBTransaction,
     pub(crate) cache: Arc<StoreCache>,
+    pub(crate) cell_undo_depth: BlockNumber,
 }
 
 impl<'a> ChainStore<'a> for StoreTransaction {
@@ -116,6 +119,7 @@ impl StoreTransaction {
         let uncles = block.uncles().pack();
         let proposals = block.data().proposals();
         self.insert_raw(COLUMN_BLOCK_HEADER, hash.as_slice(), header.as_slice())?;
+        self.insert_block_child(&block.parent_hash(), &hash)?;
         self.insert_raw(COLUMN_BLOCK_UNCLE, hash.as_slice(), uncles.as_slice())?;
         self.insert_raw(
             COLUMN_BLOCK_PROPOSAL_IDS,
@@ -133,6 +137,62 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Insert a header without its block body, used for the blocks below a snapshot checkpoint
+    pub fn insert_header(&self, header: &HeaderView) -> Result<(), Error> {
+        let hash = header.hash();
+        self.insert_raw(
+            COLUMN_BLOCK_HEADER,
+            hash.as_slice(),
+            header.pack().as_slice(),
+        )?;
+        self.insert_block_child(&header.parent_hash(), &hash)
+    }
+
+    // The children of a block are indexed by the parent hash followed by the child hash. A new
+    // block replaces its parent in the chain tips, unless it already has a stored child.
+    fn insert_block_child(
+        &self,
+        parent_hash: &packed::Byte32,
+        hash: &packed::Byte32,
+    ) -> Result<(), Error> {
+        let key = [parent_hash.as_slice(), hash.as_slice()].concat();
+        self.insert_raw(COLUMN_BLOCK_CHILDREN, &key, &[])?;
+        self.delete(COLUMN_CHAIN_TIPS, parent_hash.as_slice())?;
+        if self.get_block_children(hash).is_empty() {
+            self.insert_raw(COLUMN_CHAIN_TIPS, hash.as_slice(), &[])?;
+        }
+        Ok(())
+    }
+
+    /// Insert the main chain index of a block whose body may not be stored
+    pub fn insert_block_index(
+        &self,
+        block_hash: &packed::Byte32,
+        number: BlockNumber,
+    ) -> Result<(), Error> {
+        let block_number: packed::Uint64 = number.pack();
+        self.insert_raw(COLUMN_INDEX, block_number.as_slice(), block_hash.as_slice())?;
+        self.insert_raw(COLUMN_INDEX, block_hash.as_slice(), block_number.as_slice())
+    }
+
+    /// Insert a single committed transaction and its info without the rest of its block
+    pub fn insert_transaction(
+        &self,
+        info: &packed::TransactionInfo,
+        tx: &TransactionView,
+    ) -> Result<(), Error> {
+        self.insert_raw(
+            COLUMN_BLOCK_BODY,
+            info.key().as_slice(),
+            tx.pack().as_slice(),
+        )?;
+        self.insert_raw(
+            COLUMN_TRANSACTION_INFO,
+            tx.hash().as_slice(),
+            info.as_slice(),
+        )
+    }
+
     pub fn insert_block_ext(
         &self,
         block_hash: &packed::Byte32,
@@ -145,6 +205,8 @@ impl StoreTransaction {
         )
     }
 
+    /// Attaches the block to the main chain. It records the cell set undo data of the block, so
+    /// it must be called before the block updates the cell set.
     pub fn attach_block(&self, block: &BlockView) -> Result<(), Error> {
         let header = block.data().header();
         let block_hash = block.hash();
@@ -169,6 +231,7 @@ impl StoreTransaction {
                 &uncle.header().pack().as_slice(),
             )?;
         }
+        self.insert_block_undo(block)?;
         self.insert_raw(COLUMN_INDEX, block_hash.as_slice(), block_number.as_slice())
     }
 
@@ -217,4 +280,45 @@ impl StoreTransaction {
     pub fn delete_cell_set(&self, tx_hash: &packed::Byte32) -> Result<(), Error> {
         self.delete(COLUMN_CELL_SET, tx_hash.as_slice())
     }
+
+    // Records the metas which the block spends or removes, as they are before the block, and
+    // prunes the record which falls out of the undo depth. The blocks which spend no cell from
+    // before them have no record.
+    fn insert_block_undo(&self, block: &BlockView) -> Result<(), Error> {
+        let mut items: Vec<packed::Bytes> = Vec::new();
+        // the txs whose meta is already recorded or is created by the block
+        let mut recorded = HashSet::new();
+        for tx in block.transactions() {
+            for cell in tx.input_pts_iter() {
+                let tx_hash = cell.tx_hash();
+                if recorded.contains(&tx_hash) {
+                    continue;
+                }
+                if let Some(meta) = self.get(COLUMN_CELL_SET, tx_hash.as_slice()) {
+                    // tx hash followed by the packed meta
+                    let raw = [tx_hash.as_slice(), meta.as_ref()].concat();
+                    items.push(Bytes::from(raw).pack());
+                    recorded.insert(tx_hash);
+                }
+            }
+            recorded.insert(tx.hash());
+        }
+        if self.cell_undo_depth > 0 && !items.is_empty() {
+            let undo = packed::BytesVec::new_builder().set(items).build();
+            self.insert_raw(COLUMN_CELL_UNDO, block.hash().as_slice(), undo.as_slice())?;
+        }
+
+        if let Some(pruned_hash) = block
+            .number()
+            .checked_sub(self.cell_undo_depth)
+            .and_then(|number| self.get_block_hash(number))
+        {
+            self.delete(COLUMN_CELL_UNDO, pruned_hash.as_slice())?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_block_undo(&self, block_hash: &packed::Byte32) -> Result<(), Error> {
+        self.delete(COLUMN_CELL_UNDO, block_hash.as_slice())
+    }
 }
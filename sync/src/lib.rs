@@ -24,7 +24,13 @@ pub const MAX_INVENTORY_LEN: usize = 50_000;
 pub const MAX_SCHEDULED_LEN: usize = 4 * 1024;
 pub const MAX_BLOCKS_TO_ANNOUNCE: usize = 8;
 pub const MAX_UNCONNECTING_HEADERS: usize = 10;
-pub const MAX_BLOCKS_IN_TRANSIT_PER_PEER: usize = 16;
+// The download window of each peer starts from INIT_BLOCKS_IN_TRANSIT_PER_PEER and is adjusted
+// between MIN_BLOCKS_IN_TRANSIT_PER_PEER and MAX_BLOCKS_IN_TRANSIT_PER_PEER by the response time
+pub const INIT_BLOCKS_IN_TRANSIT_PER_PEER: usize = 16;
+pub const MIN_BLOCKS_IN_TRANSIT_PER_PEER: usize = 4;
+pub const MAX_BLOCKS_IN_TRANSIT_PER_PEER: usize = 128;
+// The maximum number of blocks requested or served in one GetBlocks message
+pub const MAX_BLOCKS_PER_GET_BLOCKS: usize = 16;
 pub const MAX_TIP_AGE: u64 = 24 * 60 * 60 * 1000;
 pub const STALE_RELAY_AGE_LIMIT: u64 = 30 * 24 * 60 * 60 * 1000;
 pub const PER_FETCH_BLOCK_LIMIT: usize = 128;
@@ -64,6 +70,11 @@ pub const MAX_LOCATOR_SIZE: usize = 101;
 
 pub const BLOCK_DOWNLOAD_TIMEOUT: u64 = 30 * 1000; // 30s
 
+// The download window grows when the average block response time is below FAST, and shrinks
+// when it is above SLOW
+pub const FAST_BLOCK_RESPONSE_TIME: u64 = 2 * 1000; // 2s
+pub const SLOW_BLOCK_RESPONSE_TIME: u64 = 10 * 1000; // 10s
+
 pub const RETRY_ASK_TX_TIMEOUT_INCREASE: Duration = Duration::from_secs(30);
 
 // ban time
@@ -80,6 +80,14 @@ impl Relayer {
         &self.shared
     }
 
+    // Relayed transactions are committed in the blocks after the tip
+    fn script_hash_type_data1_enabled(&self) -> bool {
+        let tip_epoch = self.shared.snapshot().tip_header().epoch().number();
+        self.shared
+            .consensus()
+            .script_hash_type_data1_enabled(tip_epoch + 1)
+    }
+
     fn try_process<'r>(
         &self,
         nc: Arc<dyn CKBProtocolContext + Sync>,
@@ -91,7 +99,7 @@ impl Relayer {
                 CompactBlockProcess::new(reader, self, nc, peer).execute()?;
             }
             packed::RelayMessageUnionReader::RelayTransactions(reader) => {
-                if reader.check_data() {
+                if reader.check_data_with(self.script_hash_type_data1_enabled()) {
                     TransactionsProcess::new(reader, self, nc, peer).execute()?;
                 } else {
                     return Err(err_msg("RelayTransactions: invalid data"));
@@ -107,7 +115,7 @@ impl Relayer {
                 GetBlockTransactionsProcess::new(reader, self, nc, peer).execute()?;
             }
             packed::RelayMessageUnionReader::BlockTransactions(reader) => {
-                if reader.check_data() {
+                if reader.check_data_with(self.script_hash_type_data1_enabled()) {
                     BlockTransactionsProcess::new(reader, self, nc, peer).execute()?;
                 } else {
                     return Err(err_msg("BlockTransactions: invalid data"));
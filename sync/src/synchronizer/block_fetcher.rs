@@ -1,7 +1,7 @@
 use crate::block_status::BlockStatus;
 use crate::synchronizer::Synchronizer;
 use crate::types::{HeaderView, SyncSnapshot};
-use crate::{MAX_BLOCKS_IN_TRANSIT_PER_PEER, PER_FETCH_BLOCK_LIMIT};
+use crate::PER_FETCH_BLOCK_LIMIT;
 use ckb_logger::{debug, trace};
 use ckb_network::PeerIndex;
 use ckb_store::ChainStore;
@@ -27,7 +27,7 @@ impl BlockFetcher {
         let inflight = self.synchronizer.shared().state().read_inflight_blocks();
 
         // Can't download any more from this peer
-        inflight.peer_inflight_count(self.peer) >= MAX_BLOCKS_IN_TRANSIT_PER_PEER
+        inflight.peer_inflight_count(self.peer) >= inflight.peer_window(self.peer)
     }
 
     pub fn is_better_chain(&self, header: &HeaderView) -> bool {
@@ -120,7 +120,8 @@ impl BlockFetcher {
         {
             let mut inflight = self.synchronizer.shared().state().write_inflight_blocks();
             let count = min(
-                MAX_BLOCKS_IN_TRANSIT_PER_PEER
+                inflight
+                    .peer_window(self.peer)
                     .saturating_sub(inflight.peer_inflight_count(self.peer)),
                 PER_FETCH_BLOCK_LIMIT,
             );
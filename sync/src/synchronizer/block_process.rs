@@ -42,7 +42,7 @@ impl<'a> BlockProcess<'a> {
             .synchronizer
             .shared()
             .state()
-            .new_block_received(&block)
+            .new_block_received(self.peer, &block)
             && self
                 .synchronizer
                 .process_new_block(&snapshot, self.peer, block.clone())
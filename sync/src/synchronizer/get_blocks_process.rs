@@ -1,6 +1,6 @@
 use crate::block_status::BlockStatus;
 use crate::synchronizer::Synchronizer;
-use crate::MAX_BLOCKS_IN_TRANSIT_PER_PEER;
+use crate::MAX_BLOCKS_PER_GET_BLOCKS;
 use ckb_logger::{debug, warn};
 use ckb_network::{CKBProtocolContext, PeerIndex};
 use ckb_types::{packed, prelude::*};
@@ -33,7 +33,7 @@ impl<'a> GetBlocksProcess<'a> {
         let block_hashes = self.message.block_hashes();
         let snapshot = self.synchronizer.shared.snapshot();
 
-        let n_limit = min(MAX_BLOCKS_IN_TRANSIT_PER_PEER as usize, block_hashes.len());
+        let n_limit = min(MAX_BLOCKS_PER_GET_BLOCKS, block_hashes.len());
         for block_hash in block_hashes.iter().take(n_limit) {
             debug!("get_blocks {} from peer {:?}", block_hash, self.peer);
             let block_hash = block_hash.to_entity();
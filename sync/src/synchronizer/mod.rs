@@ -15,8 +15,8 @@ use crate::block_status::BlockStatus;
 use crate::types::{HeaderView, PeerFlags, Peers, SyncSharedState, SyncSnapshot};
 use crate::{
     BAD_MESSAGE_BAN_TIME, CHAIN_SYNC_TIMEOUT, EVICTION_HEADERS_RESPONSE_TIME,
-    HEADERS_DOWNLOAD_TIMEOUT_BASE, HEADERS_DOWNLOAD_TIMEOUT_PER_HEADER, MAX_HEADERS_LEN,
-    MAX_OUTBOUND_PEERS_TO_PROTECT_FROM_DISCONNECT, POW_SPACE,
+    HEADERS_DOWNLOAD_TIMEOUT_BASE, HEADERS_DOWNLOAD_TIMEOUT_PER_HEADER, MAX_BLOCKS_PER_GET_BLOCKS,
+    MAX_HEADERS_LEN, MAX_OUTBOUND_PEERS_TO_PROTECT_FROM_DISCONNECT, POW_SPACE,
 };
 use ckb_chain::chain::ChainController;
 use ckb_logger::{debug, info, trace};
@@ -72,7 +72,16 @@ impl Synchronizer {
                 GetBlocksProcess::new(reader, self, peer, nc).execute()?;
             }
             packed::SyncMessageUnionReader::SendBlock(reader) => {
-                if reader.check_data() {
+                // The epoch is verified with the header later, it only decides which values
+                // are well-formed here
+                let epoch = core::EpochNumberWithFraction::from_full_value(
+                    reader.block().header().raw().epoch().unpack(),
+                );
+                let data1_enabled = self
+                    .shared
+                    .consensus()
+                    .script_hash_type_data1_enabled(epoch.number());
+                if reader.check_data_with(data1_enabled) {
                     BlockProcess::new(reader, self, peer, nc).execute()?;
                 } else {
                     return Err(err_msg("SendBlock: invalid data"));
@@ -366,14 +375,18 @@ impl Synchronizer {
         nc: &dyn CKBProtocolContext,
         peer: PeerIndex,
     ) {
-        let content = packed::GetBlocks::new_builder()
-            .block_hashes(v_fetch.clone().pack())
-            .build();
-        let message = packed::SyncMessage::new_builder().set(content).build();
-        let data = message.as_slice().into();
-        debug!("send_getblocks len={:?} to peer={}", v_fetch.len(), peer);
-        if let Err(err) = nc.send_message_to(peer, data) {
-            debug!("synchronizer send GetBlocks error: {:?}", err);
+        // peers serve at most MAX_BLOCKS_PER_GET_BLOCKS blocks for each message
+        for chunk in v_fetch.chunks(MAX_BLOCKS_PER_GET_BLOCKS) {
+            let content = packed::GetBlocks::new_builder()
+                .block_hashes(chunk.to_vec().pack())
+                .build();
+            let message = packed::SyncMessage::new_builder().set(content).build();
+            let data = message.as_slice().into();
+            debug!("send_getblocks len={:?} to peer={}", chunk.len(), peer);
+            if let Err(err) = nc.send_message_to(peer, data) {
+                debug!("synchronizer send GetBlocks error: {:?}", err);
+                break;
+            }
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::types::InflightBlocks;
-use crate::BLOCK_DOWNLOAD_TIMEOUT;
+use crate::{BLOCK_DOWNLOAD_TIMEOUT, INIT_BLOCKS_IN_TRANSIT_PER_PEER, SLOW_BLOCK_RESPONSE_TIME};
 use ckb_types::prelude::*;
 use ckb_types::{h256, H256};
 use std::collections::HashSet;
@@ -138,3 +138,65 @@ fn inflight_blocks_timeout() {
         Some(HashSet::from_iter(vec![1.into(), 4.into()]))
     );
 }
+
+#[cfg(not(disable_faketime))]
+#[test]
+fn inflight_blocks_download_window() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let mut inflight_blocks = InflightBlocks::default();
+    let (fast, slow) = (1.into(), 2.into());
+
+    assert_eq!(
+        inflight_blocks.peer_window(fast),
+        INIT_BLOCKS_IN_TRANSIT_PER_PEER
+    );
+
+    // fast responses grow the window
+    for i in 0..8u8 {
+        let hash = [i; 32].pack();
+        assert!(inflight_blocks.insert(fast, hash.clone()));
+        assert!(inflight_blocks.block_received(fast, hash));
+    }
+    assert_eq!(
+        inflight_blocks.peer_window(fast),
+        INIT_BLOCKS_IN_TRANSIT_PER_PEER + 8
+    );
+
+    // slow responses shrink the window
+    for i in 100..103u8 {
+        assert!(inflight_blocks.insert(slow, [i; 32].pack()));
+    }
+    faketime::write_millis(&faketime_file, SLOW_BLOCK_RESPONSE_TIME).expect("write millis");
+    for i in 100..103u8 {
+        assert!(inflight_blocks.block_received(slow, [i; 32].pack()));
+    }
+    assert_eq!(
+        inflight_blocks.peer_window(slow),
+        INIT_BLOCKS_IN_TRANSIT_PER_PEER - 3
+    );
+
+    // timeout halves the window
+    assert!(inflight_blocks.insert(slow, [200; 32].pack()));
+    faketime::write_millis(
+        &faketime_file,
+        SLOW_BLOCK_RESPONSE_TIME + BLOCK_DOWNLOAD_TIMEOUT + 1,
+    )
+    .expect("write millis");
+    inflight_blocks.prune();
+    assert_eq!(
+        inflight_blocks.peer_window(slow),
+        (INIT_BLOCKS_IN_TRANSIT_PER_PEER - 3) / 2
+    );
+    assert_eq!(
+        inflight_blocks.peer_window(fast),
+        INIT_BLOCKS_IN_TRANSIT_PER_PEER + 8
+    );
+
+    // the window is dropped with the peer
+    inflight_blocks.remove_by_peer(fast);
+    assert_eq!(
+        inflight_blocks.peer_window(fast),
+        INIT_BLOCKS_IN_TRANSIT_PER_PEER
+    );
+}
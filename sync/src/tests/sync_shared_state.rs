@@ -103,20 +103,18 @@ fn test_insert_parent_unknown_block() {
             .expect("insert orphan block"),
         false,
     );
-    assert_eq!(
-        shared
-            .snapshot()
-            .insert_new_block(&chain, PeerIndex::new(1), Arc::clone(&invalid_orphan))
-            .expect("insert orphan block"),
-        false,
-    );
+    // The orphan is checked when it arrives
+    assert!(shared
+        .snapshot()
+        .insert_new_block(&chain, PeerIndex::new(1), Arc::clone(&invalid_orphan))
+        .is_err());
     assert_eq!(
         shared.snapshot().get_block_status(&valid_hash),
         BlockStatus::BLOCK_RECEIVED
     );
     assert_eq!(
         shared.snapshot().get_block_status(&invalid_hash),
-        BlockStatus::BLOCK_RECEIVED
+        BlockStatus::BLOCK_INVALID
     );
 
     // After inserting parent of an orphan block
@@ -1,10 +1,16 @@
 use crate::block_status::BlockStatus;
 use crate::orphan_block_pool::OrphanBlockPool;
-use crate::BLOCK_DOWNLOAD_TIMEOUT;
 use crate::MAX_PEERS_PER_BLOCK;
 use crate::{NetworkProtocol, SUSPEND_SYNC_TIME};
+use crate::{
+    BLOCK_DOWNLOAD_TIMEOUT, FAST_BLOCK_RESPONSE_TIME, INIT_BLOCKS_IN_TRANSIT_PER_PEER,
+    MAX_BLOCKS_IN_TRANSIT_PER_PEER, MIN_BLOCKS_IN_TRANSIT_PER_PEER, SLOW_BLOCK_RESPONSE_TIME,
+};
 use crate::{MAX_HEADERS_LEN, MAX_TIP_AGE, RETRY_ASK_TX_TIMEOUT_INCREASE};
-use ckb_chain::chain::ChainController;
+use ckb_chain::{
+    chain::{ChainController, HeaderIndex},
+    switch::Switch,
+};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_logger::{debug, debug_target, error};
 use ckb_network::{CKBProtocolContext, PeerIndex};
@@ -18,7 +24,7 @@ use ckb_types::{
 };
 use ckb_util::{Mutex, MutexGuard};
 use ckb_util::{RwLock, RwLockReadGuard, RwLockWriteGuard};
-use ckb_verification::HeaderResolverWrapper;
+use ckb_verification::{BlockVerifier, HeaderResolverWrapper, Verifier};
 use failure::Error as FailureError;
 use faketime::unix_time_as_millis;
 use lru_cache::LruCache;
@@ -263,10 +269,55 @@ impl InflightState {
     }
 }
 
+// The number of blocks which can be in transit from a peer, adjusted by the response time
+#[derive(Debug, Clone)]
+pub struct DownloadWindow {
+    size: usize,
+    // exponential moving average of the block response time, in milliseconds
+    avg_response_time: u64,
+}
+
+impl Default for DownloadWindow {
+    fn default() -> Self {
+        DownloadWindow {
+            size: INIT_BLOCKS_IN_TRANSIT_PER_PEER,
+            avg_response_time: 0,
+        }
+    }
+}
+
+impl DownloadWindow {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn avg_response_time(&self) -> u64 {
+        self.avg_response_time
+    }
+
+    fn on_received(&mut self, response_time: u64) {
+        self.avg_response_time = if self.avg_response_time == 0 {
+            response_time
+        } else {
+            (self.avg_response_time * 7 + response_time) / 8
+        };
+        if self.avg_response_time <= FAST_BLOCK_RESPONSE_TIME {
+            self.size = cmp::min(self.size + 1, MAX_BLOCKS_IN_TRANSIT_PER_PEER);
+        } else if self.avg_response_time >= SLOW_BLOCK_RESPONSE_TIME {
+            self.size = cmp::max(self.size - 1, MIN_BLOCKS_IN_TRANSIT_PER_PEER);
+        }
+    }
+
+    fn on_timeout(&mut self) {
+        self.size = cmp::max(self.size / 2, MIN_BLOCKS_IN_TRANSIT_PER_PEER);
+    }
+}
+
 #[derive(Clone)]
 pub struct InflightBlocks {
     blocks: HashMap<PeerIndex, HashSet<Byte32>>,
     states: HashMap<Byte32, InflightState>,
+    windows: HashMap<PeerIndex, DownloadWindow>,
 }
 
 impl Default for InflightBlocks {
@@ -274,6 +325,7 @@ impl Default for InflightBlocks {
         InflightBlocks {
             blocks: HashMap::default(),
             states: HashMap::default(),
+            windows: HashMap::default(),
         }
     }
 }
@@ -319,14 +371,28 @@ impl InflightBlocks {
         self.states.get(block)
     }
 
+    // The max number of blocks in transit from the peer
+    pub fn peer_window(&self, peer: PeerIndex) -> usize {
+        self.windows
+            .get(&peer)
+            .map(DownloadWindow::size)
+            .unwrap_or(INIT_BLOCKS_IN_TRANSIT_PER_PEER)
+    }
+
+    pub fn download_window(&self, peer: PeerIndex) -> Option<&DownloadWindow> {
+        self.windows.get(&peer)
+    }
+
     pub fn prune(&mut self) {
         let now = unix_time_as_millis();
         let blocks = &mut self.blocks;
+        let windows = &mut self.windows;
         self.states.retain(|k, v| {
             let outdate = (v.timestamp + BLOCK_DOWNLOAD_TIMEOUT) < now;
             if outdate {
                 for peer in &v.peers {
                     blocks.get_mut(peer).map(|set| set.remove(k));
+                    windows.entry(*peer).or_default().on_timeout();
                 }
             }
             !outdate
@@ -350,7 +416,22 @@ impl InflightBlocks {
         ret
     }
 
+    // Removes the block received from the peer, its response time adjusts the peer window
+    pub fn block_received(&mut self, peer: PeerIndex, block: Byte32) -> bool {
+        if let Some(state) = self.states.get(&block) {
+            if state.peers.contains(&peer) {
+                let response_time = unix_time_as_millis().saturating_sub(state.timestamp);
+                self.windows
+                    .entry(peer)
+                    .or_default()
+                    .on_received(response_time);
+            }
+        }
+        self.remove_by_block(block)
+    }
+
     pub fn remove_by_peer(&mut self, peer: PeerIndex) -> bool {
+        self.windows.remove(&peer);
         self.blocks
             .remove(&peer)
             .map(|blocks| {
@@ -662,6 +743,13 @@ impl SyncSharedState {
     }
 }
 
+// Lets the chain service find the ancestors of the assume valid target among the synced headers
+impl HeaderIndex for SyncSharedState {
+    fn get_ancestor(&self, base: &Byte32, number: BlockNumber) -> Option<core::HeaderView> {
+        self.snapshot().get_ancestor(base, number)
+    }
+}
+
 impl SyncState {
     pub fn n_sync_started(&self) -> &AtomicUsize {
         &self.n_sync_started
@@ -738,6 +826,18 @@ impl SyncState {
         self.header_map.write().remove(hash);
     }
 
+    // The received headers without a received child, their blocks are not stored yet as the
+    // headers of the accepted blocks are removed from the header map
+    pub fn header_map_tips(&self) -> Vec<HeaderView> {
+        let header_map = self.header_map.read();
+        let parents: HashSet<Byte32> = header_map.values().map(HeaderView::parent_hash).collect();
+        header_map
+            .values()
+            .filter(|header| !parents.contains(&header.hash()))
+            .cloned()
+            .collect()
+    }
+
     pub(crate) fn suspend_sync(&self, peer_state: &mut PeerState) {
         peer_state.suspend_sync(SUSPEND_SYNC_TIME);
         assert_ne!(
@@ -775,8 +875,9 @@ impl SyncState {
     }
 
     // Return true when the block is that we have requested and received first time.
-    pub fn new_block_received(&self, block: &core::BlockView) -> bool {
-        self.write_inflight_blocks().remove_by_block(block.hash())
+    pub fn new_block_received(&self, peer: PeerIndex, block: &core::BlockView) -> bool {
+        self.write_inflight_blocks()
+            .block_received(peer, block.hash())
     }
 
     pub fn insert_inflight_proposals(&self, ids: Vec<packed::ProposalShortId>) -> Vec<bool> {
@@ -1170,6 +1271,15 @@ impl SyncSnapshot {
                 .is_some()
         };
 
+        // The non-contextual checks run when a block arrives, even if it is ahead of the tip, only
+        // the contextual application is serial. The orphan pool only keeps checked blocks.
+        if let Err(err) = non_contextual_verify(self.consensus(), &block) {
+            debug!("non-contextual verify block {:?} {:?}", block, err);
+            self.state
+                .insert_block_status(block.hash(), BlockStatus::BLOCK_INVALID);
+            return Err(err.into());
+        }
+
         // Insert the given block into orphan_block_pool if its parent is not found
         if !known_parent(&block) {
             debug!(
@@ -1181,17 +1291,17 @@ impl SyncSnapshot {
             return Ok(false);
         }
 
-        // Attempt to accept the given block if its parent already exist in database
+        // Attempt to accept the given block if its parent already exist in database, and then
+        // its descendant blocks in orphan pool. The returned blocks of `remove_blocks_by_parent`
+        // are in topology order by parents. The descendants stay in the pool until the given
+        // block is accepted.
         let ret = self.accept_block(chain, pi, Arc::clone(&block));
         if ret.is_err() {
             debug!("accept block {:?} {:?}", block, ret);
             return ret;
         }
 
-        // The above block has been accepted. Attempt to accept its descendant blocks in orphan pool.
-        // The returned blocks of `remove_blocks_by_parent` are in topology order by parents
-        let descendants = self.state.remove_orphan_by_parent(&block.as_ref().hash());
-        for block in descendants {
+        for block in self.state.remove_orphan_by_parent(&block.as_ref().hash()) {
             // If we can not find the block's parent in database, that means it was failed to accept
             // its parent, so we treat it as a invalid block as well.
             if !known_parent(&block) {
@@ -1223,7 +1333,8 @@ impl SyncSnapshot {
         peer: PeerIndex,
         block: Arc<core::BlockView>,
     ) -> Result<bool, FailureError> {
-        let ret = chain.process_block(Arc::clone(&block));
+        // the non-contextual checks are done by `insert_new_block`
+        let ret = chain.internal_process_block(Arc::clone(&block), Switch::DISABLE_NON_CONTEXTUAL);
         if ret.is_err() {
             error!("accept block {:?} {:?}", block, ret);
             self.state
@@ -1254,6 +1365,15 @@ impl SyncSnapshot {
     }
 }
 
+// Checks which don't depend on the chain: proposals and bytes limits, cellbase,
+// duplicated transactions and merkle roots. PoW is verified with the header.
+fn non_contextual_verify(
+    consensus: &Consensus,
+    block: &core::BlockView,
+) -> Result<(), ckb_error::Error> {
+    BlockVerifier::new(consensus).verify(block)
+}
+
 #[cfg(test)]
 mod tests {
     use super::HeaderView;
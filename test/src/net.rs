@@ -97,6 +97,7 @@ impl Net {
             dns_seeds: vec![],
             whitelist_peers: vec![],
             whitelist_only: false,
+            private_network: false,
             max_peers: self.num_nodes(),
             max_outbound_peers: self.num_nodes(),
             path: self.working_dir().into(),
@@ -107,6 +108,9 @@ impl Net {
             upnp: false,
             bootnode_mode: false,
             max_send_buffer: None,
+            compression: Default::default(),
+            proxy: None,
+            hide_listen_addresses: false,
         };
 
         let network_state =
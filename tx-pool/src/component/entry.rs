@@ -1,5 +1,6 @@
 use crate::component::container::AncestorsScoreSortKey;
 use crate::component::get_transaction_virtual_bytes;
+use ckb_script::ScriptGroupCycles;
 use ckb_types::{
     core::{Capacity, Cycle, TransactionView},
     packed::{OutPoint, ProposalShortId},
@@ -46,6 +47,8 @@ pub struct TxEntry {
     pub transaction: TransactionView,
     /// Cycles
     pub cycles: Cycle,
+    /// Cycles of each script group
+    pub group_cycles: Vec<ScriptGroupCycles>,
     /// tx size
     pub size: usize,
     /// fee
@@ -74,6 +77,7 @@ impl TxEntry {
         TxEntry {
             transaction: tx,
             cycles,
+            group_cycles: Vec::new(),
             size,
             fee,
             ancestors_size: size,
@@ -84,6 +88,29 @@ impl TxEntry {
         }
     }
 
+    /// Create new transaction pool entry with the verification result
+    pub fn from_cache_entry(
+        tx: TransactionView,
+        cache_entry: CacheEntry,
+        size: usize,
+        related_out_points: Vec<OutPoint>,
+    ) -> Self {
+        let mut entry = TxEntry::new(
+            tx,
+            cache_entry.cycles,
+            cache_entry.fee,
+            size,
+            related_out_points,
+        );
+        entry.group_cycles = cache_entry.group_cycles;
+        entry
+    }
+
+    /// The verification result, used to add the transaction again without running scripts
+    pub fn to_cache_entry(&self) -> CacheEntry {
+        CacheEntry::new(self.cycles, self.fee, self.group_cycles.clone())
+    }
+
     pub fn as_sorted_key(&self) -> AncestorsScoreSortKey {
         AncestorsScoreSortKey::from(self)
     }
@@ -16,12 +16,25 @@ pub struct TxPoolConfig {
     pub max_cycles: Cycle,
     // tx verify cache capacity
     pub max_verify_cache_size: usize,
+    // script group verify cache capacity, 0 means disabled
+    #[serde(default)]
+    pub max_script_verify_cache_size: usize,
     // conflict tx cache capacity
     pub max_conflict_cache_size: usize,
     // committed transactions hash cache capacity
     pub max_committed_txs_hash_cache_size: usize,
     // txs with lower fee rate than this will not be relayed or be mined
     pub min_fee_rate: FeeRate,
+    // verify scripts in slices of <verify_slice_cycles> cycles, 0 means verify in one go
+    #[serde(default)]
+    pub verify_slice_cycles: Cycle,
+    // txs consuming more than <max_tx_cycles> cycles will not be accepted, 0 means disabled
+    #[serde(default)]
+    pub max_tx_cycles: Cycle,
+    // txs with a script group consuming more than <max_script_group_cycles> cycles will not be
+    // accepted, 0 means disabled
+    #[serde(default)]
+    pub max_script_group_cycles: Cycle,
 }
 
 impl Default for TxPoolConfig {
@@ -30,9 +43,13 @@ impl Default for TxPoolConfig {
             max_mem_size: 20_000_000, // 20mb
             max_cycles: 200_000_000_000,
             max_verify_cache_size: 100_000,
+            max_script_verify_cache_size: 100_000,
             max_conflict_cache_size: 1_000,
             max_committed_txs_hash_cache_size: 100_000,
             min_fee_rate: DEFAULT_MIN_FEE_RATE,
+            verify_slice_cycles: 20_000_000,
+            max_tx_cycles: 0,
+            max_script_group_cycles: 0,
         }
     }
 }
@@ -1,4 +1,5 @@
 use ckb_error::{Error, ErrorKind};
+use ckb_types::{core::Cycle, packed::Byte32};
 use failure::Fail;
 
 #[derive(Debug, PartialEq, Clone, Eq, Fail)]
@@ -6,6 +7,12 @@ pub enum SubmitTxError {
     /// The fee rate of transaction is lower than min fee rate
     #[fail(display = "LowFeeRate")]
     LowFeeRate,
+    /// The cycles of transaction exceed `max_tx_cycles`
+    #[fail(display = "ExceededMaximumCycles {} > {}", _0, _1)]
+    ExceededMaximumCycles(Cycle, Cycle),
+    /// The cycles of a script group exceed `max_script_group_cycles`
+    #[fail(display = "ExceededMaximumScriptGroupCycles {}: {} > {}", _0, _1, _2)]
+    ExceededMaximumScriptGroupCycles(Byte32, Cycle, Cycle),
 }
 
 impl From<SubmitTxError> for Error {
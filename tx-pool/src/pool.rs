@@ -4,10 +4,12 @@ use crate::component::orphan::OrphanPool;
 use crate::component::pending::PendingQueue;
 use crate::component::proposed::ProposedPool;
 use crate::config::TxPoolConfig;
+use crate::error::SubmitTxError;
 use ckb_dao::DaoCalculator;
 use ckb_error::{Error, ErrorKind, InternalErrorKind};
 use ckb_fee_estimator::Estimator as FeeEstimator;
 use ckb_logger::{debug_target, error_target, trace_target};
+use ckb_script::ScriptVerifyCache;
 use ckb_snapshot::Snapshot;
 use ckb_store::ChainStore;
 use ckb_types::{
@@ -52,6 +54,8 @@ pub struct TxPool {
     pub(crate) total_tx_cycles: Cycle,
     // tx fee estimator
     pub(crate) fee_estimator: FeeEstimator,
+    // cache of script group verification results, shared with block verification
+    pub(crate) script_verify_cache: Arc<ScriptVerifyCache>,
     pub snapshot: Arc<Snapshot>,
 }
 
@@ -70,6 +74,7 @@ impl TxPool {
         config: TxPoolConfig,
         snapshot: Arc<Snapshot>,
         last_txs_updated_at: Arc<AtomicU64>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
     ) -> TxPool {
         let conflict_cache_size = config.max_conflict_cache_size;
         let committed_txs_hash_cache_size = config.max_committed_txs_hash_cache_size;
@@ -87,6 +92,7 @@ impl TxPool {
             total_tx_cycles: 0,
             snapshot,
             fee_estimator: FeeEstimator::default(),
+            script_verify_cache,
         }
     }
 
@@ -346,7 +352,7 @@ impl TxPool {
     pub(crate) fn verify_rtx(
         &self,
         rtx: &ResolvedTransaction,
-        cache_entry: Option<CacheEntry>,
+        cache_entry: Option<&CacheEntry>,
     ) -> Result<CacheEntry, Error> {
         let snapshot = self.snapshot();
         let tip_header = snapshot.tip_header();
@@ -365,7 +371,8 @@ impl TxPool {
                     consensus,
                 )
                 .verify()?;
-                Ok(cache_entry)
+                self.check_cycles_policy(cache_entry)?;
+                Ok(cache_entry.clone())
             }
             None => {
                 let max_cycles = consensus.max_block_cycles();
@@ -377,25 +384,50 @@ impl TxPool {
                     tip_header.hash(),
                     consensus,
                     snapshot,
+                    Arc::clone(&self.script_verify_cache),
                 )
                 .verify(max_cycles)?;
+                self.check_cycles_policy(&cache_entry)?;
                 Ok(cache_entry)
             }
         }
     }
 
+    // Pool policies on the cycles of a valid transaction, the rejected transaction can still
+    // be committed by other miners
+    pub(crate) fn check_cycles_policy(&self, cache_entry: &CacheEntry) -> Result<(), Error> {
+        let max_tx_cycles = self.config.max_tx_cycles;
+        if max_tx_cycles != 0 && cache_entry.cycles > max_tx_cycles {
+            return Err(
+                SubmitTxError::ExceededMaximumCycles(cache_entry.cycles, max_tx_cycles).into(),
+            );
+        }
+        let max_script_group_cycles = self.config.max_script_group_cycles;
+        if max_script_group_cycles != 0 {
+            if let Some(group) = cache_entry.max_group_cycles() {
+                if group.cycles > max_script_group_cycles {
+                    return Err(SubmitTxError::ExceededMaximumScriptGroupCycles(
+                        group.script_hash.clone(),
+                        group.cycles,
+                        max_script_group_cycles,
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
     // remove resolved tx from orphan pool
     pub(crate) fn try_proposed_orphan_by_ancestor(&mut self, tx: &TransactionView) {
         let entries = self.orphan.remove_by_ancestor(tx);
         for entry in entries {
             let tx_hash = entry.transaction.hash().to_owned();
+            let cycles = entry.cache_entry.as_ref().map(|c| c.cycles).unwrap_or(0);
             if self.contains_proposed(&entry.transaction.proposal_short_id()) {
                 let ret = self.proposed_tx(entry.cache_entry, entry.size, entry.transaction);
                 if ret.is_err() {
-                    self.update_statics_for_remove_tx(
-                        entry.size,
-                        entry.cache_entry.map(|c| c.cycles).unwrap_or(0),
-                    );
+                    self.update_statics_for_remove_tx(entry.size, cycles);
                     trace_target!(
                         crate::LOG_TARGET_TX_POOL,
                         "proposed tx {} failed {:?}",
@@ -406,10 +438,7 @@ impl TxPool {
             } else {
                 let ret = self.pending_tx(entry.cache_entry, entry.size, entry.transaction);
                 if ret.is_err() {
-                    self.update_statics_for_remove_tx(
-                        entry.size,
-                        entry.cache_entry.map(|c| c.cycles).unwrap_or(0),
-                    );
+                    self.update_statics_for_remove_tx(entry.size, cycles);
                     trace_target!(
                         crate::LOG_TARGET_TX_POOL,
                         "pending tx {} failed {:?}",
@@ -451,8 +480,7 @@ impl TxPool {
     where
         F: FnOnce(
             &mut TxPool,
-            Cycle,
-            Capacity,
+            CacheEntry,
             usize,
             Vec<OutPoint>,
             TransactionView,
@@ -463,23 +491,14 @@ impl TxPool {
 
         match tx_resolved_result {
             Ok((cache_entry, related_dep_out_points)) => {
-                add_to_pool(
-                    self,
-                    cache_entry.cycles,
-                    cache_entry.fee,
-                    size,
-                    related_dep_out_points,
-                    tx,
-                )?;
+                add_to_pool(self, cache_entry.clone(), size, related_dep_out_points, tx)?;
                 Ok(cache_entry)
             }
             Err(err) => {
+                let cycles = cache_entry.as_ref().map(|c| c.cycles).unwrap_or(0);
                 match err.kind() {
                     ErrorKind::Transaction => {
-                        self.update_statics_for_remove_tx(
-                            size,
-                            cache_entry.map(|c| c.cycles).unwrap_or(0),
-                        );
+                        self.update_statics_for_remove_tx(size, cycles);
                         debug_target!(
                             crate::LOG_TARGET_TX_POOL,
                             "Failed to add tx to {} {}, verify failed, reason: {:?}",
@@ -499,10 +518,7 @@ impl TxPool {
                                     .insert(short_id, DefectEntry::new(tx, 0, cache_entry, size))
                                     .is_some()
                                 {
-                                    self.update_statics_for_remove_tx(
-                                        size,
-                                        cache_entry.map(|c| c.cycles).unwrap_or(0),
-                                    );
+                                    self.update_statics_for_remove_tx(size, cycles);
                                 }
                             }
 
@@ -511,10 +527,7 @@ impl TxPool {
                                     .add_orphan(cache_entry, size, tx, out_points.to_owned())
                                     .is_some()
                                 {
-                                    self.update_statics_for_remove_tx(
-                                        size,
-                                        cache_entry.map(|c| c.cycles).unwrap_or(0),
-                                    );
+                                    self.update_statics_for_remove_tx(size, cycles);
                                 }
                             }
 
@@ -529,10 +542,7 @@ impl TxPool {
                             | OutPointError::InvalidHeader(_)
                             | OutPointError::InvalidDepGroup(_)
                             | OutPointError::OutOfOrder(_) => {
-                                self.update_statics_for_remove_tx(
-                                    size,
-                                    cache_entry.map(|c| c.cycles).unwrap_or(0),
-                                );
+                                self.update_statics_for_remove_tx(size, cycles);
                             }
                         }
                     }
@@ -544,10 +554,7 @@ impl TxPool {
                             tx_hash,
                             err
                         );
-                        self.update_statics_for_remove_tx(
-                            size,
-                            cache_entry.map(|c| c.cycles).unwrap_or(0),
-                        );
+                        self.update_statics_for_remove_tx(size, cycles);
                     }
                 }
                 Err(err)
@@ -564,10 +571,11 @@ impl TxPool {
         let tx_result = self
             .resolve_tx_from_pending_and_proposed(tx.clone())
             .and_then(|rtx| {
-                self.verify_rtx(&rtx, cache_entry).map(|cache_entry| {
-                    let related_dep_out_points = rtx.related_dep_out_points();
-                    (cache_entry, related_dep_out_points)
-                })
+                self.verify_rtx(&rtx, cache_entry.as_ref())
+                    .map(|cache_entry| {
+                        let related_dep_out_points = rtx.related_dep_out_points();
+                        (cache_entry, related_dep_out_points)
+                    })
             });
         self.handle_tx_by_resolved_result(
             "gap",
@@ -575,8 +583,9 @@ impl TxPool {
             size,
             tx,
             tx_result,
-            |tx_pool, cycles, fee, size, related_dep_out_points, tx| {
-                let entry = TxEntry::new(tx, cycles, fee, size, related_dep_out_points);
+            |tx_pool, cache_entry, size, related_dep_out_points, tx| {
+                let entry =
+                    TxEntry::from_cache_entry(tx, cache_entry, size, related_dep_out_points);
                 let tx_hash = entry.transaction.hash();
                 if tx_pool.add_gap(entry) {
                     Ok(())
@@ -596,10 +605,11 @@ impl TxPool {
         tx: TransactionView,
     ) -> Result<CacheEntry, Error> {
         let tx_result = self.resolve_tx_from_proposed(tx.clone()).and_then(|rtx| {
-            self.verify_rtx(&rtx, cache_entry).map(|cache_entry| {
-                let related_dep_out_points = rtx.related_dep_out_points();
-                (cache_entry, related_dep_out_points)
-            })
+            self.verify_rtx(&rtx, cache_entry.as_ref())
+                .map(|cache_entry| {
+                    let related_dep_out_points = rtx.related_dep_out_points();
+                    (cache_entry, related_dep_out_points)
+                })
         });
         self.handle_tx_by_resolved_result(
             "proposed",
@@ -607,8 +617,9 @@ impl TxPool {
             size,
             tx,
             tx_result,
-            |tx_pool, cycles, fee, size, related_dep_out_points, tx| {
-                let entry = TxEntry::new(tx, cycles, fee, size, related_dep_out_points);
+            |tx_pool, cache_entry, size, related_dep_out_points, tx| {
+                let entry =
+                    TxEntry::from_cache_entry(tx, cache_entry, size, related_dep_out_points);
                 tx_pool.add_proposed(entry);
                 Ok(())
             },
@@ -624,10 +635,11 @@ impl TxPool {
         let tx_result = self
             .resolve_tx_from_pending_and_proposed(tx.clone())
             .and_then(|rtx| {
-                self.verify_rtx(&rtx, cache_entry).map(|cache_entry| {
-                    let related_dep_out_points = rtx.related_dep_out_points();
-                    (cache_entry, related_dep_out_points)
-                })
+                self.verify_rtx(&rtx, cache_entry.as_ref())
+                    .map(|cache_entry| {
+                        let related_dep_out_points = rtx.related_dep_out_points();
+                        (cache_entry, related_dep_out_points)
+                    })
             });
         self.handle_tx_by_resolved_result(
             "pending",
@@ -635,8 +647,9 @@ impl TxPool {
             size,
             tx,
             tx_result,
-            |tx_pool, cycles, fee, size, related_dep_out_points, tx| {
-                let entry = TxEntry::new(tx, cycles, fee, size, related_dep_out_points);
+            |tx_pool, cache_entry, size, related_dep_out_points, tx| {
+                let entry =
+                    TxEntry::from_cache_entry(tx, cache_entry, size, related_dep_out_points);
                 let tx_hash = entry.transaction.hash();
                 if tx_pool.add_pending(entry) {
                     Ok(())
@@ -672,30 +685,31 @@ impl TxPool {
         let tx_hash = tx.hash();
         let mut readd_tx = false;
         let cache_entry = txs_verify_cache.get(&tx_hash).cloned();
+        let cached_cycles = cache_entry.as_ref().map(|c| c.cycles);
         let tx_short_id = tx.proposal_short_id();
         let tx_size = tx.data().serialized_size_in_block();
         if snapshot.proposals().contains_proposed(&tx_short_id) {
             if let Ok(new_cache_entry) = self.proposed_tx_and_descendants(cache_entry, tx_size, tx)
             {
-                if cache_entry.is_none() {
+                self.update_statics_for_add_tx(tx_size, new_cache_entry.cycles);
+                if cached_cycles.is_none() {
                     ret = Some((tx_hash.clone(), new_cache_entry));
                 }
-                self.update_statics_for_add_tx(tx_size, new_cache_entry.cycles);
                 readd_tx = true;
             }
         } else if snapshot.proposals().contains_gap(&tx_short_id) {
             if let Ok(new_cache_entry) = self.gap_tx(cache_entry, tx_size, tx) {
-                if cache_entry.is_none() {
+                if cached_cycles.is_none() {
                     ret = Some((tx_hash.clone(), new_cache_entry));
                 }
-                self.update_statics_for_add_tx(tx_size, cache_entry.map(|c| c.cycles).unwrap_or(0));
+                self.update_statics_for_add_tx(tx_size, cached_cycles.unwrap_or(0));
                 readd_tx = true;
             }
         } else if let Ok(new_cache_entry) = self.pending_tx(cache_entry, tx_size, tx) {
-            if cache_entry.is_none() {
+            if cached_cycles.is_none() {
                 ret = Some((tx_hash.clone(), new_cache_entry));
             }
-            self.update_statics_for_add_tx(tx_size, cache_entry.map(|c| c.cycles).unwrap_or(0));
+            self.update_statics_for_add_tx(tx_size, cached_cycles.unwrap_or(0));
             readd_tx = true;
         }
 
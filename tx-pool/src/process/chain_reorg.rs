@@ -133,7 +133,7 @@ pub fn update_tx_pool_for_reorg(
         if snapshot.proposals().contains_proposed(&key.id) {
             let entry = tx_pool.gap.get(&key.id).expect("exists");
             entries.push((
-                Some(CacheEntry::new(entry.cycles, entry.fee)),
+                Some(entry.to_cache_entry()),
                 entry.size,
                 entry.transaction.to_owned(),
             ));
@@ -150,14 +150,14 @@ pub fn update_tx_pool_for_reorg(
         let entry = tx_pool.pending.get(&key.id).expect("exists");
         if snapshot.proposals().contains_proposed(&key.id) {
             entries.push((
-                Some(CacheEntry::new(entry.cycles, entry.fee)),
+                Some(entry.to_cache_entry()),
                 entry.size,
                 entry.transaction.to_owned(),
             ));
             removed.push(key.id.clone());
         } else if snapshot.proposals().contains_gap(&key.id) {
             gaps.push((
-                Some(CacheEntry::new(entry.cycles, entry.fee)),
+                Some(entry.to_cache_entry()),
                 entry.size,
                 entry.transaction.to_owned(),
             ));
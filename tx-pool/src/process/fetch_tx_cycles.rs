@@ -0,0 +1,41 @@
+use crate::pool::TxPool;
+use ckb_types::packed::ProposalShortId;
+use ckb_verification::cache::CacheEntry;
+use futures::future::Future;
+use tokio::prelude::{Async, Poll};
+use tokio::sync::lock::Lock;
+
+pub struct FetchTxCyclesProcess {
+    pub tx_pool: Lock<TxPool>,
+    pub proposal_id: Option<ProposalShortId>,
+}
+
+impl FetchTxCyclesProcess {
+    pub fn new(tx_pool: Lock<TxPool>, proposal_id: ProposalShortId) -> FetchTxCyclesProcess {
+        FetchTxCyclesProcess {
+            tx_pool,
+            proposal_id: Some(proposal_id),
+        }
+    }
+}
+
+impl Future for FetchTxCyclesProcess {
+    type Item = Option<CacheEntry>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.tx_pool.poll_lock() {
+            Async::Ready(guard) => {
+                let id = self.proposal_id.take().expect("cannot poll twice");
+                let ret = guard
+                    .pending
+                    .get(&id)
+                    .or_else(|| guard.gap.get(&id))
+                    .or_else(|| guard.proposed.get(&id))
+                    .map(|entry| entry.to_cache_entry());
+                Ok(Async::Ready(ret))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
@@ -3,6 +3,7 @@ mod chain_reorg;
 mod estimate_fee_rate;
 mod estimator_process_block;
 mod estimator_track_tx;
+mod fetch_tx_cycles;
 mod fetch_tx_for_rpc;
 mod fetch_txs;
 mod fetch_txs_with_cycles;
@@ -21,6 +22,7 @@ pub use ckb_verification::txs_verify_cache::{FetchCache, UpdateCache};
 pub use estimate_fee_rate::EstimateFeeRateProcess;
 pub use estimator_process_block::EstimatorProcessBlockProcess;
 pub use estimator_track_tx::EstimatorTrackTxProcess;
+pub use fetch_tx_cycles::FetchTxCyclesProcess;
 pub use fetch_tx_for_rpc::FetchTxRPCProcess;
 pub use fetch_txs::FetchTxsProcess;
 pub use fetch_txs_with_cycles::FetchTxsWithCyclesProcess;
@@ -3,19 +3,21 @@ use crate::error::SubmitTxError;
 use crate::pool::TxPool;
 use crate::FeeRate;
 use ckb_error::{Error, InternalErrorKind};
+use ckb_script::ScriptVerifyCache;
 use ckb_snapshot::Snapshot;
 use ckb_types::{
     core::{
         cell::{
             resolve_transaction, OverlayCellProvider, ResolvedTransaction, TransactionsProvider,
         },
-        Capacity, TransactionView,
+        Capacity, Cycle, TransactionView,
     },
     packed::Byte32,
 };
-use ckb_verification::cache::CacheEntry;
+use ckb_verification::cache::{CacheEntry, TxVerifyState};
 use ckb_verification::{ContextualTransactionVerifier, TransactionVerifier};
 use futures::future::Future;
+use futures::task;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::prelude::{Async, Poll};
@@ -82,21 +84,52 @@ impl Future for PreResolveTxsProcess {
 pub struct VerifyTxsProcess {
     pub snapshot: Arc<Snapshot>,
     pub txs_verify_cache: HashMap<Byte32, CacheEntry>,
+    pub script_verify_cache: Arc<ScriptVerifyCache>,
     pub txs: Option<Vec<ResolvedTransaction>>,
+    // verification state of each transaction, updated slice by slice
+    pub states: Vec<Option<TxVerifyState>>,
+    pub verify_slice_cycles: Cycle,
 }
 
 impl VerifyTxsProcess {
     pub fn new(
         snapshot: Arc<Snapshot>,
         txs_verify_cache: HashMap<Byte32, CacheEntry>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
         txs: Vec<ResolvedTransaction>,
+        verify_slice_cycles: Cycle,
     ) -> VerifyTxsProcess {
         VerifyTxsProcess {
             snapshot,
             txs_verify_cache,
+            script_verify_cache,
+            states: vec![None; txs.len()],
             txs: Some(txs),
+            verify_slice_cycles,
         }
     }
+
+    // Verify one slice of the first unfinished transaction, return false if all are completed
+    fn verify_slice(&mut self) -> Result<bool, Error> {
+        let txs = self.txs.as_ref().expect("cannot execute twice");
+        let index = match self.states.iter().position(|state| match state {
+            Some(TxVerifyState::Completed(_)) => false,
+            _ => true,
+        }) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        let state = verify_rtx_slice(
+            &self.snapshot,
+            &txs[index],
+            &self.txs_verify_cache,
+            &self.script_verify_cache,
+            self.verify_slice_cycles,
+            self.states[index].as_ref(),
+        )?;
+        self.states[index] = Some(state);
+        Ok(true)
+    }
 }
 
 impl Future for VerifyTxsProcess {
@@ -104,13 +137,27 @@ impl Future for VerifyTxsProcess {
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        let txs = self.txs.take().expect("cannot execute twice");
+        if self.verify_slice_cycles == 0 {
+            let txs = self.txs.take().expect("cannot execute twice");
+            return Ok(Async::Ready(verify_rtxs(
+                &self.snapshot,
+                txs,
+                &self.txs_verify_cache,
+                &self.script_verify_cache,
+            )?));
+        }
 
-        Ok(Async::Ready(verify_rtxs(
-            &self.snapshot,
-            txs,
-            &self.txs_verify_cache,
-        )?))
+        if self.verify_slice()? {
+            // yield to other tasks between slices
+            task::current().notify();
+            return Ok(Async::NotReady);
+        }
+        let txs = self.txs.take().expect("cannot execute twice");
+        let entries = self.states.drain(..).map(|state| match state {
+            Some(TxVerifyState::Completed(cache_entry)) => cache_entry,
+            _ => unreachable!("all transactions are verified"),
+        });
+        Ok(Async::Ready(txs.into_iter().zip(entries).collect()))
     }
 }
 
@@ -195,15 +242,19 @@ impl<'a> SubmitTxsExecutor<'a> {
 
         let cache = txs
             .iter()
-            .map(|(tx, cycles)| (tx.transaction.hash(), *cycles))
+            .map(|(tx, cache_entry)| (tx.transaction.hash(), cache_entry.clone()))
+            .collect();
+        let cycles_vec = txs
+            .iter()
+            .map(|(_, cache_entry)| cache_entry.clone())
             .collect();
-        let cycles_vec = txs.iter().map(|(_, cycles)| *cycles).collect();
 
         for ((rtx, cache_entry), (tx_size, fee, status)) in txs.into_iter().zip(status.into_iter())
         {
             if self.tx_pool.reach_cycles_limit(cache_entry.cycles) {
                 return Err(InternalErrorKind::TransactionPoolFull.into());
             }
+            self.tx_pool.check_cycles_policy(&cache_entry)?;
 
             let min_fee = self.tx_pool.config.min_fee_rate.fee(tx_size);
             // reject txs which fee lower than min fee rate
@@ -211,11 +262,11 @@ impl<'a> SubmitTxsExecutor<'a> {
                 return Err(SubmitTxError::LowFeeRate.into());
             }
 
+            let cycles = cache_entry.cycles;
             let related_dep_out_points = rtx.related_dep_out_points();
-            let entry = TxEntry::new(
+            let entry = TxEntry::from_cache_entry(
                 rtx.transaction,
-                cache_entry.cycles,
-                fee,
+                cache_entry,
                 tx_size,
                 related_dep_out_points,
             );
@@ -235,8 +286,7 @@ impl<'a> SubmitTxsExecutor<'a> {
                 TxStatus::Gap => self.tx_pool.add_gap(entry),
                 TxStatus::Proposed => self.tx_pool.add_proposed(entry),
             } {
-                self.tx_pool
-                    .update_statics_for_add_tx(tx_size, cache_entry.cycles);
+                self.tx_pool.update_statics_for_add_tx(tx_size, cycles);
             }
         }
 
@@ -312,10 +362,58 @@ fn resolve_tx_from_pending_and_proposed<'a>(
     resolve_transaction(tx, &mut HashSet::new(), &provider, snapshot)
 }
 
+fn verify_rtx_slice(
+    snapshot: &Snapshot,
+    tx: &ResolvedTransaction,
+    txs_verify_cache: &HashMap<Byte32, CacheEntry>,
+    script_verify_cache: &Arc<ScriptVerifyCache>,
+    verify_slice_cycles: Cycle,
+    state: Option<&TxVerifyState>,
+) -> Result<TxVerifyState, Error> {
+    let tip_header = snapshot.tip_header();
+    let tip_number = tip_header.number();
+    let epoch = tip_header.epoch();
+    let consensus = snapshot.consensus();
+
+    if let Some(cache_entry) = txs_verify_cache.get(&tx.transaction.hash()) {
+        return ContextualTransactionVerifier::new(
+            &tx,
+            snapshot,
+            tip_number + 1,
+            epoch,
+            tip_header.hash(),
+            consensus,
+        )
+        .verify()
+        .map(|_| TxVerifyState::Completed(cache_entry.clone()));
+    }
+
+    let transaction_snapshot = match state {
+        Some(TxVerifyState::Suspended(transaction_snapshot)) => Some(transaction_snapshot.as_ref()),
+        _ => None,
+    };
+    TransactionVerifier::new(
+        &tx,
+        snapshot,
+        tip_number + 1,
+        epoch,
+        tip_header.hash(),
+        consensus,
+        snapshot,
+        Arc::clone(script_verify_cache),
+    )
+    .resumable_verify(
+        consensus.max_block_cycles(),
+        verify_slice_cycles,
+        transaction_snapshot,
+    )
+}
+
 fn verify_rtxs(
     snapshot: &Snapshot,
     txs: Vec<ResolvedTransaction>,
     txs_verify_cache: &HashMap<Byte32, CacheEntry>,
+    script_verify_cache: &Arc<ScriptVerifyCache>,
 ) -> Result<Vec<(ResolvedTransaction, CacheEntry)>, Error> {
     let tip_header = snapshot.tip_header();
     let tip_number = tip_header.number();
@@ -335,7 +433,7 @@ fn verify_rtxs(
                     consensus,
                 )
                 .verify()
-                .map(|_| (tx, *cache_entry))
+                .map(|_| (tx, cache_entry.clone()))
             } else {
                 TransactionVerifier::new(
                     &tx,
@@ -345,6 +443,7 @@ fn verify_rtxs(
                     tip_header.hash(),
                     consensus,
                     snapshot,
+                    Arc::clone(script_verify_cache),
                 )
                 .verify(consensus.max_block_cycles())
                 .map(|cycles| (tx, cycles))
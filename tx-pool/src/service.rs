@@ -6,16 +6,17 @@ use crate::pool::{TxPool, TxPoolInfo};
 use crate::process::{
     BlockTemplateBuilder, BlockTemplateCacheProcess, BuildCellbaseProcess, ChainReorgProcess,
     EstimateFeeRateProcess, EstimatorProcessBlockProcess, EstimatorTrackTxProcess, FetchCache,
-    FetchTxRPCProcess, FetchTxsProcess, FetchTxsWithCyclesProcess, FreshProposalsFilterProcess,
-    NewUncleProcess, PackageTxsProcess, PlugEntryProcess, PlugTarget, PreResolveTxsProcess,
-    PrepareUnclesProcess, SubmitTxsProcess, TxPoolInfoProcess, UpdateBlockTemplateCache,
-    UpdateCache, VerifyTxsProcess,
+    FetchTxCyclesProcess, FetchTxRPCProcess, FetchTxsProcess, FetchTxsWithCyclesProcess,
+    FreshProposalsFilterProcess, NewUncleProcess, PackageTxsProcess, PlugEntryProcess, PlugTarget,
+    PreResolveTxsProcess, PrepareUnclesProcess, SubmitTxsProcess, TxPoolInfoProcess,
+    UpdateBlockTemplateCache, UpdateCache, VerifyTxsProcess,
 };
 use crate::FeeRate;
 use ckb_error::{Error, InternalErrorKind};
 use ckb_future_executor::{new_executor, Executor};
 use ckb_jsonrpc_types::BlockTemplate;
 use ckb_logger::error;
+use ckb_script::ScriptVerifyCache;
 use ckb_snapshot::{Snapshot, SnapshotMgr};
 use ckb_stop_handler::{SignalSender, StopHandler};
 use ckb_types::{
@@ -87,6 +88,7 @@ pub enum Message {
     ),
     GetTxPoolInfo(Request<(), TxPoolInfo>),
     FetchTxRPC(Request<ProposalShortId, Option<(bool, TransactionView)>>),
+    FetchTxCycles(Request<ProposalShortId, Option<CacheEntry>>),
     NewUncle(Notify<UncleBlockView>),
     PlugEntry(Request<(Vec<TxEntry>, PlugTarget), ()>),
     EstimateFeeRate(Request<usize, FeeRate>),
@@ -211,6 +213,14 @@ impl TxPoolController {
         response.recv().map_err(Into::into)
     }
 
+    pub fn fetch_tx_cycles(&self, id: ProposalShortId) -> Result<Option<CacheEntry>, FailureError> {
+        let mut sender = self.sender.clone();
+        let (responder, response) = crossbeam_channel::bounded(1);
+        let request = Request::call(id, responder);
+        sender.try_send(Message::FetchTxCycles(request))?;
+        response.recv().map_err(Into::into)
+    }
+
     pub fn fetch_txs(
         &self,
         short_ids: Vec<ProposalShortId>,
@@ -277,10 +287,17 @@ impl TxPoolServiceBuilder {
         snapshot: Arc<Snapshot>,
         block_assembler_config: Option<BlockAssemblerConfig>,
         txs_verify_cache: Lock<TxVerifyCache>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
         snapshot_mgr: Arc<SnapshotMgr>,
     ) -> TxPoolServiceBuilder {
         let last_txs_updated_at = Arc::new(AtomicU64::new(0));
-        let tx_pool = TxPool::new(tx_pool_config, snapshot, Arc::clone(&last_txs_updated_at));
+        let verify_slice_cycles = tx_pool_config.verify_slice_cycles;
+        let tx_pool = TxPool::new(
+            tx_pool_config,
+            snapshot,
+            Arc::clone(&last_txs_updated_at),
+            Arc::clone(&script_verify_cache),
+        );
         let block_assembler = block_assembler_config.map(BlockAssembler::new);
 
         TxPoolServiceBuilder {
@@ -288,8 +305,10 @@ impl TxPoolServiceBuilder {
                 tx_pool,
                 block_assembler,
                 txs_verify_cache,
+                script_verify_cache,
                 last_txs_updated_at,
                 snapshot_mgr,
+                verify_slice_cycles,
             )),
         }
     }
@@ -326,8 +345,10 @@ pub struct TxPoolService {
     tx_pool: Lock<TxPool>,
     block_assembler: Option<BlockAssembler>,
     txs_verify_cache: Lock<TxVerifyCache>,
+    script_verify_cache: Arc<ScriptVerifyCache>,
     last_txs_updated_at: Arc<AtomicU64>,
     snapshot_mgr: Arc<SnapshotMgr>,
+    verify_slice_cycles: Cycle,
 }
 
 impl TxPoolService {
@@ -335,15 +356,19 @@ impl TxPoolService {
         tx_pool: TxPool,
         block_assembler: Option<BlockAssembler>,
         txs_verify_cache: Lock<TxVerifyCache>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
         last_txs_updated_at: Arc<AtomicU64>,
         snapshot_mgr: Arc<SnapshotMgr>,
+        verify_slice_cycles: Cycle,
     ) -> Self {
         Self {
             tx_pool: Lock::new(tx_pool),
             block_assembler,
             txs_verify_cache,
+            script_verify_cache,
             last_txs_updated_at,
             snapshot_mgr,
+            verify_slice_cycles,
         }
     }
 
@@ -412,6 +437,15 @@ impl TxPoolService {
                 };
                 future::ok(())
             })),
+            Message::FetchTxCycles(Request {
+                responder,
+                arguments: id,
+            }) => Box::new(self.fetch_tx_cycles(id).and_then(move |cache_entry| {
+                if let Err(e) = responder.send(cache_entry) {
+                    error!("responder send fetch_tx_cycles failed {:?}", e)
+                };
+                future::ok(())
+            })),
             Message::FetchTxs(Request {
                 responder,
                 arguments: short_ids,
@@ -592,14 +626,22 @@ impl TxPoolService {
         let keys: Vec<Byte32> = txs.iter().map(|tx| tx.hash()).collect();
         let fetched_cache = FetchCache::new(self.txs_verify_cache.clone(), keys);
         let txs_verify_cache = self.txs_verify_cache.clone();
+        let script_verify_cache = Arc::clone(&self.script_verify_cache);
         let tx_pool = self.tx_pool.clone();
+        let verify_slice_cycles = self.verify_slice_cycles;
 
         let pre_resolve = PreResolveTxsProcess::new(tx_pool.clone(), txs);
 
         pre_resolve.and_then(move |(tip_hash, snapshot, rtxs, status)| {
             fetched_cache
                 .then(move |cache| {
-                    VerifyTxsProcess::new(snapshot, cache.expect("fetched_cache never fail"), rtxs)
+                    VerifyTxsProcess::new(
+                        snapshot,
+                        cache.expect("fetched_cache never fail"),
+                        script_verify_cache,
+                        rtxs,
+                        verify_slice_cycles,
+                    )
                 })
                 .and_then(move |txs| SubmitTxsProcess::new(tx_pool, txs, tip_hash, status))
                 .map(move |(map, cache_entry)| {
@@ -631,6 +673,13 @@ impl TxPoolService {
         FetchTxRPCProcess::new(self.tx_pool.clone(), id)
     }
 
+    fn fetch_tx_cycles(
+        &self,
+        id: ProposalShortId,
+    ) -> impl Future<Item = Option<CacheEntry>, Error = ()> {
+        FetchTxCyclesProcess::new(self.tx_pool.clone(), id)
+    }
+
     fn fetch_txs(
         &self,
         short_ids: Vec<ProposalShortId>,
@@ -10,6 +10,7 @@ use std::path::{Path, PathBuf};
 
 use serde_derive::{Deserialize, Serialize};
 
+use ckb_chain::ReorgConfig;
 use ckb_chain_spec::ChainSpec;
 use ckb_db::DBConfig;
 use ckb_indexer::IndexerConfig;
@@ -23,6 +24,7 @@ use ckb_resource::Resource;
 use ckb_rpc::Config as RpcConfig;
 use ckb_store::StoreConfig;
 use ckb_tx_pool::{BlockAssemblerConfig, TxPoolConfig};
+use ckb_types::H256;
 
 use super::sentry_config::SentryConfig;
 use super::{cli, ExitCode};
@@ -52,6 +54,8 @@ pub struct CKBAppConfig {
     pub store: StoreConfig,
     pub alert_signature: Option<AlertSignatureConfig>,
     pub alert_notifier: Option<AlertNotifierConfig>,
+    #[serde(default)]
+    pub reorg: ReorgConfig,
 }
 
 // change the order of fields will break integration test, see module doc.
@@ -68,6 +72,9 @@ pub struct MinerAppConfig {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ChainConfig {
     pub spec: Resource,
+    // Overrides `assume_valid_target` of the chain spec
+    #[serde(default)]
+    pub assume_valid_target: Option<H256>,
 }
 
 impl AppConfig {
@@ -3,6 +3,7 @@ use ckb_chain_spec::consensus::Consensus;
 use ckb_jsonrpc_types::ScriptHashType;
 use ckb_miner::MinerConfig;
 use ckb_pow::PowEngine;
+use ckb_types::H256;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -18,6 +19,31 @@ pub struct ImportArgs {
     pub source: PathBuf,
 }
 
+pub struct SnapshotExportArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub target: PathBuf,
+}
+
+pub struct SnapshotImportArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub source: PathBuf,
+    pub trusted_hash: H256,
+}
+
+pub struct MigrateArgs {
+    pub config: Box<CKBAppConfig>,
+    pub check: bool,
+}
+
+pub struct ProfileScriptArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub source: PathBuf,
+    pub target: Option<PathBuf>,
+}
+
 pub struct RunArgs {
     pub config: Box<CKBAppConfig>,
     pub consensus: Consensus,
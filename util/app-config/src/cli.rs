@@ -14,7 +14,10 @@ pub const CMD_HASHES: &str = "hashes";
 pub const CMD_BLAKE256: &str = "blake256";
 pub const CMD_BLAKE160: &str = "blake160";
 pub const CMD_SECP256K1_LOCK: &str = "secp256k1-lock";
+pub const CMD_PROFILE_SCRIPT: &str = "profile-script";
 pub const CMD_RESET_DATA: &str = "reset-data";
+pub const CMD_SNAPSHOT: &str = "snapshot";
+pub const CMD_MIGRATE: &str = "migrate";
 
 pub const ARG_CONFIG_DIR: &str = "config-dir";
 pub const ARG_FORMAT: &str = "format";
@@ -44,6 +47,8 @@ pub const ARG_NETWORK: &str = "network";
 pub const ARG_NETWORK_PEER_STORE: &str = "network-peer-store";
 pub const ARG_NETWORK_SECRET_KEY: &str = "network-secret-key";
 pub const ARG_LOGS: &str = "logs";
+pub const ARG_TRUSTED_HASH: &str = "trusted-hash";
+pub const ARG_CHECK: &str = "check";
 
 const GROUP_BA: &str = "ba";
 
@@ -71,6 +76,8 @@ fn basic_app<'b>() -> App<'static, 'b> {
         .subcommand(prof())
         .subcommand(stats())
         .subcommand(reset_data())
+        .subcommand(snapshot())
+        .subcommand(migrate())
 }
 
 pub fn get_matches(version: &Version) -> ArgMatches<'static> {
@@ -212,6 +219,69 @@ fn import() -> App<'static, 'static> {
         )
 }
 
+fn migrate() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_MIGRATE)
+        .about(
+            "Runs the pending database migrations, which otherwise run when the node starts\n\
+             Example:\n\
+             ckb migrate --check",
+        )
+        .arg(
+            Arg::with_name(ARG_CHECK)
+                .long(ARG_CHECK)
+                .help("Lists the pending migrations without running them"),
+        )
+}
+
+fn snapshot() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_SNAPSHOT)
+        .about("Exports or imports the cell set snapshot for checkpointed fast sync")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(snapshot_export())
+        .subcommand(snapshot_import())
+}
+
+fn snapshot_export() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_EXPORT)
+        .about("Exports the cell set at the tip and prints its hash")
+        .arg(
+            Arg::with_name(ARG_TARGET)
+                .short("t")
+                .long(ARG_TARGET)
+                .value_name("path")
+                .required(true)
+                .index(1)
+                .help("Specifies the export target path."),
+        )
+}
+
+fn snapshot_import() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_IMPORT)
+        .about(
+            "Starts an empty database at the tip of a snapshot\n\
+             Example:\n\
+             ckb snapshot import --trusted-hash <hash> <path>",
+        )
+        .arg(
+            Arg::with_name(ARG_SOURCE)
+                .short("s")
+                .long(ARG_SOURCE)
+                .value_name("path")
+                .required(true)
+                .index(1)
+                .help("Specifies the snapshot file path."),
+        )
+        .arg(
+            Arg::with_name(ARG_TRUSTED_HASH)
+                .long(ARG_TRUSTED_HASH)
+                .value_name("hash")
+                .required(true)
+                .takes_value(true)
+                .validator(is_h256)
+                .help("The hash printed by `ckb snapshot export`, the snapshot must match it."),
+        )
+}
+
 fn cli() -> App<'static, 'static> {
     SubCommand::with_name(CMD_CLI)
         .about("CLI tools")
@@ -220,6 +290,7 @@ fn cli() -> App<'static, 'static> {
         .subcommand(cli_blake256())
         .subcommand(cli_blake160())
         .subcommand(cli_secp256k1_lock())
+        .subcommand(cli_profile_script())
 }
 
 fn cli_hashes() -> App<'static, 'static> {
@@ -280,6 +351,28 @@ fn cli_secp256k1_lock() -> App<'static, 'static> {
         )
 }
 
+fn cli_profile_script() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_PROFILE_SCRIPT)
+        .about("Profiles cycles of transaction scripts, prints folded stacks for flamegraph")
+        .arg(
+            Arg::with_name(ARG_SOURCE)
+                .short("s")
+                .long(ARG_SOURCE)
+                .value_name("path")
+                .required(true)
+                .index(1)
+                .help("Specifies the transaction JSON file path."),
+        )
+        .arg(
+            Arg::with_name(ARG_TARGET)
+                .short("t")
+                .long(ARG_TARGET)
+                .value_name("path")
+                .takes_value(true)
+                .help("Writes the folded stacks to the path instead of stdout."),
+        )
+}
+
 fn init() -> App<'static, 'static> {
     SubCommand::with_name(CMD_INIT)
         .about("Creates a CKB direcotry or reinitializes an existing one")
@@ -361,7 +454,7 @@ fn init() -> App<'static, 'static> {
                 .long(ARG_BA_HASH_TYPE)
                 .value_name("hash_type")
                 .takes_value(true)
-                .possible_values(&["data", "type"])
+                .possible_values(&["data", "type", "data1"])
                 .default_value("type")
                 .help("Sets hash type in [block_assembler]"),
         )
@@ -415,6 +508,14 @@ fn is_hex(hex: String) -> Result<(), String> {
     }
 }
 
+fn is_h256(hex: String) -> Result<(), String> {
+    if hex.len() != 66 {
+        Err("Must be a 0x-prefixed hexadecimal string of 32 bytes".to_string())
+    } else {
+        is_hex(hex)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,7 +6,8 @@ mod sentry_config;
 
 pub use app_config::{AppConfig, CKBAppConfig, MinerAppConfig};
 pub use args::{
-    ExportArgs, ImportArgs, InitArgs, MinerArgs, ProfArgs, ResetDataArgs, RunArgs, StatsArgs,
+    ExportArgs, ImportArgs, InitArgs, MigrateArgs, MinerArgs, ProfArgs, ProfileScriptArgs,
+    ResetDataArgs, RunArgs, SnapshotExportArgs, SnapshotImportArgs, StatsArgs,
 };
 pub use ckb_tx_pool::BlockAssemblerConfig;
 pub use exit_code::ExitCode;
@@ -15,8 +16,10 @@ use ckb_build_info::Version;
 use ckb_chain_spec::{consensus::Consensus, ChainSpec};
 use ckb_jsonrpc_types::ScriptHashType;
 use ckb_logger::{info_target, LoggerInitGuard};
+use ckb_types::H256;
 use clap::{value_t, ArgMatches, ErrorKind};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 pub(crate) const LOG_TARGET_SENTRY: &str = "sentry";
 
@@ -95,8 +98,11 @@ impl Setup {
     }
 
     pub fn run<'m>(self, matches: &ArgMatches<'m>) -> Result<RunArgs, ExitCode> {
-        let consensus = self.consensus()?;
+        let mut consensus = self.consensus()?;
         let config = self.config.into_ckb()?;
+        if let Some(target) = &config.chain.assume_valid_target {
+            consensus.assume_valid_target = Some(target.clone());
+        }
 
         Ok(RunArgs {
             config,
@@ -182,6 +188,74 @@ impl Setup {
         })
     }
 
+    pub fn migrate<'m>(self, matches: &ArgMatches<'m>) -> Result<MigrateArgs, ExitCode> {
+        let config = self.config.into_ckb()?;
+        let check = matches.is_present(cli::ARG_CHECK);
+
+        Ok(MigrateArgs { config, check })
+    }
+
+    pub fn snapshot_export<'m>(
+        self,
+        matches: &ArgMatches<'m>,
+    ) -> Result<SnapshotExportArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let target = value_t!(matches.value_of(cli::ARG_TARGET), PathBuf)?;
+
+        Ok(SnapshotExportArgs {
+            config,
+            consensus,
+            target,
+        })
+    }
+
+    pub fn snapshot_import<'m>(
+        self,
+        matches: &ArgMatches<'m>,
+    ) -> Result<SnapshotImportArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let source = value_t!(matches.value_of(cli::ARG_SOURCE), PathBuf)?;
+        let trusted_hash = matches
+            .value_of(cli::ARG_TRUSTED_HASH)
+            .and_then(|hash| H256::from_str(&hash[2..]).ok())
+            .ok_or_else(|| {
+                eprintln!("Invalid --{}", cli::ARG_TRUSTED_HASH);
+                ExitCode::Cli
+            })?;
+
+        Ok(SnapshotImportArgs {
+            config,
+            consensus,
+            source,
+            trusted_hash,
+        })
+    }
+
+    pub fn profile_script<'m>(
+        matches: &ArgMatches<'m>,
+        sub_matches: &ArgMatches<'m>,
+    ) -> Result<ProfileScriptArgs, ExitCode> {
+        let root_dir = Self::root_dir_from_matches(matches)?;
+        let setup = Setup {
+            subcommand_name: cli::CMD_PROFILE_SCRIPT.to_string(),
+            config: AppConfig::load_for_subcommand(&root_dir, cli::CMD_PROFILE_SCRIPT)?,
+            is_sentry_enabled: false,
+        };
+        let consensus = setup.consensus()?;
+        let config = setup.config.into_ckb()?;
+        let source = value_t!(sub_matches.value_of(cli::ARG_SOURCE), PathBuf)?;
+        let target = sub_matches.value_of(cli::ARG_TARGET).map(PathBuf::from);
+
+        Ok(ProfileScriptArgs {
+            config,
+            consensus,
+            source,
+            target,
+        })
+    }
+
     pub fn init<'m>(matches: &ArgMatches<'m>) -> Result<InitArgs, ExitCode> {
         if matches.is_present("list-specs") {
             eprintln!(
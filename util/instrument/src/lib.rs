@@ -1,15 +1,21 @@
 //! # The Instrument Library
 //!
-//! Instruments for ckb for working with `Export`, `Import`
+//! Instruments for ckb for working with `Export`, `Import` and snapshots
 //!
 //! - [Export](instrument::export::Export) provide block data
 //!   export function.
 //! - [Import](instrument::import::Import) import block data which
 //!   export from `Export`.
+//! - [SnapshotExport](instrument::snapshot::SnapshotExport) export the
+//!   cell set at the tip with a hash commitment.
+//! - [SnapshotImport](instrument::snapshot::SnapshotImport) start an
+//!   empty database from a trusted snapshot.
 
 mod export;
 mod import;
 mod iter;
+mod snapshot;
 
 pub use crate::export::Export;
 pub use crate::import::Import;
+pub use crate::snapshot::{SnapshotExport, SnapshotImport};
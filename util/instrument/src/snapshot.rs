@@ -0,0 +1,590 @@
+//! Cell set snapshots for checkpointed fast sync
+//!
+//! A snapshot file is a sequence of records. Every record is a tag byte followed by a
+//! `BytesVec` which holds the packed items of the record:
+//!
+//! - `TAG_EPOCH`: `[epoch hash, EpochExt]` of every epoch up to the tip
+//! - `TAG_HEADER`: `[HeaderView, BlockExt, epoch hash]` of every main chain block from genesis
+//! - `TAG_BLOCK`: `[Block]` of the genesis and the recent main chain blocks, which are required
+//!   to verify the blocks after the tip
+//! - `TAG_UNDO`: `[block hash, cell undo record]` of the recent blocks which spend cells from
+//!   before them, they restore the spent cells when the recent blocks are detached by a fork
+//! - `TAG_LIVE_TX`: `[tx hash, TransactionMeta]` of every transaction which still has live cells
+//!   at the tip, followed by `[TransactionInfo, TransactionView]` unless its block is exported
+//!   in full
+//! - `TAG_TIP`: `[tip hash, EpochExt]`, always the last record
+//!
+//! The blake2b hash of the whole file is the commitment, a node only imports a snapshot whose
+//! hash matches the trusted hash given by the operator. The file is hashed while it is imported
+//! and the tip, which makes the store usable, is written only once the hash matches.
+
+use ckb_chain_spec::consensus::Consensus;
+use ckb_db::Direction;
+use ckb_hash::{new_blake2b, Blake2b};
+use ckb_shared::shared::Shared;
+use ckb_store::{ChainDB, ChainStore, StoreTransaction, COLUMN_BLOCK_HEADER, COLUMN_CELL_UNDO};
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockExt, BlockNumber, BlockView, EpochExt, HeaderView, TransactionView},
+    packed,
+    prelude::*,
+    H256,
+};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+const TAG_EPOCH: u8 = 0;
+const TAG_HEADER: u8 = 1;
+const TAG_BLOCK: u8 = 2;
+const TAG_LIVE_TX: u8 = 3;
+const TAG_TIP: u8 = 4;
+const TAG_UNDO: u8 = 5;
+
+// Commits the import every so many records to bound the memory used by the db transaction
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// Export the cell set at the tip to a snapshot file.
+pub struct SnapshotExport {
+    /// export target path
+    pub target: PathBuf,
+    pub shared: Shared,
+}
+
+impl SnapshotExport {
+    pub fn new(shared: Shared, target: PathBuf) -> Self {
+        SnapshotExport { shared, target }
+    }
+
+    /// snapshot file name
+    fn file_name(&self, tip_number: BlockNumber) -> String {
+        format!(
+            "{}-{}.{}",
+            self.shared.consensus().id,
+            tip_number,
+            "snapshot"
+        )
+    }
+
+    /// Writes the snapshot and returns its path and hash commitment.
+    pub fn execute(self) -> Result<(PathBuf, H256), Box<dyn Error>> {
+        fs::create_dir_all(&self.target)?;
+        let snapshot = self.shared.snapshot();
+        let consensus = snapshot.consensus();
+        let tip_number = snapshot.tip_number();
+        let path = self.target.join(self.file_name(tip_number));
+
+        let f = fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&path)?;
+        let mut writer = SnapshotWriter::new(io::BufWriter::new(f));
+
+        for number in 0..=snapshot.epoch_ext().number() {
+            let hash = snapshot
+                .get_epoch_index(number)
+                .ok_or_else(|| format!("epoch {} is missing", number))?;
+            let epoch = snapshot
+                .get_epoch_ext(&hash)
+                .ok_or_else(|| format!("epoch ext {} is missing", hash))?;
+            writer.write_record(TAG_EPOCH, &[hash.as_bytes(), epoch.pack().as_bytes()])?;
+        }
+
+        for number in 0..=tip_number {
+            let header = get_main_chain_header(&**snapshot, number)?;
+            let hash = header.hash();
+            let ext = snapshot
+                .get_block_ext(&hash)
+                .ok_or_else(|| format!("block ext {} is missing", hash))?;
+            let epoch_hash = snapshot
+                .get_block_epoch_index(&hash)
+                .ok_or_else(|| format!("block epoch index {} is missing", hash))?;
+            writer.write_record(
+                TAG_HEADER,
+                &[
+                    header.pack().as_bytes(),
+                    ext.pack().as_bytes(),
+                    epoch_hash.as_bytes(),
+                ],
+            )?;
+        }
+
+        let recent_start = tip_number.saturating_sub(recent_blocks_window(consensus));
+        let mut exported_blocks = HashSet::new();
+        for number in Some(0).into_iter().chain(recent_start.max(1)..=tip_number) {
+            let hash = get_main_chain_header(&**snapshot, number)?.hash();
+            let block = snapshot
+                .get_block(&hash)
+                .ok_or_else(|| format!("block {} is missing", hash))?;
+            writer.write_record(TAG_BLOCK, &[block.data().as_bytes()])?;
+            exported_blocks.insert(hash);
+        }
+
+        // the spent cells of older blocks are not exported otherwise, a fork could not restore
+        // them without the undo records
+        for number in recent_start.max(1)..=tip_number {
+            let hash = get_main_chain_header(&**snapshot, number)?.hash();
+            match snapshot.get(COLUMN_CELL_UNDO, hash.as_slice()) {
+                Some(undo) => writer.write_record(
+                    TAG_UNDO,
+                    &[hash.as_bytes(), Bytes::from(undo.as_ref().to_vec())],
+                )?,
+                None => {
+                    if snapshot.get_block_undo(&hash).is_none() {
+                        return Err(format!(
+                            "the cell undo record of block {} is missing, \
+                             the store.cell_undo_depth must cover the recent {} blocks",
+                            hash,
+                            recent_blocks_window(consensus)
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+
+        for (tx_hash, meta) in snapshot.cell_set().iter() {
+            let mut items = vec![tx_hash.as_bytes(), meta.pack().as_bytes()];
+            if !exported_blocks.contains(&meta.block_hash()) {
+                let info = snapshot
+                    .get_transaction_info_packed(tx_hash)
+                    .ok_or_else(|| format!("transaction info {} is missing", tx_hash))?;
+                let (tx, _) = snapshot
+                    .get_transaction(tx_hash)
+                    .ok_or_else(|| format!("transaction {} is missing", tx_hash))?;
+                items.push(info.as_bytes());
+                items.push(tx.pack().as_bytes());
+            }
+            writer.write_record(TAG_LIVE_TX, &items)?;
+        }
+
+        writer.write_record(
+            TAG_TIP,
+            &[
+                snapshot.tip_hash().as_bytes(),
+                snapshot.epoch_ext().pack().as_bytes(),
+            ],
+        )?;
+        let hash = writer.finish()?;
+        Ok((path, hash))
+    }
+}
+
+/// Import a snapshot file into an empty database, the node then starts at the snapshot tip.
+pub struct SnapshotImport {
+    /// source file contains the snapshot
+    source: PathBuf,
+    /// the hash commitment the snapshot must match
+    trusted_hash: H256,
+    store: ChainDB,
+    consensus: Consensus,
+}
+
+impl SnapshotImport {
+    pub fn new(store: ChainDB, consensus: Consensus, source: PathBuf, trusted_hash: H256) -> Self {
+        SnapshotImport {
+            source,
+            trusted_hash,
+            store,
+            consensus,
+        }
+    }
+
+    /// Imports the snapshot and returns the tip header.
+    ///
+    /// The store must be empty. A failed import leaves partial data behind but never the tip,
+    /// the data must be removed by `ckb reset-data --database` before retrying.
+    pub fn execute(self) -> Result<HeaderView, Box<dyn Error>> {
+        let has_headers = self
+            .store
+            .get_iter(COLUMN_BLOCK_HEADER, &[], Direction::Forward)
+            .next()
+            .is_some();
+        if has_headers
+            || self.store.get_tip_header().is_some()
+            || self.store.get_block_hash(0).is_some()
+        {
+            return Err("the database is not empty".into());
+        }
+
+        let mut reader = SnapshotReader::new(io::BufReader::new(fs::File::open(&self.source)?));
+        let mut db_txn = self.store.begin_transaction();
+        let mut pending = 0;
+        let mut tip = None;
+        while let Some((tag, record)) = reader.read_record()? {
+            if tip.is_some() {
+                return Err("unexpected record after the tip".into());
+            }
+            match tag {
+                TAG_EPOCH => {
+                    let items = expect_items(&record, 2)?;
+                    let hash = packed::Byte32::from_slice(&items[0])?;
+                    let epoch: EpochExt = packed::EpochExt::from_slice(&items[1])?.unpack();
+                    db_txn.insert_epoch_ext(&hash, &epoch)?;
+                }
+                TAG_HEADER => {
+                    let items = expect_items(&record, 3)?;
+                    let header: HeaderView = packed::HeaderView::from_slice(&items[0])?.unpack();
+                    let ext: BlockExt = packed::BlockExt::from_slice(&items[1])?.unpack();
+                    let epoch_hash = packed::Byte32::from_slice(&items[2])?;
+                    if header.is_genesis() && header.hash() != self.consensus.genesis_hash() {
+                        return Err(format!(
+                            "genesis mismatch, expected {} but the snapshot has {}",
+                            self.consensus.genesis_hash(),
+                            header.hash()
+                        )
+                        .into());
+                    }
+                    db_txn.insert_header(&header)?;
+                    db_txn.insert_block_ext(&header.hash(), &ext)?;
+                    db_txn.insert_block_epoch_index(&header.hash(), &epoch_hash)?;
+                    db_txn.insert_block_index(&header.hash(), header.number())?;
+                }
+                TAG_BLOCK => {
+                    let items = expect_items(&record, 1)?;
+                    let block: BlockView =
+                        packed::Block::from_slice(&items[0])?.into_view_without_reset_header();
+                    db_txn.insert_block(&block)?;
+                    db_txn.attach_block(&block)?;
+                }
+                TAG_UNDO => {
+                    let items = expect_items(&record, 2)?;
+                    let hash = packed::Byte32::from_slice(&items[0])?;
+                    packed::BytesVec::from_slice(&items[1])?;
+                    db_txn.insert_raw(COLUMN_CELL_UNDO, hash.as_slice(), &items[1])?;
+                }
+                TAG_LIVE_TX => {
+                    let items = if record.len() == 2 {
+                        expect_items(&record, 2)?
+                    } else {
+                        expect_items(&record, 4)?
+                    };
+                    let tx_hash = packed::Byte32::from_slice(&items[0])?;
+                    let meta = packed::TransactionMeta::from_slice(&items[1])?;
+                    if items.len() == 4 {
+                        let info = packed::TransactionInfo::from_slice(&items[2])?;
+                        let tx: TransactionView =
+                            packed::TransactionView::from_slice(&items[3])?.unpack();
+                        if tx.hash() != tx_hash {
+                            return Err(format!("transaction {} hash mismatch", tx_hash).into());
+                        }
+                        db_txn.insert_transaction(&info, &tx)?;
+                    }
+                    db_txn.update_cell_set(&tx_hash, &meta)?;
+                }
+                TAG_TIP => {
+                    // written once the whole file is hashed
+                    let items = expect_items(&record, 2)?;
+                    let hash = packed::Byte32::from_slice(&items[0])?;
+                    let epoch: EpochExt = packed::EpochExt::from_slice(&items[1])?.unpack();
+                    tip = Some((hash, epoch));
+                }
+                _ => return Err(format!("unknown snapshot record tag {}", tag).into()),
+            }
+
+            pending += 1;
+            if pending >= IMPORT_BATCH_SIZE {
+                db_txn.commit()?;
+                db_txn = self.store.begin_transaction();
+                pending = 0;
+            }
+        }
+
+        let (tip_hash, epoch) = tip.ok_or("the snapshot has no tip record")?;
+        let hash = reader.finish();
+        if hash != self.trusted_hash {
+            return Err(format!(
+                "snapshot hash mismatch, trusted {:#x} but the snapshot hashes to {:#x}",
+                self.trusted_hash, hash
+            )
+            .into());
+        }
+        let tip = db_txn
+            .get_block_header(&tip_hash)
+            .ok_or_else(|| format!("tip header {} is missing", tip_hash))?;
+        // `Shared::init_store` expects the genesis to be stored as a full block
+        check_genesis(&db_txn, &self.consensus)?;
+        db_txn.insert_tip_header(&tip)?;
+        db_txn.insert_current_epoch_ext(&epoch)?;
+        db_txn.commit()?;
+        Ok(tip)
+    }
+}
+
+/// Blocks a node must keep in full below its tip: the finalization delay covers the block
+/// rewards and the proposal window covers the proposals committed by the next blocks.
+fn recent_blocks_window(consensus: &Consensus) -> BlockNumber {
+    consensus.finalization_delay_length() + consensus.tx_proposal_window().farthest()
+}
+
+fn get_main_chain_header<'a, S: ChainStore<'a>>(
+    store: &'a S,
+    number: BlockNumber,
+) -> Result<HeaderView, Box<dyn Error>> {
+    store
+        .get_block_hash(number)
+        .and_then(|hash| store.get_block_header(&hash))
+        .ok_or_else(|| format!("main chain header {} is missing", number).into())
+}
+
+fn check_genesis(db_txn: &StoreTransaction, consensus: &Consensus) -> Result<(), Box<dyn Error>> {
+    if db_txn.get_block(&consensus.genesis_hash()).is_some() {
+        Ok(())
+    } else {
+        Err("the snapshot has no genesis block".into())
+    }
+}
+
+fn expect_items(items: &packed::BytesVec, len: usize) -> Result<Vec<Bytes>, Box<dyn Error>> {
+    if items.len() == len {
+        Ok(items
+            .clone()
+            .into_iter()
+            .map(|item| item.unpack())
+            .collect())
+    } else {
+        Err(format!("unexpected snapshot record of {} items", items.len()).into())
+    }
+}
+
+struct SnapshotWriter<W> {
+    inner: W,
+    hasher: Blake2b,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn new(inner: W) -> Self {
+        SnapshotWriter {
+            inner,
+            hasher: new_blake2b(),
+        }
+    }
+
+    fn write_record(&mut self, tag: u8, items: &[Bytes]) -> io::Result<()> {
+        let record = packed::BytesVec::new_builder()
+            .set(items.iter().map(|item| item.pack()).collect())
+            .build();
+        for data in &[&[tag][..], record.as_slice()] {
+            self.hasher.update(data);
+            self.inner.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<H256> {
+        self.inner.flush()?;
+        let mut hash = [0u8; 32];
+        self.hasher.finalize(&mut hash);
+        Ok(hash.into())
+    }
+}
+
+struct SnapshotReader<R> {
+    inner: R,
+    hasher: Blake2b,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn new(inner: R) -> Self {
+        SnapshotReader {
+            inner,
+            hasher: new_blake2b(),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<(u8, packed::BytesVec)>, Box<dyn Error>> {
+        let mut tag = [0u8; 1];
+        if self.inner.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+        // the first 4 bytes of a molecule dynvec are its total size
+        let mut size = [0u8; 4];
+        self.inner.read_exact(&mut size)?;
+        let total_size = u32::from_le_bytes(size) as usize;
+        if total_size < size.len() {
+            return Err(format!("invalid snapshot record size {}", total_size).into());
+        }
+        let mut record = size.to_vec();
+        record.resize(total_size, 0);
+        self.inner.read_exact(&mut record[size.len()..])?;
+
+        self.hasher.update(&tag);
+        self.hasher.update(&record);
+        let record = packed::BytesVec::from_slice(&record)?;
+        Ok(Some((tag[0], record)))
+    }
+
+    fn finish(self) -> H256 {
+        let mut hash = [0u8; 32];
+        self.hasher.finalize(&mut hash);
+        hash.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_chain::{
+        chain::{ChainController, ChainService},
+        switch::Switch,
+    };
+    use ckb_db::{DBConfig, RocksDB};
+    use ckb_shared::shared::SharedBuilder;
+    use ckb_store::{migrations, StoreConfig, COLUMNS};
+    use ckb_test_chain_utils::{always_success_cellbase, always_success_consensus};
+    use ckb_types::{
+        core::{BlockBuilder, Capacity, TransactionBuilder},
+        packed::{CellInput, CellOutput, OutPoint},
+    };
+    use std::sync::Arc;
+
+    fn build_block(shared: &Shared, parent: &HeaderView, nonce: u128) -> BlockBuilder {
+        let snapshot = shared.snapshot();
+        let parent_epoch = snapshot.get_block_epoch(&parent.hash()).unwrap();
+        let epoch = snapshot
+            .next_epoch_ext(shared.consensus(), &parent_epoch, parent)
+            .unwrap_or(parent_epoch);
+        let number = parent.number() + 1;
+        BlockBuilder::default()
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .timestamp((parent.timestamp() + 1).pack())
+            .epoch(epoch.number_with_fraction(number).pack())
+            .compact_target(epoch.compact_target().pack())
+            .nonce(nonce.pack())
+            .transaction(always_success_cellbase(
+                number,
+                Capacity::zero(),
+                shared.consensus(),
+            ))
+    }
+
+    fn spend(tx_hash: &packed::Byte32) -> TransactionView {
+        TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::new(tx_hash.clone(), 0), 0))
+            .output(CellOutput::default())
+            .output_data(Bytes::new().pack())
+            .build()
+    }
+
+    // Extends the chain from `parent`, `txs` are committed at the given heights
+    fn extend_chain(
+        shared: &Shared,
+        chain_controller: &ChainController,
+        parent: HeaderView,
+        len: BlockNumber,
+        nonce: u128,
+        txs: &[(BlockNumber, TransactionView)],
+    ) {
+        let mut parent = parent;
+        for _ in 0..len {
+            let number = parent.number() + 1;
+            let block = build_block(shared, &parent, nonce)
+                .transactions(
+                    txs.iter()
+                        .filter(|(n, _)| *n == number)
+                        .map(|(_, tx)| tx.clone()),
+                )
+                .build();
+            chain_controller
+                .internal_process_block(Arc::new(block.clone()), Switch::DISABLE_ALL)
+                .expect("process block ok");
+            parent = block.header();
+        }
+    }
+
+    fn import(
+        config: &DBConfig,
+        source: PathBuf,
+        trusted_hash: H256,
+    ) -> Result<HeaderView, Box<dyn Error>> {
+        let db = RocksDB::open_with_migrations(config, COLUMNS, &migrations()).unwrap();
+        let store = ChainDB::new(db, StoreConfig::default());
+        SnapshotImport::new(store, always_success_consensus(), source, trusted_hash).execute()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let consensus = always_success_consensus();
+        let (shared, table) = SharedBuilder::default()
+            .consensus(consensus.clone())
+            .build()
+            .unwrap();
+        let chain_controller = ChainService::new(shared.clone(), table).start::<&str>(None);
+
+        // tx2 spends tx1, which is fully dead at the tip and lies below the recent blocks
+        let tx1 = spend(&consensus.genesis_block().transactions()[0].hash());
+        let tx2 = spend(&tx1.hash());
+        let genesis = consensus.genesis_block().header();
+        let txs = [(2, tx1.clone()), (25, tx2.clone())];
+        extend_chain(&shared, &chain_controller, genesis, 30, 0, &txs);
+        // block 2 is not exported in full
+        assert!(recent_blocks_window(&consensus) < 30 - 2);
+        assert!(!shared.snapshot().cell_set().contains_key(&tx1.hash()));
+
+        let target = tempfile::tempdir().unwrap();
+        let (path, hash) = SnapshotExport::new(shared.clone(), target.path().to_path_buf())
+            .execute()
+            .unwrap();
+
+        let config_of = |dir: &tempfile::TempDir| DBConfig {
+            path: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        // a snapshot which does not match the trusted hash leaves no tip behind
+        let mismatched = tempfile::tempdir().unwrap();
+        assert!(import(&config_of(&mismatched), path.clone(), H256::default()).is_err());
+        {
+            let db = RocksDB::open_with_migrations(&config_of(&mismatched), COLUMNS, &migrations())
+                .unwrap();
+            assert!(ChainDB::new(db, StoreConfig::default())
+                .get_tip_header()
+                .is_none());
+        }
+
+        let imported = tempfile::tempdir().unwrap();
+        let tip = import(&config_of(&imported), path.clone(), hash.clone()).unwrap();
+        assert_eq!(&tip, shared.snapshot().tip_header());
+        // the store is not empty any more
+        assert!(import(&config_of(&imported), path, hash).is_err());
+
+        let (imported_shared, imported_table) =
+            SharedBuilder::with_db_config(&config_of(&imported))
+                .consensus(consensus.clone())
+                .build()
+                .unwrap();
+        {
+            let snapshot = shared.snapshot();
+            let imported_snapshot = imported_shared.snapshot();
+            assert_eq!(imported_snapshot.tip_header(), snapshot.tip_header());
+            assert_eq!(imported_snapshot.epoch_ext(), snapshot.epoch_ext());
+            assert_eq!(imported_snapshot.cell_set(), snapshot.cell_set());
+        }
+
+        // a fork which detaches the block spending tx1 restores it from the imported undo record
+        let imported_chain_controller =
+            ChainService::new(imported_shared.clone(), imported_table).start::<&str>(None);
+        let fork_parent = shared
+            .snapshot()
+            .get_block_hash(24)
+            .and_then(|hash| shared.snapshot().get_block_header(&hash))
+            .unwrap();
+        extend_chain(&shared, &chain_controller, fork_parent.clone(), 7, 1, &[]);
+        extend_chain(
+            &imported_shared,
+            &imported_chain_controller,
+            fork_parent,
+            7,
+            1,
+            &[],
+        );
+        let snapshot = shared.snapshot();
+        let imported_snapshot = imported_shared.snapshot();
+        assert_eq!(imported_snapshot.tip_header(), snapshot.tip_header());
+        assert!(imported_snapshot.cell_set().contains_key(&tx1.hash()));
+        assert_eq!(imported_snapshot.cell_set(), snapshot.cell_set());
+    }
+}
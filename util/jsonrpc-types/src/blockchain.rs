@@ -13,6 +13,7 @@ use std::fmt;
 pub enum ScriptHashType {
     Data,
     Type,
+    Data1,
 }
 
 impl Default for ScriptHashType {
@@ -26,6 +27,7 @@ impl From<ScriptHashType> for core::ScriptHashType {
         match json {
             ScriptHashType::Data => core::ScriptHashType::Data,
             ScriptHashType::Type => core::ScriptHashType::Type,
+            ScriptHashType::Data1 => core::ScriptHashType::Data1,
         }
     }
 }
@@ -35,6 +37,7 @@ impl From<core::ScriptHashType> for ScriptHashType {
         match core {
             core::ScriptHashType::Data => ScriptHashType::Data,
             core::ScriptHashType::Type => ScriptHashType::Type,
+            core::ScriptHashType::Data1 => ScriptHashType::Data1,
         }
     }
 }
@@ -44,6 +47,7 @@ impl fmt::Display for ScriptHashType {
         match self {
             ScriptHashType::Data => write!(f, "data"),
             ScriptHashType::Type => write!(f, "type"),
+            ScriptHashType::Data1 => write!(f, "data1"),
         }
     }
 }
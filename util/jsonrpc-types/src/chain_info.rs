@@ -1,5 +1,5 @@
-use crate::{AlertMessage, EpochNumber, Timestamp};
-use ckb_types::U256;
+use crate::{AlertMessage, BlockNumber, EpochNumber, Timestamp};
+use ckb_types::{H256, U256};
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -17,3 +17,40 @@ pub struct ChainInfo {
     // any network and blockchain warnings
     pub alerts: Vec<AlertMessage>,
 }
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReorgRecord {
+    // when the fork was found
+    pub timestamp: Timestamp,
+    // number of blocks detached from the main chain
+    pub depth: BlockNumber,
+    pub detached_blocks: Vec<H256>,
+    pub attached_blocks: Vec<H256>,
+    // the fork exceeded the max reorg depth and was kept as a fork
+    pub refused: bool,
+}
+
+// Ordered from the best to the worst, a branch has the worst status of its blocks
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainTipStatus {
+    /// All the blocks of the branch are stored and have been verified
+    ValidFork,
+    /// All the blocks of the branch are stored, some have never been verified
+    ValidHeaders,
+    /// Some blocks of the branch are only known by their headers
+    HeadersOnly,
+    /// Some blocks of the branch are invalid
+    Invalid,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ChainTip {
+    pub number: BlockNumber,
+    pub hash: H256,
+    // absent if it is unknown
+    pub total_difficulty: Option<U256>,
+    // number of blocks from the tip back to the main chain
+    pub branch_length: BlockNumber,
+    pub status: ChainTipStatus,
+}
@@ -6,6 +6,13 @@ pub struct DryRunResult {
     pub cycles: Cycle,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct ProfileResult {
+    pub cycles: Cycle,
+    // One `<script hash>;<function>[;syscall_<number>] <cycles>` stack per line
+    pub folded_stacks: String,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 pub struct EstimateResult {
     pub fee_rate: FeeRate,
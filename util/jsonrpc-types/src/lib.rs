@@ -27,12 +27,12 @@ pub use self::blockchain::{
 };
 pub use self::bytes::JsonBytes;
 pub use self::cell::{CellOutputWithOutPoint, CellWithStatus};
-pub use self::chain_info::ChainInfo;
-pub use self::experiment::{DryRunResult, EstimateResult};
+pub use self::chain_info::{ChainInfo, ChainTip, ChainTipStatus, ReorgRecord};
+pub use self::experiment::{DryRunResult, EstimateResult, ProfileResult};
 pub use self::fixed_bytes::Byte32;
 pub use self::indexer::{CellTransaction, LiveCell, LockHashIndexState, TransactionPoint};
 pub use self::net::{BannedAddr, Node, NodeAddress};
-pub use self::pool::TxPoolInfo;
+pub use self::pool::{ScriptGroupCycles, ScriptGroupType, TxCycles, TxPoolInfo};
 pub use self::proposal_short_id::ProposalShortId;
 pub use self::sync::PeerState;
 pub use self::uint128::Uint128;
@@ -1,4 +1,5 @@
-use crate::{Timestamp, Uint64};
+use crate::{Capacity, Cycle, Timestamp, Uint64};
+use ckb_types::H256;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
@@ -10,3 +11,24 @@ pub struct TxPoolInfo {
     pub total_tx_cycles: Uint64,
     pub last_txs_updated_at: Timestamp,
 }
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptGroupType {
+    Lock,
+    Type,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct ScriptGroupCycles {
+    pub script_hash: H256,
+    pub group_type: ScriptGroupType,
+    pub cycles: Cycle,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct TxCycles {
+    pub cycles: Cycle,
+    pub fee: Capacity,
+    pub script_groups: Vec<ScriptGroupCycles>,
+}
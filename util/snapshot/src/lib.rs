@@ -22,6 +22,10 @@ use ckb_types::{
 use im::hashmap::HashMap as HamtMap;
 use std::sync::Arc;
 
+/// The most blocks below the tip `Snapshot::cell_set_at` rebuilds the cell set for, every call
+/// replays the undo data of all the blocks after the given one
+pub const MAX_CELL_SET_AT_DEPTH: BlockNumber = 1000;
+
 pub struct SnapshotMgr {
     inner: ArcSwap<Snapshot>,
 }
@@ -105,6 +109,28 @@ impl Snapshot {
         &self.total_difficulty
     }
 
+    /// Rebuilds the cell set as of a main chain block by applying the undo data of the blocks
+    /// after it, `None` if the block is not on the main chain, is deeper than
+    /// `MAX_CELL_SET_AT_DEPTH` or some undo data is pruned.
+    pub fn cell_set_at(&self, block_hash: &Byte32) -> Option<HamtMap<Byte32, TransactionMeta>> {
+        let number = self.get_block_number(block_hash)?;
+        if self.tip_number().saturating_sub(number) > MAX_CELL_SET_AT_DEPTH {
+            return None;
+        }
+        let mut cell_set = self.cell_set.clone();
+        for detached_number in ((number + 1)..=self.tip_number()).rev() {
+            let detached_hash = self.get_block_hash(detached_number)?;
+            let undo = self.get_block_undo(&detached_hash)?;
+            for tx_hash in self.get_block_txs_hashes(&detached_hash) {
+                cell_set.remove(&tx_hash);
+            }
+            for (tx_hash, meta) in undo {
+                cell_set.insert(tx_hash, meta);
+            }
+        }
+        Some(cell_set)
+    }
+
     pub fn finalize_block_reward(
         &self,
         parent: &HeaderView,
@@ -7,6 +7,8 @@ use crate::packed;
 pub enum ScriptHashType {
     Data = 0,
     Type = 1,
+    // Matches the binary by data hash like `Data`, but runs it on the VM version 1
+    Data1 = 2,
 }
 
 impl Default for ScriptHashType {
@@ -22,15 +24,17 @@ impl TryFrom<packed::Byte> for ScriptHashType {
         match Into::<u8>::into(v) {
             0 => Ok(ScriptHashType::Data),
             1 => Ok(ScriptHashType::Type),
+            2 => Ok(ScriptHashType::Data1),
             _ => Err(err_msg(format!("Invalid script hash type {}", v))),
         }
     }
 }
 
 impl ScriptHashType {
+    // `Data1` is only valid once its hard fork is activated
     #[inline]
-    pub(crate) fn verify_value(v: u8) -> bool {
-        v <= 1
+    pub(crate) fn verify_value(v: u8, allow_data1: bool) -> bool {
+        v <= 1 || (allow_data1 && v == ScriptHashType::Data1 as u8)
     }
 }
 
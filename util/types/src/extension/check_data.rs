@@ -2,31 +2,50 @@ use crate::{core, packed};
 
 /*
  * Blockchain
+ *
+ * `check_data` accepts the values valid before any hard fork. The hash type `Data1` is only
+ * valid once its hard fork is activated, `check_data_with(true)` accepts it as well.
  */
 
 impl<'r> packed::ScriptReader<'r> {
     pub fn check_data(&self) -> bool {
-        core::ScriptHashType::verify_value(self.hash_type().into())
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        core::ScriptHashType::verify_value(self.hash_type().into(), allow_data1)
     }
 }
 
 impl<'r> packed::ScriptOptReader<'r> {
     pub fn check_data(&self) -> bool {
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
         self.to_opt()
-            .map(|i| core::ScriptHashType::verify_value(i.hash_type().into()))
+            .map(|i| i.check_data_with(allow_data1))
             .unwrap_or(true)
     }
 }
 
 impl<'r> packed::CellOutputReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.lock().check_data() && self.type_().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.lock().check_data_with(allow_data1) && self.type_().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::CellOutputVecReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.iter().all(|i| i.check_data())
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.iter().all(|i| i.check_data_with(allow_data1))
     }
 }
 
@@ -44,27 +63,43 @@ impl<'r> packed::CellDepVecReader<'r> {
 
 impl<'r> packed::RawTransactionReader<'r> {
     pub fn check_data(&self) -> bool {
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
         self.outputs().len() == self.outputs_data().len()
             && self.cell_deps().check_data()
-            && self.outputs().check_data()
+            && self.outputs().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::TransactionReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.raw().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.raw().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::TransactionVecReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.iter().all(|i| i.check_data())
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.iter().all(|i| i.check_data_with(allow_data1))
     }
 }
 
 impl<'r> packed::BlockReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.transactions().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.transactions().check_data_with(allow_data1)
     }
 }
 
@@ -74,31 +109,51 @@ impl<'r> packed::BlockReader<'r> {
 
 impl<'r> packed::BlockTransactionsReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.transactions().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.transactions().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::RelayTransactionReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.transaction().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.transaction().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::RelayTransactionVecReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.iter().all(|i| i.check_data())
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.iter().all(|i| i.check_data_with(allow_data1))
     }
 }
 
 impl<'r> packed::RelayTransactionsReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.transactions().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.transactions().check_data_with(allow_data1)
     }
 }
 
 impl<'r> packed::SendBlockReader<'r> {
     pub fn check_data(&self) -> bool {
-        self.block().check_data()
+        self.check_data_with(false)
+    }
+
+    pub fn check_data_with(&self, allow_data1: bool) -> bool {
+        self.block().check_data_with(allow_data1)
     }
 }
 
@@ -211,4 +266,24 @@ mod tests {
         test_check_data_via_transaction(false, &[&output_right1], &[], &[&cell_dep_right]);
         test_check_data_via_transaction(false, &[], &[&[]], &[&cell_dep_right]);
     }
+
+    #[test]
+    fn check_data_with_data1() {
+        let script_data1 = packed::Script::new_builder().hash_type(2.into()).build();
+        let script_error = packed::Script::new_builder().hash_type(3.into()).build();
+        let output_data1 = packed::CellOutput::new_builder()
+            .lock(script_data1.clone())
+            .build();
+        let output_error = packed::CellOutput::new_builder()
+            .lock(script_data1)
+            .type_(Some(script_error).pack())
+            .build();
+
+        let tx = create_transaction(&[&output_data1], &[&[]], &[]);
+        assert!(!tx.as_reader().check_data());
+        assert!(tx.as_reader().check_data_with(true));
+
+        let tx = create_transaction(&[&output_error], &[&[]], &[]);
+        assert!(!tx.as_reader().check_data_with(true));
+    }
 }
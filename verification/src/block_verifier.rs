@@ -1,6 +1,7 @@
 use crate::header_verifier::HeaderResolver;
+use crate::transaction_verifier::{ScriptHashTypeVerifier, VersionVerifier as TxVersionVerifier};
 use crate::{BlockErrorKind, CellbaseError, Verifier};
-use ckb_chain_spec::consensus::Consensus;
+use ckb_chain_spec::{consensus::Consensus, hardfork::HardForkFeature};
 use ckb_error::Error;
 use ckb_store::ChainStore;
 use ckb_types::{
@@ -31,6 +32,20 @@ impl<'a> Verifier for BlockVerifier<'a> {
         BlockProposalsLimitVerifier::new(max_block_proposals_limit).verify(target)?;
         BlockBytesVerifier::new(max_block_bytes).verify(target)?;
         CellbaseVerifier::new().verify(target)?;
+        // cellbase skips the transaction verifier, check its version and output scripts
+        // against the block epoch. The cellbase version was never checked before, so it is
+        // only checked once tx_version_1 is activated
+        if let Some(cellbase) = target.transaction(0) {
+            let epoch = target.epoch().number();
+            if self.consensus.is_active(HardForkFeature::TxVersion1, epoch) {
+                TxVersionVerifier::new(&cellbase, self.consensus.max_tx_version(epoch)).verify()?;
+            }
+            ScriptHashTypeVerifier::new(
+                &cellbase,
+                self.consensus.script_hash_type_data1_enabled(epoch),
+            )
+            .verify()?;
+        }
         DuplicateVerifier::new().verify(target)?;
         MerkleRootVerifier::new().verify(target)
     }
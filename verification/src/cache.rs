@@ -1,18 +1,38 @@
+use ckb_script::{ScriptGroupCycles, TransactionSnapshot};
 use ckb_types::{
     core::{Capacity, Cycle},
     packed::Byte32,
 };
+use std::sync::Arc;
 
 pub type TxVerifyCache = lru_cache::LruCache<Byte32, CacheEntry>;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CacheEntry {
     pub cycles: Cycle,
     pub fee: Capacity,
+    // Cycles of each script group, they sum up to `cycles`
+    pub group_cycles: Vec<ScriptGroupCycles>,
 }
 
 impl CacheEntry {
-    pub fn new(cycles: Cycle, fee: Capacity) -> Self {
-        CacheEntry { cycles, fee }
+    pub fn new(cycles: Cycle, fee: Capacity, group_cycles: Vec<ScriptGroupCycles>) -> Self {
+        CacheEntry {
+            cycles,
+            fee,
+            group_cycles,
+        }
     }
+
+    /// The most expensive script group, None if the transaction has no script group
+    pub fn max_group_cycles(&self) -> Option<&ScriptGroupCycles> {
+        self.group_cycles.iter().max_by_key(|group| group.cycles)
+    }
+}
+
+/// Verification state of a transaction verified in slices
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxVerifyState {
+    Completed(CacheEntry),
+    Suspended(Arc<TransactionSnapshot>),
 }
@@ -12,6 +12,7 @@ use ckb_error::Error;
 use ckb_future_executor::Executor;
 use ckb_logger::error_target;
 use ckb_reward_calculator::RewardCalculator;
+use ckb_script::ScriptVerifyCache;
 use ckb_store::ChainStore;
 use ckb_traits::BlockMedianTimeContext;
 use ckb_types::{
@@ -27,6 +28,7 @@ use ckb_types::{
 use futures::future::{self, Future};
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::sync::lock::Lock;
 
 pub struct VerifyContext<'a, CS> {
@@ -40,6 +42,7 @@ pub trait Switch {
     fn disable_two_phase_commit(&self) -> bool;
     fn disable_daoheader(&self) -> bool;
     fn disable_reward(&self) -> bool;
+    fn disable_script(&self) -> bool;
 }
 
 impl<'a, CS: ChainStore<'a>> VerifyContext<'a, CS> {
@@ -338,6 +341,8 @@ struct BlockTxsVerifier<'a, CS> {
     epoch_number_with_fraction: EpochNumberWithFraction,
     parent_hash: Byte32,
     resolved: &'a [ResolvedTransaction],
+    // skip script execution, the blocks are assumed valid
+    skip_script: bool,
 }
 
 impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
@@ -348,6 +353,7 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
         epoch_number_with_fraction: EpochNumberWithFraction,
         parent_hash: Byte32,
         resolved: &'a [ResolvedTransaction],
+        skip_script: bool,
     ) -> Self {
         BlockTxsVerifier {
             context,
@@ -355,6 +361,7 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
             epoch_number_with_fraction,
             parent_hash,
             resolved,
+            skip_script,
         }
     }
 
@@ -378,6 +385,7 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
     pub fn verify(
         &self,
         txs_verify_cache: Lock<TxVerifyCache>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
         executor: &Executor,
     ) -> Result<(Cycle, Vec<CacheEntry>), Error> {
         let keys: Vec<Byte32> = self
@@ -411,9 +419,9 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
                         }
                         .into()
                     })
-                    .map(|_| (tx_hash, *cache_entry))
+                    .map(|_| (tx_hash, cache_entry.clone()))
                 } else {
-                    TransactionVerifier::new(
+                    let verifier = TransactionVerifier::new(
                         &tx,
                         self.context,
                         self.block_number,
@@ -421,9 +429,15 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
                         self.parent_hash.clone(),
                         self.context.consensus,
                         self.context.store,
-                    )
-                    .verify(self.context.consensus.max_block_cycles())
-                    .map_err(|error| {
+                        Arc::clone(&script_verify_cache),
+                    );
+                    let result = if self.skip_script {
+                        verifier.verify_skip_script()
+                    } else {
+                        verifier.verify(self.context.consensus.max_block_cycles())
+                    };
+                    result
+                        .map_err(|error| {
                         BlockTransactionsError {
                             index: index as u32,
                             error,
@@ -433,6 +447,9 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
                     .map(|cache_entry| (tx_hash, cache_entry))
                 }
             })
+            .collect::<Vec<_>>()
+            // report the error of the first failed transaction in the block
+            .into_iter()
             .collect::<Result<Vec<(Byte32, CacheEntry)>, Error>>()?;
 
         let sum: Cycle = ret.iter().map(|(_, cache_entry)| cache_entry.cycles).sum();
@@ -441,8 +458,11 @@ impl<'a, CS: ChainStore<'a>> BlockTxsVerifier<'a, CS> {
             .map(|(_, cache_entry)| cache_entry)
             .cloned()
             .collect();
-        let update = UpdateCache::new(txs_verify_cache.clone(), ret.into_iter().collect());
-        executor.spawn(Box::new(update));
+        // entries without script cycles must not be reused by the tx-pool or other blocks
+        if !self.skip_script {
+            let update = UpdateCache::new(txs_verify_cache.clone(), ret.into_iter().collect());
+            executor.spawn(Box::new(update));
+        }
 
         if sum > self.context.consensus.max_block_cycles() {
             Err(BlockErrorKind::ExceededMaximumCycles.into())
@@ -515,6 +535,7 @@ impl<'a, CS: ChainStore<'a>> ContextualBlockVerifier<'a, CS> {
         resolved: &'a [ResolvedTransaction],
         block: &'a BlockView,
         txs_verify_cache: Lock<TxVerifyCache>,
+        script_verify_cache: Arc<ScriptVerifyCache>,
         executor: &Executor,
         switch: SW,
     ) -> Result<(Cycle, Vec<CacheEntry>), Error> {
@@ -560,7 +581,8 @@ impl<'a, CS: ChainStore<'a>> ContextualBlockVerifier<'a, CS> {
             block.epoch(),
             parent_hash,
             resolved,
+            switch.disable_script(),
         )
-        .verify(txs_verify_cache, executor)
+        .verify(txs_verify_cache, script_verify_cache, executor)
     }
 }
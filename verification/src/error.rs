@@ -37,6 +37,9 @@ pub enum TransactionError {
 
     /// The transaction size is too large
     ExceededMaximumBlockBytes,
+
+    /// The hash type of an output script is not activated yet
+    InvalidScriptHashType,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Display)]
@@ -229,7 +232,8 @@ impl TransactionError {
 
             TransactionError::Immature
             | TransactionError::CellbaseImmaturity
-            | TransactionError::MismatchedVersion => false,
+            | TransactionError::MismatchedVersion
+            | TransactionError::InvalidScriptHashType => false,
         }
     }
 }
@@ -37,7 +37,8 @@ impl<'a, T: HeaderResolver, M: BlockMedianTimeContext> Verifier for HeaderVerifi
     type Target = T;
     fn verify(&self, target: &T) -> Result<(), Error> {
         let header = target.header();
-        VersionVerifier::new(header, self.consensus.block_version()).verify()?;
+        let max_block_version = self.consensus.max_block_version(header.epoch().number());
+        VersionVerifier::new(header, max_block_version).verify()?;
         // POW check first
         PowVerifier::new(header, self.consensus.pow_engine().as_ref()).verify()?;
         let parent = target.parent().ok_or_else(|| UnknownParentError {
@@ -49,21 +50,22 @@ impl<'a, T: HeaderResolver, M: BlockMedianTimeContext> Verifier for HeaderVerifi
     }
 }
 
+// Rejects headers whose version is higher than the max version activated at the header epoch
 pub struct VersionVerifier<'a> {
     header: &'a HeaderView,
-    block_version: Version,
+    max_block_version: Version,
 }
 
 impl<'a> VersionVerifier<'a> {
-    pub fn new(header: &'a HeaderView, block_version: Version) -> Self {
+    pub fn new(header: &'a HeaderView, max_block_version: Version) -> Self {
         VersionVerifier {
             header,
-            block_version,
+            max_block_version,
         }
     }
 
     pub fn verify(&self) -> Result<(), Error> {
-        if self.header.version() != self.block_version {
+        if self.header.version() > self.max_block_version {
             return Err(BlockErrorKind::Version.into());
         }
         Ok(())
@@ -1,15 +1,19 @@
 use super::super::block_verifier::{
-    BlockBytesVerifier, BlockProposalsLimitVerifier, CellbaseVerifier, DuplicateVerifier,
-    MerkleRootVerifier,
+    BlockBytesVerifier, BlockProposalsLimitVerifier, BlockVerifier, CellbaseVerifier,
+    DuplicateVerifier, MerkleRootVerifier,
 };
 use super::super::contextual_block_verifier::EpochVerifier;
-use crate::{BlockErrorKind, CellbaseError, EpochError};
+use crate::{BlockErrorKind, CellbaseError, EpochError, TransactionError, Verifier};
+use ckb_chain_spec::{
+    consensus::ConsensusBuilder,
+    hardfork::{HardForkConfig, HardForkFeature},
+};
 use ckb_error::assert_error_eq;
 use ckb_types::{
     bytes::Bytes,
     core::{
-        capacity_bytes, BlockBuilder, BlockNumber, Capacity, EpochExt, HeaderBuilder,
-        TransactionBuilder, TransactionView,
+        capacity_bytes, BlockBuilder, BlockNumber, Capacity, EpochExt, EpochNumberWithFraction,
+        HeaderBuilder, TransactionBuilder, TransactionView,
     },
     h256,
     packed::{Byte32, CellInput, CellOutputBuilder, OutPoint, ProposalShortId, Script},
@@ -256,6 +260,36 @@ pub fn test_cellbase_with_two_output_data() {
     )
 }
 
+#[test]
+pub fn test_cellbase_version() {
+    let mut hardfork = HardForkConfig::default();
+    hardfork.set_activation_epoch(HardForkFeature::TxVersion1, Some(10));
+    let consensus = ConsensusBuilder::default().hardfork(hardfork).build();
+    let cellbase = create_cellbase_transaction_with_block_number(2)
+        .as_advanced_builder()
+        .version(2u32.pack())
+        .build();
+    let block_at = |epoch| {
+        BlockBuilder::default()
+            .header(
+                HeaderBuilder::default()
+                    .number(2u64.pack())
+                    .epoch(EpochNumberWithFraction::new(epoch, 0, 1000).pack())
+                    .build(),
+            )
+            .transaction(cellbase.clone())
+            .build()
+    };
+
+    // the cellbase version is not checked before tx_version_1 is activated
+    let verifier = BlockVerifier::new(&consensus);
+    assert!(verifier.verify(&block_at(9)).is_ok());
+    assert_error_eq!(
+        verifier.verify(&block_at(10)).unwrap_err(),
+        TransactionError::MismatchedVersion,
+    );
+}
+
 #[test]
 pub fn test_block_with_duplicated_txs() {
     let tx = create_normal_transaction();
@@ -20,6 +20,10 @@ pub fn test_version() {
     let verifier = VersionVerifier::new(&header, BLOCK_VERSION);
 
     assert_error_eq!(verifier.verify().unwrap_err(), BlockErrorKind::Version);
+
+    // accepted once a higher version is activated
+    let verifier = VersionVerifier::new(&header, BLOCK_VERSION + 1);
+    assert!(verifier.verify().is_ok());
 }
 
 #[cfg(not(disable_faketime))]
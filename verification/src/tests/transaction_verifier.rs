@@ -1,9 +1,14 @@
 use super::super::transaction_verifier::{
     CapacityVerifier, DuplicateDepsVerifier, EmptyVerifier, MaturityVerifier, OutputsDataVerifier,
-    Since, SinceVerifier, SizeVerifier, VersionVerifier,
+    ScriptHashTypeVerifier, Since, SinceVerifier, SizeVerifier, VersionVerifier,
 };
 use crate::TransactionError;
-use ckb_chain_spec::{build_genesis_type_id_script, OUTPUT_INDEX_DAO};
+use ckb_chain_spec::{
+    build_genesis_type_id_script,
+    consensus::ConsensusBuilder,
+    hardfork::{HardForkConfig, HardForkFeature},
+    OUTPUT_INDEX_DAO,
+};
 use ckb_error::{assert_error_eq, Error};
 use ckb_test_chain_utils::MockMedianTime;
 use ckb_traits::BlockMedianTimeContext;
@@ -13,11 +18,11 @@ use ckb_types::{
     core::{
         capacity_bytes,
         cell::{CellMetaBuilder, ResolvedTransaction},
-        BlockNumber, Capacity, EpochNumber, EpochNumberWithFraction, TransactionBuilder,
-        TransactionInfo, TransactionView, Version,
+        BlockNumber, Capacity, EpochNumber, EpochNumberWithFraction, ScriptHashType,
+        TransactionBuilder, TransactionInfo, TransactionView, Version,
     },
     h256,
-    packed::{CellDep, CellInput, CellOutput, OutPoint},
+    packed::{CellDep, CellInput, CellOutput, OutPoint, Script},
     prelude::*,
     H256,
 };
@@ -42,6 +47,56 @@ pub fn test_version() {
         verifier.verify().unwrap_err(),
         TransactionError::MismatchedVersion,
     );
+
+    // accepted once a higher version is activated
+    let verifier = VersionVerifier::new(&transaction, TX_VERSION + 1);
+    assert!(verifier.verify().is_ok());
+}
+
+#[test]
+pub fn test_script_hash_type_data1() {
+    let mut hardfork = HardForkConfig::default();
+    hardfork.set_activation_epoch(HardForkFeature::ScriptHashTypeData1, Some(10));
+    let consensus = ConsensusBuilder::default().hardfork(hardfork).build();
+    let data1_script = Script::new_builder()
+        .hash_type(ScriptHashType::Data1.into())
+        .build();
+
+    for transaction in vec![
+        TransactionBuilder::default()
+            .output(CellOutput::new_builder().lock(data1_script.clone()).build())
+            .output_data(Bytes::new().pack())
+            .build(),
+        TransactionBuilder::default()
+            .output(
+                CellOutput::new_builder()
+                    .type_(Some(data1_script.clone()).pack())
+                    .build(),
+            )
+            .output_data(Bytes::new().pack())
+            .build(),
+    ] {
+        // rejected before the activation epoch
+        let verifier =
+            ScriptHashTypeVerifier::new(&transaction, consensus.script_hash_type_data1_enabled(9));
+        assert_error_eq!(
+            verifier.verify().unwrap_err(),
+            TransactionError::InvalidScriptHashType,
+        );
+
+        // accepted from the activation epoch
+        let verifier =
+            ScriptHashTypeVerifier::new(&transaction, consensus.script_hash_type_data1_enabled(10));
+        assert!(verifier.verify().is_ok());
+    }
+
+    // other hash types are always accepted
+    let transaction = TransactionBuilder::default()
+        .output(CellOutput::new_builder().build())
+        .output_data(Bytes::new().pack())
+        .build();
+    let verifier = ScriptHashTypeVerifier::new(&transaction, false);
+    assert!(verifier.verify().is_ok());
 }
 
 #[test]
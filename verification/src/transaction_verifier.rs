@@ -1,9 +1,12 @@
-use crate::cache::CacheEntry;
+use crate::cache::{CacheEntry, TxVerifyState};
 use crate::TransactionError;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_dao::DaoCalculator;
 use ckb_error::Error;
-use ckb_script::TransactionScriptsVerifier;
+use ckb_script::{
+    DataLoader, ScriptGroupCycles, ScriptProfile, ScriptVerifyCache, TransactionScriptsVerifier,
+    TransactionSnapshot, VerifyResult,
+};
 use ckb_store::{data_loader_wrapper::DataLoaderWrapper, ChainStore};
 use ckb_traits::BlockMedianTimeContext;
 use ckb_types::{
@@ -18,10 +21,12 @@ use ckb_types::{
 use lru_cache::LruCache;
 use std::cell::RefCell;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 pub struct ContextualTransactionVerifier<'a, M> {
     pub maturity: MaturityVerifier<'a>,
     pub since: SinceVerifier<'a, M>,
+    pub script_hash_type: ScriptHashTypeVerifier<'a>,
 }
 
 impl<'a, M> ContextualTransactionVerifier<'a, M>
@@ -49,10 +54,15 @@ where
                 epoch_number_with_fraction,
                 parent_hash,
             ),
+            script_hash_type: ScriptHashTypeVerifier::new(
+                &rtx.transaction,
+                consensus.script_hash_type_data1_enabled(epoch_number_with_fraction.number()),
+            ),
         }
     }
 
     pub fn verify(&self) -> Result<(), Error> {
+        self.script_hash_type.verify()?;
         self.maturity.verify()?;
         self.since.verify()?;
         Ok(())
@@ -61,6 +71,7 @@ where
 
 pub struct TransactionVerifier<'a, M, CS> {
     pub version: VersionVerifier<'a>,
+    pub script_hash_type: ScriptHashTypeVerifier<'a>,
     pub size: SizeVerifier<'a>,
     pub empty: EmptyVerifier<'a>,
     pub maturity: MaturityVerifier<'a>,
@@ -86,9 +97,19 @@ where
         parent_hash: Byte32,
         consensus: &'a Consensus,
         chain_store: &'a CS,
+        script_cache: Arc<ScriptVerifyCache>,
     ) -> Self {
+        let epoch = epoch_number_with_fraction.number();
+        let mut script = ScriptVerifier::new(rtx, chain_store);
+        script.set_script_cache(script_cache);
+        script.set_native_crypto(consensus.native_crypto_syscalls_enabled(epoch));
+        script.set_exec(consensus.exec_syscall_enabled(epoch));
         TransactionVerifier {
-            version: VersionVerifier::new(&rtx.transaction, consensus.tx_version()),
+            version: VersionVerifier::new(&rtx.transaction, consensus.max_tx_version(epoch)),
+            script_hash_type: ScriptHashTypeVerifier::new(
+                &rtx.transaction,
+                consensus.script_hash_type_data1_enabled(epoch),
+            ),
             size: SizeVerifier::new(&rtx.transaction, consensus.max_block_bytes()),
             empty: EmptyVerifier::new(&rtx.transaction),
             maturity: MaturityVerifier::new(
@@ -98,7 +119,7 @@ where
             ),
             duplicate_deps: DuplicateDepsVerifier::new(&rtx.transaction),
             outputs_data_verifier: OutputsDataVerifier::new(&rtx.transaction),
-            script: ScriptVerifier::new(rtx, chain_store),
+            script,
             capacity: CapacityVerifier::new(rtx, consensus.dao_type_hash()),
             since: SinceVerifier::new(
                 rtx,
@@ -112,17 +133,61 @@ where
     }
 
     pub fn verify(&self, max_cycles: Cycle) -> Result<CacheEntry, Error> {
+        self.verify_without_script()?;
+        let group_cycles = self.script.verify_groups(max_cycles)?;
+        let cycles = group_cycles.iter().map(|group| group.cycles).sum();
+        let fee = self.fee_calculator.transaction_fee()?;
+        Ok(CacheEntry::new(cycles, fee, group_cycles))
+    }
+
+    /// Run all the checks except script execution, the returned entry has zero cycles.
+    pub fn verify_skip_script(&self) -> Result<CacheEntry, Error> {
+        self.verify_without_script()?;
+        let fee = self.fee_calculator.transaction_fee()?;
+        Ok(CacheEntry::new(0, fee, Vec::new()))
+    }
+
+    /// Verify scripts in slices of `step_cycles` cycles, pass the suspended snapshot back to
+    /// continue. Checks other than scripts are done in the first slice.
+    pub fn resumable_verify(
+        &self,
+        max_cycles: Cycle,
+        step_cycles: Cycle,
+        snapshot: Option<&TransactionSnapshot>,
+    ) -> Result<TxVerifyState, Error> {
+        let result = match snapshot {
+            Some(snapshot) => {
+                self.script
+                    .resume_from_snapshot(snapshot, max_cycles, step_cycles)?
+            }
+            None => {
+                self.verify_without_script()?;
+                self.script.resumable_verify(max_cycles, step_cycles)?
+            }
+        };
+        match result {
+            VerifyResult::Completed(cycles, group_cycles) => {
+                let fee = self.fee_calculator.transaction_fee()?;
+                Ok(TxVerifyState::Completed(CacheEntry::new(
+                    cycles,
+                    fee,
+                    group_cycles,
+                )))
+            }
+            VerifyResult::Suspended(snapshot) => Ok(TxVerifyState::Suspended(Arc::new(snapshot))),
+        }
+    }
+
+    fn verify_without_script(&self) -> Result<(), Error> {
         self.version.verify()?;
+        self.script_hash_type.verify()?;
         self.size.verify()?;
         self.empty.verify()?;
         self.maturity.verify()?;
         self.capacity.verify()?;
         self.duplicate_deps.verify()?;
         self.outputs_data_verifier.verify()?;
-        self.since.verify()?;
-        let cycles = self.script.verify(max_cycles)?;
-        let fee = self.fee_calculator.transaction_fee()?;
-        Ok(CacheEntry::new(cycles, fee))
+        self.since.verify()
     }
 }
 
@@ -155,27 +220,63 @@ impl<'a, CS: ChainStore<'a>> FeeCalculator<'a, CS> {
     }
 }
 
+// Rejects transactions whose version is higher than the max version activated at the epoch
 pub struct VersionVerifier<'a> {
     transaction: &'a TransactionView,
-    tx_version: Version,
+    max_tx_version: Version,
 }
 
 impl<'a> VersionVerifier<'a> {
-    pub fn new(transaction: &'a TransactionView, tx_version: Version) -> Self {
+    pub fn new(transaction: &'a TransactionView, max_tx_version: Version) -> Self {
         VersionVerifier {
             transaction,
-            tx_version,
+            max_tx_version,
         }
     }
 
     pub fn verify(&self) -> Result<(), Error> {
-        if self.transaction.version() != self.tx_version {
+        if self.transaction.version() > self.max_tx_version {
             return Err((TransactionError::MismatchedVersion).into());
         }
         Ok(())
     }
 }
 
+// Rejects transactions creating cells with a script hash type which is not activated at the
+// epoch. The scripts of the inputs were checked when the cells were created.
+pub struct ScriptHashTypeVerifier<'a> {
+    transaction: &'a TransactionView,
+    data1_enabled: bool,
+}
+
+impl<'a> ScriptHashTypeVerifier<'a> {
+    pub fn new(transaction: &'a TransactionView, data1_enabled: bool) -> Self {
+        ScriptHashTypeVerifier {
+            transaction,
+            data1_enabled,
+        }
+    }
+
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.data1_enabled {
+            return Ok(());
+        }
+        let data1: u8 = ScriptHashType::Data1.into();
+        let has_data1 = self.transaction.outputs().into_iter().any(|output| {
+            Into::<u8>::into(output.lock().hash_type()) == data1
+                || output
+                    .type_()
+                    .to_opt()
+                    .map(|script| Into::<u8>::into(script.hash_type()) == data1)
+                    .unwrap_or(false)
+        });
+        if has_data1 {
+            return Err((TransactionError::InvalidScriptHashType).into());
+        }
+        Ok(())
+    }
+}
+
 pub struct SizeVerifier<'a> {
     transaction: &'a TransactionView,
     block_bytes_limit: u64,
@@ -202,6 +303,9 @@ impl<'a> SizeVerifier<'a> {
 pub struct ScriptVerifier<'a, CS> {
     chain_store: &'a CS,
     resolved_transaction: &'a ResolvedTransaction,
+    script_cache: Option<Arc<ScriptVerifyCache>>,
+    native_crypto: bool,
+    exec: bool,
 }
 
 impl<'a, CS: ChainStore<'a>> ScriptVerifier<'a, CS> {
@@ -209,12 +313,83 @@ impl<'a, CS: ChainStore<'a>> ScriptVerifier<'a, CS> {
         ScriptVerifier {
             chain_store,
             resolved_transaction,
+            script_cache: None,
+            native_crypto: false,
+            exec: false,
+        }
+    }
+
+    pub fn set_script_cache(&mut self, script_cache: Arc<ScriptVerifyCache>) {
+        self.script_cache = Some(script_cache);
+    }
+
+    /// Enable the native blake2b and secp256k1 syscalls, see
+    /// `Consensus::native_crypto_syscalls_enabled`
+    pub fn set_native_crypto(&mut self, enabled: bool) {
+        self.native_crypto = enabled;
+    }
+
+    /// Enable the exec syscall for the scripts running on VM version 1, see
+    /// `Consensus::exec_syscall_enabled`
+    pub fn set_exec(&mut self, enabled: bool) {
+        self.exec = enabled;
+    }
+
+    fn configure<DL: DataLoader>(&self, verifier: &mut TransactionScriptsVerifier<DL>) {
+        if let Some(script_cache) = &self.script_cache {
+            verifier.set_script_cache(Arc::clone(script_cache));
         }
+        verifier.set_native_crypto(self.native_crypto);
+        verifier.set_exec(self.exec);
     }
 
     pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, Error> {
         let data_loader = DataLoaderWrapper::new(self.chain_store);
-        TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader).verify(max_cycles)
+        let mut verifier =
+            TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader);
+        self.configure(&mut verifier);
+        verifier.verify(max_cycles)
+    }
+
+    pub fn verify_groups(&self, max_cycles: Cycle) -> Result<Vec<ScriptGroupCycles>, Error> {
+        let data_loader = DataLoaderWrapper::new(self.chain_store);
+        let mut verifier =
+            TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader);
+        self.configure(&mut verifier);
+        verifier.verify_groups(max_cycles)
+    }
+
+    pub fn resumable_verify(
+        &self,
+        max_cycles: Cycle,
+        step_cycles: Cycle,
+    ) -> Result<VerifyResult, Error> {
+        let data_loader = DataLoaderWrapper::new(self.chain_store);
+        let mut verifier =
+            TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader);
+        self.configure(&mut verifier);
+        verifier.resumable_verify(max_cycles, step_cycles)
+    }
+
+    pub fn resume_from_snapshot(
+        &self,
+        snapshot: &TransactionSnapshot,
+        max_cycles: Cycle,
+        step_cycles: Cycle,
+    ) -> Result<VerifyResult, Error> {
+        let data_loader = DataLoaderWrapper::new(self.chain_store);
+        let mut verifier =
+            TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader);
+        self.configure(&mut verifier);
+        verifier.resume_from_snapshot(snapshot, max_cycles, step_cycles)
+    }
+
+    pub fn profile(&self, max_cycles: Cycle) -> Result<Vec<ScriptProfile>, Error> {
+        let data_loader = DataLoaderWrapper::new(self.chain_store);
+        let mut verifier =
+            TransactionScriptsVerifier::new(&self.resolved_transaction, &data_loader);
+        self.configure(&mut verifier);
+        verifier.profile(max_cycles)
     }
 }
 